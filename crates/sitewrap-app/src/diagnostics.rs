@@ -0,0 +1,73 @@
+use std::rc::Rc;
+
+use adw::prelude::*;
+use gtk4 as gtk;
+use gtk4::glib;
+use sitewrap_portal::{PortalCapabilities, PortalKind, Portals};
+
+/// Lists every portal with its interface version, or what stops working without it.
+/// The window opens immediately and fills in once the probe answers.
+pub fn open_diagnostics_window(parent: &impl IsA<gtk::Window>, portals: Rc<dyn Portals>) {
+    let window = adw::PreferencesWindow::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("Desktop Integration")
+        .default_width(560)
+        .default_height(520)
+        .search_enabled(false)
+        .build();
+    let page = adw::PreferencesPage::builder()
+        .title("Portals")
+        .icon_name("computer-symbolic")
+        .build();
+    let group = adw::PreferencesGroup::builder()
+        .title("xdg-desktop-portal")
+        .description("Checking available portals…")
+        .build();
+    page.add(&group);
+    window.add(&page);
+    window.present();
+
+    glib::MainContext::default().spawn_local(async move {
+        let capabilities = portals.capabilities().await;
+        fill_group(&group, &capabilities);
+    });
+}
+
+fn fill_group(group: &adw::PreferencesGroup, capabilities: &PortalCapabilities) {
+    let missing = capabilities.missing();
+    group.set_description(Some(&match missing.len() {
+        0 => "All portals are available.".to_string(),
+        1 => "1 portal is missing; the feature it provides is disabled.".to_string(),
+        n => format!("{n} portals are missing; the features they provide are disabled."),
+    }));
+
+    for kind in PortalKind::ALL {
+        let row = adw::ActionRow::builder()
+            .title(kind.label())
+            .subtitle(kind.interface())
+            .build();
+        match capabilities.version(kind) {
+            Some(version) => {
+                row.add_prefix(&gtk::Image::from_icon_name("emblem-ok-symbolic"));
+                row.add_suffix(
+                    &gtk::Label::builder()
+                        .label(format!("Version {version}"))
+                        .css_classes(["dim-label"])
+                        .build(),
+                );
+            }
+            None => {
+                row.add_prefix(&gtk::Image::from_icon_name("dialog-warning-symbolic"));
+                row.set_subtitle(&format!("Missing · affects: {}", kind.affects()));
+                row.add_suffix(
+                    &gtk::Label::builder()
+                        .label("Unavailable")
+                        .css_classes(["error"])
+                        .build(),
+                );
+            }
+        }
+        group.add(&row);
+    }
+}
@@ -0,0 +1,113 @@
+//! Re-checking app icons against their sites, from the manager and `sitewrap refresh-icons`.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use sitewrap_icons::{default_fetcher, refresh_icon_with, Fetcher, IconOrigin, RefreshOutcome};
+use sitewrap_model::{AppPaths, IconSource, LauncherStatus, WebAppDefinition, WebAppId};
+use sitewrap_portal::{export_launcher, update_launcher};
+use url::Url;
+
+use crate::{
+    manager::{launcher_descriptor_for, normalization_for},
+    AppContext,
+};
+
+/// How old an icon check may get before the manager repeats it on startup.
+pub(crate) const ICON_REFRESH_INTERVAL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// What `app`'s icon is refreshed from; `None` for imported files.
+pub(crate) fn icon_origin(app: &WebAppDefinition) -> Result<Option<IconOrigin>> {
+    Ok(match &app.icon_source {
+        IconSource::Site => Some(IconOrigin::Site {
+            start_url: Url::parse(&app.start_url)?,
+            app_name: app.name.clone(),
+        }),
+        IconSource::Url { url, maskable } => Some(IconOrigin::Pinned {
+            url: Url::parse(url).context("invalid icon URL")?,
+            maskable: *maskable,
+        }),
+        IconSource::Custom => None,
+    })
+}
+
+/// Re-checks `app`'s cached icon; blocking. Returns whether the image changed, in which
+/// case the launcher needs reinstalling.
+pub(crate) fn refresh_app_icon(app: &WebAppDefinition, paths: &AppPaths) -> Result<bool> {
+    refresh_app_icon_with(&default_fetcher(), app, paths)
+}
+
+/// [`refresh_app_icon`] with downloads going through `fetcher`.
+pub(crate) fn refresh_app_icon_with(
+    fetcher: &dyn Fetcher,
+    app: &WebAppDefinition,
+    paths: &AppPaths,
+) -> Result<bool> {
+    let Some(origin) = icon_origin(app)? else {
+        return Ok(false);
+    };
+    let outcome = refresh_icon_with(
+        fetcher,
+        &origin,
+        &app.icon_id,
+        &paths.icons_cache_dir(),
+        normalization_for(app.icon_style),
+    )?;
+    Ok(matches!(outcome, RefreshOutcome::Updated(_)))
+}
+
+/// `sitewrap refresh-icons`: refreshes every app's icon, or just `only`'s, and updates the
+/// launchers whose icon changed.
+pub(crate) fn refresh_icons(ctx: &AppContext, only: Option<WebAppId>) -> Result<()> {
+    let apps = match only {
+        Some(id) => vec![ctx.registry.load(id)?],
+        None => ctx.registry.list()?,
+    };
+    for mut app in apps {
+        if matches!(app.icon_source, IconSource::Custom) {
+            println!("{}: custom icon, skipped", app.name);
+            continue;
+        }
+        match refresh_app_icon(&app, &ctx.paths) {
+            Ok(false) => println!("{}: unchanged", app.name),
+            Ok(true) => {
+                let status = reinstall_launcher(&app, &ctx.paths);
+                match &status {
+                    Some(LauncherStatus::Failed { error }) => {
+                        println!(
+                            "{}: icon updated, launcher update failed: {error}",
+                            app.name
+                        )
+                    }
+                    _ => println!("{}: icon updated", app.name),
+                }
+                if let Some(status) = status {
+                    app.launcher = status;
+                    ctx.registry.save(&app)?;
+                }
+            }
+            Err(err) => println!("{}: refresh failed: {err:#}", app.name),
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites the launcher the way it was installed. `None` when there is none to update.
+fn reinstall_launcher(app: &WebAppDefinition, paths: &AppPaths) -> Option<LauncherStatus> {
+    let descriptor = launcher_descriptor_for(app, paths);
+    let result = match &app.launcher {
+        LauncherStatus::Installed | LauncherStatus::Unknown => {
+            update_launcher(&descriptor).map(|()| LauncherStatus::Installed)
+        }
+        LauncherStatus::Exported { path } => {
+            let dir = path.parent().and_then(|p| p.parent())?;
+            export_launcher(&descriptor, dir).map(|exported| LauncherStatus::Exported {
+                path: exported.desktop_file,
+            })
+        }
+        LauncherStatus::Failed { .. } => return None,
+    };
+    Some(result.unwrap_or_else(|err| LauncherStatus::Failed {
+        error: format!("{err:#}"),
+    }))
+}
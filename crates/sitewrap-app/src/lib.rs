@@ -2,13 +2,19 @@ use std::rc::Rc;
 
 use adw::prelude::*;
 use adw::Application;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use gtk4 as gtk;
-use gtk4::glib;
+use gtk4::{gio, glib};
 use sitewrap_engine as engine;
-use sitewrap_model::{AppPaths, AppRegistry, PermissionRepository, WebAppId};
-use tracing::error;
+use sitewrap_model::{
+    AppPaths, AppRegistry, AuditLog, PermissionRepository, Trash, WebAppDefinition, WebAppId,
+};
+use sitewrap_portal::{BackgroundRequest, DesktopPortals, Portals};
+use tracing::{error, info};
+use url::Url;
 
+mod diagnostics;
+mod icon_refresh;
 mod manager;
 mod permissions_ui;
 mod resources;
@@ -16,10 +22,24 @@ mod shell;
 
 pub const APP_ID: &str = "xyz.andriishafar.Sitewrap";
 
+/// Application id, and so window class, of the shell running `id`. Launchers name it
+/// in `StartupWMClass`, so the desktop groups the shell's windows with its launcher.
+pub(crate) fn shell_app_id(id: WebAppId) -> String {
+    format!("{APP_ID}.WebApp_{}", id.simple())
+}
+
 #[derive(Clone, Debug)]
 pub enum AppMode {
     Manager,
-    Shell(WebAppId),
+    /// Run a single web app, optionally opening a deep link instead of its start URL.
+    Shell {
+        id: WebAppId,
+        url: Option<String>,
+    },
+    /// Open a URI from another application in whichever web app handles it.
+    Open(String),
+    /// Re-check icons against their sites without a window; all apps when `None`.
+    RefreshIcons(Option<WebAppId>),
 }
 
 #[derive(Clone)]
@@ -27,6 +47,9 @@ struct AppContext {
     paths: AppPaths,
     registry: AppRegistry,
     permissions: PermissionRepository,
+    audit: AuditLog,
+    trash: Trash,
+    portals: Rc<dyn Portals>,
 }
 
 impl AppContext {
@@ -35,23 +58,63 @@ impl AppContext {
         Ok(Self {
             registry: AppRegistry::new(paths.clone()),
             permissions: PermissionRepository::new(paths.clone()),
+            audit: AuditLog::new(paths.clone()),
+            trash: Trash::new(paths.clone()),
+            portals: Rc::new(DesktopPortals),
             paths,
         })
     }
 }
 
 pub fn run(mode: AppMode) -> Result<()> {
+    let ctx = Rc::new(AppContext::new()?);
+    let mode = match mode {
+        AppMode::Open(uri) => match resolve_open(&ctx, &uri)? {
+            Some(mode) => mode,
+            None => {
+                info!(target: "app", uri, "no web app handles URI; passing it to the desktop");
+                return sitewrap_portal::open_uri(&uri);
+            }
+        },
+        AppMode::RefreshIcons(only) => return icon_refresh::refresh_icons(&ctx, only),
+        mode => mode,
+    };
+
     resources::register()?;
     engine::init()?;
 
-    if !sitewrap_portal::is_supported() {
-        sitewrap_portal::warn_if_stubbed();
-    }
-
-    let app = Application::builder().application_id(APP_ID).build();
+    let application_id = match &mode {
+        AppMode::Shell { id, .. } => shell_app_id(*id),
+        _ => APP_ID.to_string(),
+    };
+    // X11 takes the window class from the program name rather than the application id.
+    glib::set_prgname(Some(application_id.as_str()));
+    // Later launches of a running shell arrive as command lines so their deep link
+    // reaches the existing window instead of only raising it.
+    let app = Application::builder()
+        .application_id(application_id.as_str())
+        .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE)
+        .build();
 
-    let ctx = Rc::new(AppContext::new()?);
-    let mode_for_activate = mode.clone();
+    {
+        let ctx = ctx.clone();
+        let mode = mode.clone();
+        app.connect_command_line(move |app, command_line| {
+            let url = command_line
+                .arguments()
+                .get(1)
+                .and_then(|arg| arg.to_str())
+                .map(str::to_string);
+            match on_activate(app, ctx.clone(), mode_with_url(&mode, url)) {
+                Ok(()) => 0,
+                Err(err) => {
+                    error!(target: "app", "failed to handle command line: {err:?}");
+                    1
+                }
+            }
+        });
+    }
+    let mode_for_activate = mode_with_url(&mode, None);
     app.connect_activate(move |app| {
         if let Err(err) = on_activate(app, ctx.clone(), mode_for_activate.clone()) {
             error!(target: "app", "failed to activate application: {err:?}");
@@ -63,18 +126,90 @@ pub fn run(mode: AppMode) -> Result<()> {
         glib::ControlFlow::Continue
     });
 
-    app.run();
+    // Only the deep link is forwarded; the rest of our arguments are not GApplication's.
+    let mut args = vec![std::env::args().next().unwrap_or_else(|| "sitewrap".into())];
+    if let AppMode::Shell { url: Some(url), .. } = &mode {
+        args.push(url.clone());
+    }
+    app.run_with_args(&args);
     engine::shutdown();
     Ok(())
 }
 
+/// Picks the shell to run for `sitewrap open <uri>`, matching apps by handler and scope.
+fn resolve_open(ctx: &AppContext, uri: &str) -> Result<Option<AppMode>> {
+    let parsed = Url::parse(uri).with_context(|| format!("invalid URI: {uri}"))?;
+    let apps = ctx.registry.list()?;
+    Ok(
+        sitewrap_model::find_app_for_uri(&apps, &parsed).map(|(app, target)| AppMode::Shell {
+            id: app.id,
+            url: Some(target.to_string()),
+        }),
+    )
+}
+
+/// `mode` with the deep link of the launch being handled rather than the first one.
+fn mode_with_url(mode: &AppMode, url: Option<String>) -> AppMode {
+    match mode {
+        AppMode::Shell { id, .. } => AppMode::Shell { id: *id, url },
+        mode => mode.clone(),
+    }
+}
+
 fn on_activate(app: &Application, ctx: Rc<AppContext>, mode: AppMode) -> Result<()> {
     match mode {
         AppMode::Manager => manager::show(app, ctx),
-        AppMode::Shell(id) => shell::show(app, ctx, id),
+        AppMode::Shell { id, url } => shell::show(app, ctx, id, url.as_deref()),
+        AppMode::Open(uri) => anyhow::bail!("unresolved open request for {uri}"),
+        AppMode::RefreshIcons(_) => anyhow::bail!("icon refresh runs without a window"),
+    }
+}
+
+/// What to ask the Background portal for `app`; the autostart entry reopens its shell.
+fn background_request_for(app: &WebAppDefinition) -> BackgroundRequest {
+    BackgroundRequest {
+        reason: format!("{} keeps running to deliver notifications", app.name),
+        autostart: app.behavior.start_at_login,
+        commandline: vec![
+            "sitewrap".to_string(),
+            "--shell".to_string(),
+            app.id.to_string(),
+        ],
     }
 }
 
 fn builder_from_resource(path: &str) -> gtk::Builder {
     gtk::Builder::from_resource(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_launches_bring_their_own_deep_link() {
+        let id = WebAppId::new_v4();
+        let first = AppMode::Shell {
+            id,
+            url: Some("https://mail.example/inbox".into()),
+        };
+
+        // A jump-list action on the running shell carries its `--url` on the command line.
+        let action = mode_with_url(&first, Some("https://mail.example/compose".into()));
+        assert!(matches!(
+            action,
+            AppMode::Shell { id: action_id, url: Some(url) }
+                if action_id == id && url == "https://mail.example/compose"
+        ));
+
+        // Plain activation only raises the window; it must not replay the first link.
+        assert!(matches!(
+            mode_with_url(&first, None),
+            AppMode::Shell { url: None, .. }
+        ));
+        assert!(matches!(
+            mode_with_url(&AppMode::Manager, Some("ignored".into())),
+            AppMode::Manager
+        ));
+    }
+}
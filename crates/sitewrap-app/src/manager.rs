@@ -1,45 +1,205 @@
-use std::{cell::RefCell, rc::Rc, thread};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
 
 use adw::prelude::*;
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use gtk4 as gtk;
 use gtk4::gdk;
 use gtk4::gio;
 use gtk4::glib;
-use sitewrap_icons::fetch_and_cache_icon;
-use sitewrap_model::{normalize_url, AppPaths, WebAppDefinition, WebAppId};
-use sitewrap_portal::{install_launcher, remove_launcher, warn_if_stubbed, LauncherDescriptor};
-use time::OffsetDateTime;
+use sitewrap_icons::{
+    cache_custom_icon, cache_icon_for_site_with, cache_icon_from_url, default_fetcher,
+    fetch_and_cache_icon, fetch_manifest, fetch_site_info_with, generate_fallback_icon,
+    import_custom_icon, needs_refresh, pick_accent, preview_candidates, scalable_icon_path,
+    Cancellable, IconPreview, IconRecord, IconResult, Normalization, Rgb, SiteInfo, WebManifest,
+    ICON_SIZES,
+};
+use sitewrap_model::{
+    all_tags, normalize_url, parse_tags, AppPaths, AppShortcut, AuditAction, AuditEntry, AuditLog,
+    ColorSchemePreference, IconSource, IconStyle, LauncherStatus, LinkHandlers, ManagerSettings,
+    PermissionKind, PermissionPreset, PermissionState, SchemeHandler, SortMode, WebAppDefinition,
+    WebAppId,
+};
+use sitewrap_portal::{
+    export_launcher, remove_exported_launcher, DesktopAction, LauncherDescriptor, PickFileRequest,
+    PortalKind, Portals,
+};
+use time::{Duration, OffsetDateTime};
 use url::Url;
 
-use crate::{builder_from_resource, permissions_ui::*, AppContext};
+use crate::{
+    background_request_for, builder_from_resource,
+    diagnostics::open_diagnostics_window,
+    icon_refresh::{refresh_app_icon, refresh_app_icon_with, ICON_REFRESH_INTERVAL},
+    permissions_ui::*,
+    shell_app_id, AppContext,
+};
 
 const MANAGER_UI: &str = "/xyz/andriishafar/sitewrap/ui/manager_window.ui";
+/// How far back the privacy dashboard looks.
+const PRIVACY_WINDOW_DAYS: i64 = 30;
+/// Recent events listed under each origin in the privacy dashboard.
+const PRIVACY_RECENT_EVENTS: usize = 10;
+/// Pause after the last edit in the create dialog before the site is looked up.
+const CREATE_LOOKUP_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+/// Icon id the create dialog renders under before the app has its own.
+const DRAFT_ICON_ID: &str = "draft";
+/// Prefix of the create dialog's staging directories in the icon cache.
+const DRAFT_DIR_PREFIX: &str = ".draft-";
+/// Site icons offered in the icon picker.
+const ICON_PREVIEW_LIMIT: usize = 8;
+/// Image types accepted as a custom icon.
+const ICON_FILE_TYPES: [&str; 6] = [
+    "image/png",
+    "image/svg+xml",
+    "image/x-icon",
+    "image/vnd.microsoft.icon",
+    "image/jpeg",
+    "image/webp",
+];
+
+/// The two entries of each row in a pair editor (shortcuts, scheme handlers), with the row container.
+type PairRows = Rc<RefCell<Vec<(gtk::Box, gtk::Entry, gtk::Entry)>>>;
+
+/// Detail page settings besides the name and URL; see [`DetailForm::extras`].
+struct EditExtras {
+    tags: Vec<String>,
+    shortcuts: Vec<AppShortcut>,
+    link_handlers: LinkHandlers,
+    open_external_links: bool,
+    show_navigation: bool,
+    run_in_background: bool,
+    start_at_login: bool,
+    color_scheme: ColorSchemePreference,
+    /// `None` after "Automatic", so the accent is derived from the site again.
+    accent_color: Option<String>,
+    icon_style: IconStyle,
+}
+
+/// What the background fetch learned about a site besides its icon.
+#[derive(Debug, Default)]
+struct SiteMetadata {
+    shortcuts: Vec<AppShortcut>,
+    accent_color: Option<String>,
+}
 
 #[derive(Clone)]
 struct Handlers {
     ctx: Rc<AppContext>,
     apps: Rc<RefCell<Vec<WebAppDefinition>>>,
     list: gtk::ListBox,
+    /// Takes the list's place when [`ManagerSettings::grid`] is set.
+    grid: gtk::FlowBox,
     search_entry: gtk::SearchEntry,
     window: adw::ApplicationWindow,
     main_stack: gtk::Stack,
+    settings: Rc<RefCell<ManagerSettings>>,
+    /// Hidden while no app has a tag.
+    tag_bar: gtk::Box,
+    tag_filter: gtk::DropDown,
+    /// The tags offered by `tag_filter`, after its "All Apps" item.
+    tags: Rc<RefCell<Vec<String>>>,
+    /// Set while `tag_filter` is updated from code rather than by the user.
+    syncing_tags: Rc<Cell<bool>>,
+    bulk: BulkBar,
+    toasts: adw::ToastOverlay,
+    split_view: adw::NavigationSplitView,
+    detail_page: adw::NavigationPage,
+    /// Shown in `detail_page` while no app is selected.
+    detail_placeholder: gtk::Widget,
+    detail: Rc<RefCell<Option<Rc<DetailView>>>>,
+}
+
+/// Selection mode, where apps are ticked in the sidebar and acted on together.
+#[derive(Clone)]
+struct BulkBar {
+    button: gtk::ToggleButton,
+    bar: gtk::ActionBar,
+    label: gtk::Label,
+    actions_button: gtk::MenuButton,
+    /// The ticked apps; `None` outside selection mode.
+    selected: Rc<RefCell<Option<HashSet<WebAppId>>>>,
 }
 
 fn desktop_id_for(app: &WebAppDefinition) -> String {
     format!("{}.desktop", app.icon_id)
 }
 
-fn launcher_descriptor_for(app: &WebAppDefinition, paths: &AppPaths) -> LauncherDescriptor {
+pub(crate) fn launcher_descriptor_for(
+    app: &WebAppDefinition,
+    paths: &AppPaths,
+) -> LauncherDescriptor {
     let icon_path = paths
         .icons_cache_dir()
         .join(format!("{}-128x128.png", app.icon_id));
+    let scalable_icon = scalable_icon_path(&paths.icons_cache_dir(), &app.icon_id);
+    let mut exec = vec![
+        "sitewrap".to_string(),
+        "--shell".to_string(),
+        app.id.to_string(),
+    ];
+    if !app.link_handlers.is_empty() {
+        // The desktop passes the clicked link as a trailing argument.
+        exec.push("%u".to_string());
+    }
     LauncherDescriptor {
         desktop_id: desktop_id_for(app),
+        mime_types: app.link_handlers.mime_types(),
         name: app.name.clone(),
-        exec: format!("sitewrap --shell {}", app.id),
+        exec,
         icon_name: app.icon_id.clone(),
         icon_file: icon_path.exists().then_some(icon_path),
+        scalable_icon: scalable_icon.exists().then_some(scalable_icon),
+        icon_set: ICON_SIZES
+            .iter()
+            .map(|&size| {
+                let path = paths
+                    .icons_cache_dir()
+                    .join(format!("{}-{size}x{size}.png", app.icon_id));
+                (size, path)
+            })
+            .filter(|(_, path)| path.exists())
+            .collect(),
+        comment: Some(format!("Web app for {}", app.primary_origin)),
+        startup_wm_class: Some(shell_app_id(app.id)),
+        actions: app
+            .shortcuts
+            .iter()
+            .enumerate()
+            .map(|(index, shortcut)| {
+                DesktopAction::new(
+                    &format!("shortcut-{index}"),
+                    shortcut.label.as_str(),
+                    [
+                        "sitewrap".to_string(),
+                        "--shell".to_string(),
+                        app.id.to_string(),
+                        "--url".to_string(),
+                        shortcut.url.clone(),
+                    ],
+                )
+            })
+            .collect(),
+        keywords: Url::parse(&app.start_url)
+            .ok()
+            .and_then(|url| {
+                url.host_str()
+                    .map(|h| h.trim_start_matches("www.").to_string())
+            })
+            .into_iter()
+            .collect(),
+        ..Default::default()
     }
 }
 
@@ -51,6 +211,15 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>) -> Result<()> {
     let list: gtk::ListBox = builder
         .object("apps_list")
         .context("apps_list missing in blueprint")?;
+    let grid: gtk::FlowBox = builder
+        .object("apps_grid")
+        .context("apps_grid missing in blueprint")?;
+    let tag_bar: gtk::Box = builder
+        .object("tag_bar")
+        .context("tag_bar missing in blueprint")?;
+    let tag_filter: gtk::DropDown = builder
+        .object("tag_filter")
+        .context("tag_filter missing in blueprint")?;
     let create_btn: gtk::Button = builder
         .object("create_button")
         .context("create_button missing in blueprint")?;
@@ -60,33 +229,67 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>) -> Result<()> {
     let main_stack: gtk::Stack = builder
         .object("main_stack")
         .context("main_stack missing in blueprint")?;
+    let privacy_btn: gtk::Button = builder
+        .object("privacy_button")
+        .context("privacy_button missing in blueprint")?;
+    let diagnostics_btn: gtk::Button = builder
+        .object("diagnostics_button")
+        .context("diagnostics_button missing in blueprint")?;
+    let split_view: adw::NavigationSplitView = builder
+        .object("split_view")
+        .context("split_view missing in blueprint")?;
+    let detail_page: adw::NavigationPage = builder
+        .object("detail_page")
+        .context("detail_page missing in blueprint")?;
+    let detail_placeholder = detail_page
+        .child()
+        .context("detail_page has no placeholder")?;
+    let toasts: adw::ToastOverlay = builder
+        .object("toast_overlay")
+        .context("toast_overlay missing in blueprint")?;
+    let select_btn: gtk::ToggleButton = builder
+        .object("select_button")
+        .context("select_button missing in blueprint")?;
+    let select_all_btn: gtk::Button = builder
+        .object("select_all_button")
+        .context("select_all_button missing in blueprint")?;
+    let bulk = BulkBar {
+        button: select_btn.clone(),
+        bar: builder
+            .object("bulk_bar")
+            .context("bulk_bar missing in blueprint")?,
+        label: builder
+            .object("selection_label")
+            .context("selection_label missing in blueprint")?,
+        actions_button: builder
+            .object("bulk_actions_button")
+            .context("bulk_actions_button missing in blueprint")?,
+        selected: Rc::new(RefCell::new(None)),
+    };
 
     window.set_application(Some(app));
 
     let apps = Rc::new(RefCell::new(ctx.registry.list()?));
 
-    if !sitewrap_portal::is_supported() {
-        let dialog = adw::MessageDialog::builder()
-            .transient_for(&window)
-            .heading("Desktop integration unavailable")
-            .body("xdg-desktop-portal is not available; launchers and notifications will be disabled.")
-            .build();
-        dialog.add_response("close", "OK");
-        dialog.set_default_response(Some("close"));
-        dialog.set_close_response("close");
-        dialog.connect_response(None, |d, _| d.close());
-        dialog.present();
-    } else {
-        warn_if_stubbed();
-    }
-
     let handlers = Handlers {
         ctx: Rc::clone(&ctx),
         apps: Rc::clone(&apps),
         list: list.clone(),
+        grid: grid.clone(),
         search_entry: search_entry.clone(),
         window: window.clone(),
         main_stack: main_stack.clone(),
+        settings: Rc::new(RefCell::new(ManagerSettings::load(&ctx.paths))),
+        tag_bar,
+        tag_filter: tag_filter.clone(),
+        tags: Rc::new(RefCell::new(Vec::new())),
+        syncing_tags: Rc::new(Cell::new(false)),
+        bulk,
+        toasts,
+        split_view,
+        detail_page,
+        detail_placeholder,
+        detail: Rc::new(RefCell::new(None)),
     };
 
     let refresh_list = {
@@ -96,8 +299,15 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>) -> Result<()> {
         move |query: &str| refresh_listbox(&list, &apps.borrow(), query, &handlers)
     };
 
+    tag_filter.set_model(Some(&gtk::StringList::new(&["All Apps"])));
+    sync_tag_filter(&handlers);
     refresh_list("");
 
+    let actions = gio::SimpleActionGroup::new();
+    install_view_actions(&handlers, &actions);
+    install_bulk_actions(&handlers, &actions);
+    window.insert_action_group("manager", Some(&actions));
+
     {
         let window = window.clone();
         let handlers = handlers.clone();
@@ -108,12 +318,153 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>) -> Result<()> {
         }));
     }
 
+    {
+        let handlers = handlers.clone();
+        privacy_btn.connect_clicked(move |_| {
+            if let Err(err) = open_privacy_dashboard(&handlers) {
+                tracing::error!(target: "ui", "failed to open privacy dashboard: {err:?}");
+            }
+        });
+    }
+
+    {
+        let handlers = handlers.clone();
+        diagnostics_btn.connect_clicked(move |_| {
+            open_diagnostics_window(&handlers.window, Rc::clone(&handlers.ctx.portals));
+        });
+    }
+
     {
         let refresh = refresh_list.clone();
         search_entry.connect_search_changed(move |entry| refresh(&entry.text()));
     }
 
+    {
+        let handlers = handlers.clone();
+        list.connect_row_selected(move |_, row| {
+            if let Some(app_id) = row.and_then(row_app_id) {
+                show_detail(&handlers, app_id);
+            }
+        });
+    }
+
+    {
+        // On narrow windows only one pane shows; activating a row opens its page.
+        let handlers = handlers.clone();
+        list.connect_row_activated(move |_, row| {
+            // In selection mode the row ticks its check button instead.
+            if handlers.bulk.selected.borrow().is_some() {
+                return;
+            }
+            if let Some(app_id) = row_app_id(row) {
+                show_detail(&handlers, app_id);
+                handlers.split_view.set_show_content(true);
+            }
+        });
+    }
+
+    {
+        let handlers = handlers.clone();
+        grid.connect_selected_children_changed(move |grid| {
+            if let Some(app_id) = grid.selected_children().first().and_then(row_app_id) {
+                show_detail(&handlers, app_id);
+            }
+        });
+    }
+
+    {
+        let handlers = handlers.clone();
+        grid.connect_child_activated(move |_, child| {
+            if handlers.bulk.selected.borrow().is_some() {
+                if let Some(check) = tile_check(child) {
+                    check.set_active(!check.is_active());
+                }
+                return;
+            }
+            if let Some(app_id) = row_app_id(child) {
+                show_detail(&handlers, app_id);
+                handlers.split_view.set_show_content(true);
+            }
+        });
+    }
+
+    {
+        let handlers = handlers.clone();
+        select_btn.connect_toggled(move |button| set_selection_mode(&handlers, button.is_active()));
+    }
+
+    {
+        let handlers = handlers.clone();
+        select_all_btn.connect_clicked(move |_| {
+            let query = handlers.search_entry.text();
+            let visible = handlers
+                .settings
+                .borrow()
+                .visible_apps(&handlers.apps.borrow(), &query);
+            if let Some(selected) = handlers.bulk.selected.borrow_mut().as_mut() {
+                selected.extend(visible.iter().map(|app| app.id));
+            }
+            refresh_current(&handlers);
+        });
+    }
+
+    {
+        let handlers = handlers.clone();
+        tag_filter.connect_selected_notify(move |dropdown| {
+            if handlers.syncing_tags.get() {
+                return;
+            }
+            let tag = (dropdown.selected() as usize)
+                .checked_sub(1)
+                .and_then(|index| handlers.tags.borrow().get(index).cloned());
+            handlers.settings.borrow_mut().tag = tag;
+            save_settings(&handlers);
+            refresh_current(&handlers);
+        });
+    }
+
+    {
+        let handlers = handlers.clone();
+        window.connect_is_active_notify(move |window| {
+            if window.is_active() {
+                refresh_detail_permissions(&handlers);
+            }
+        });
+    }
+
+    {
+        let handlers = handlers.clone();
+        let detail_page = handlers.detail_page.clone();
+        detail_page.connect_shown(move |_| refresh_detail_permissions(&handlers));
+    }
+
+    purge_expired_trash(&handlers);
+    remove_draft_dirs(&handlers.ctx.paths);
     window.present();
+    refresh_stale_icons(&handlers);
+
+    // Probe portals off the main loop; the warning appears once the answer is in.
+    glib::MainContext::default().spawn_local(async move {
+        let capabilities = handlers.ctx.portals.capabilities().await;
+        let missing = capabilities.missing();
+        if missing.is_empty() {
+            return;
+        }
+        tracing::warn!(target: "portal", ?missing, "some desktop portals are unavailable");
+        diagnostics_btn.add_css_class("warning");
+        diagnostics_btn.set_tooltip_text(Some(&format!(
+            "Desktop Integration: {} portal{} missing",
+            missing.len(),
+            if missing.len() == 1 { "" } else { "s" }
+        )));
+        if !capabilities.has(PortalKind::DynamicLauncher) {
+            show_message(
+                &handlers,
+                "Launcher installation unavailable",
+                "The DynamicLauncher portal is not available. Launchers can still be exported manually from each app's Desktop Launcher row.",
+            );
+        }
+    });
     Ok(())
 }
 
@@ -123,8 +474,11 @@ fn refresh_listbox(
     query: &str,
     handlers: &Handlers,
 ) {
+    let settings = handlers.settings.borrow().clone();
     if apps.is_empty() {
         handlers.main_stack.set_visible_child_name("empty");
+    } else if settings.grid {
+        handlers.main_stack.set_visible_child_name("grid");
     } else {
         handlers.main_stack.set_visible_child_name("list");
     }
@@ -132,115 +486,201 @@ fn refresh_listbox(
     while let Some(child) = list.first_child() {
         list.remove(&child);
     }
-    let needle = query.trim().to_ascii_lowercase();
-    let mut sorted = apps.to_vec();
-    sorted.sort_by(|a, b| {
-        a.name
-            .to_ascii_lowercase()
-            .cmp(&b.name.to_ascii_lowercase())
-    });
-
-    for app in sorted.iter() {
-        let matches = needle.is_empty()
-            || app.name.to_ascii_lowercase().contains(&needle)
-            || app.start_url.to_ascii_lowercase().contains(&needle)
-            || app.primary_origin.to_ascii_lowercase().contains(&needle)
-            || (app.behavior.open_external_links && "external".contains(&needle))
-            || (app.behavior.show_navigation && "navigation".contains(&needle));
-        if !matches {
+    while let Some(child) = handlers.grid.first_child() {
+        handlers.grid.remove(&child);
+    }
+    let selected = handlers
+        .detail
+        .borrow()
+        .as_ref()
+        .map(|detail| detail.app_id);
+    if let Some(ticked) = handlers.bulk.selected.borrow_mut().as_mut() {
+        ticked.retain(|id| apps.iter().any(|app| app.id == *id));
+    }
+    update_bulk_bar(handlers);
+
+    // Only the visible view is filled; switching views refreshes again.
+    for app in settings.visible_apps(apps, query).iter() {
+        if settings.grid {
+            let tile = make_tile(app, handlers);
+            handlers.grid.insert(&tile, -1);
+            if selected == Some(app.id) {
+                handlers.grid.select_child(&tile);
+            }
             continue;
         }
         let row = make_row(app, handlers);
         list.append(&row);
+        if selected == Some(app.id) {
+            // Keeps the shown app highlighted; its page is left as it is.
+            list.select_row(row.downcast_ref::<gtk::ListBoxRow>());
+        }
     }
     list.show();
 }
 
+/// Offers the tags in use in the tag filter, which is hidden while there are none. A
+/// selected tag that no app has any more falls back to "All Apps".
+fn sync_tag_filter(handlers: &Handlers) {
+    let tags = all_tags(&handlers.apps.borrow());
+    let mut settings = handlers.settings.borrow_mut();
+    let position = settings.tag.as_ref().and_then(|tag| {
+        tags.iter()
+            .position(|known| known.to_lowercase() == tag.to_lowercase())
+    });
+    if position.is_none() {
+        settings.tag = None;
+    }
+    let selected = position.map_or(0, |index| index + 1);
+    drop(settings);
+
+    handlers.syncing_tags.set(true);
+    if *handlers.tags.borrow() != tags {
+        let mut labels = vec!["All Apps"];
+        labels.extend(tags.iter().map(String::as_str));
+        handlers
+            .tag_filter
+            .set_model(Some(&gtk::StringList::new(&labels)));
+        handlers.tags.replace(tags);
+    }
+    handlers.tag_filter.set_selected(selected as u32);
+    handlers.syncing_tags.set(false);
+    handlers
+        .tag_bar
+        .set_visible(!handlers.tags.borrow().is_empty());
+}
+
+/// Adds the "manager.sort", "manager.grid" and "manager.show-trash" actions behind the
+/// view menu.
+fn install_view_actions(handlers: &Handlers, actions: &gio::SimpleActionGroup) {
+    let settings = handlers.settings.borrow().clone();
+
+    let sort = gio::SimpleAction::new_stateful(
+        "sort",
+        Some(glib::VariantTy::STRING),
+        &settings.sort.key().to_variant(),
+    );
+    sort.connect_activate(glib::clone!(@strong handlers => move |action, target| {
+        let Some(mode) = target
+            .and_then(|target| target.str())
+            .and_then(SortMode::from_key)
+        else {
+            return;
+        };
+        action.set_state(&mode.key().to_variant());
+        handlers.settings.borrow_mut().sort = mode;
+        save_settings(&handlers);
+        refresh_current(&handlers);
+    }));
+    actions.add_action(&sort);
+
+    let grid = gio::SimpleAction::new_stateful("grid", None, &settings.grid.to_variant());
+    grid.connect_activate(glib::clone!(@strong handlers => move |action, _| {
+        let grid = !handlers.settings.borrow().grid;
+        action.set_state(&grid.to_variant());
+        handlers.settings.borrow_mut().grid = grid;
+        save_settings(&handlers);
+        refresh_current(&handlers);
+    }));
+    actions.add_action(&grid);
+
+    let show_trash = gio::SimpleAction::new("show-trash", None);
+    show_trash.connect_activate(glib::clone!(@strong handlers => move |_, _| {
+        open_trash_window(&handlers);
+    }));
+    actions.add_action(&show_trash);
+}
+
+fn save_settings(handlers: &Handlers) {
+    if let Err(err) = handlers.settings.borrow().save(&handlers.ctx.paths) {
+        tracing::warn!(target: "ui", "failed to save manager settings: {err:?}");
+    }
+}
+
+/// A sidebar row for `app`; its widget name is the app id, see [`row_app_id`].
 fn make_row(app: &WebAppDefinition, handlers: &Handlers) -> gtk::Widget {
-    let row = adw::ExpanderRow::builder()
+    let row = adw::ActionRow::builder()
         .title(&app.name)
         .subtitle(&app.start_url)
+        .subtitle_lines(1)
+        .activatable(true)
         .build();
+    row.set_widget_name(&app.id.to_string());
 
+    if let Some(check) = bulk_check(app, handlers) {
+        row.add_prefix(&check);
+        row.set_activatable_widget(Some(&check));
+    }
     let icon = icon_widget(app, &handlers.ctx.paths);
     row.add_prefix(&icon);
 
-    let launch_btn = gtk::Button::builder()
-        .label("Launch")
-        .valign(gtk::Align::Center)
-        .css_classes(["suggested-action"])
-        .build();
-
-    let app_launch = app.clone();
-    let handlers_launch = handlers.clone();
-    launch_btn.connect_clicked(move |_| {
-        if let Err(err) = run_launch(&handlers_launch, &app_launch) {
-            tracing::error!(target: "ui", "launch failed: {err:?}");
-        }
-    });
+    if let LauncherStatus::Failed { error } = &app.launcher {
+        let warning = gtk::Image::builder()
+            .icon_name("dialog-warning-symbolic")
+            .tooltip_text(format!("Launcher not installed: {error}"))
+            .valign(gtk::Align::Center)
+            .build();
+        row.add_suffix(&warning);
+    }
 
-    row.add_action(&launch_btn);
+    row.upcast()
+}
 
-    let last_launched_row = adw::ActionRow::builder()
-        .title("Last Launched")
-        .subtitle(format_last_launched(app))
+/// A grid tile for `app`; like a sidebar row, its widget name is the app id.
+fn make_tile(app: &WebAppDefinition, handlers: &Handlers) -> gtk::FlowBoxChild {
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .margin_top(6)
+        .margin_bottom(6)
         .build();
-    row.add_row(&last_launched_row);
+    if let Some(check) = bulk_check(app, handlers) {
+        check.set_halign(gtk::Align::Center);
+        content.append(&check);
+    }
+    content.append(&icon_widget(app, &handlers.ctx.paths));
+    content.append(
+        &gtk::Label::builder()
+            .label(&app.name)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .max_width_chars(12)
+            .build(),
+    );
 
-    let edit_row = adw::ActionRow::builder()
-        .title("Edit Details")
-        .activatable(true)
+    let tile = gtk::FlowBoxChild::builder()
+        .child(&content)
+        .tooltip_text(&app.start_url)
         .build();
-    edit_row.add_suffix(&gtk::Image::from_icon_name("document-edit-symbolic"));
-    let app_edit = app.clone();
-    let handlers_edit = handlers.clone();
-    edit_row.connect_activated(move |_| {
-        if let Err(err) = open_edit_window(&handlers_edit, &app_edit) {
-            tracing::error!(target: "ui", "edit failed: {err:?}");
-        }
-    });
-    row.add_row(&edit_row);
+    tile.set_widget_name(&app.id.to_string());
+    tile
+}
 
-    let permissions_row = adw::ActionRow::builder()
-        .title("Permissions")
-        .activatable(true)
+/// In selection mode, the check button that ticks `app`; see [`BulkBar`].
+fn bulk_check(app: &WebAppDefinition, handlers: &Handlers) -> Option<gtk::CheckButton> {
+    let active = handlers.bulk.selected.borrow().as_ref()?.contains(&app.id);
+    let check = gtk::CheckButton::builder()
+        .active(active)
+        .valign(gtk::Align::Center)
+        .tooltip_text(format!("Select {}", app.name))
         .build();
-    permissions_row.add_suffix(&gtk::Image::from_icon_name("user-info-symbolic"));
-    let app_permissions = app.clone();
-    let handlers_permissions = handlers.clone();
-    permissions_row.connect_activated(move |_| {
-        if let Err(err) = open_permissions_window_manager(&handlers_permissions, &app_permissions) {
-            tracing::error!(target: "ui", "permissions failed: {err:?}");
+    let handlers = handlers.clone();
+    let app_id = app.id;
+    check.connect_toggled(move |check| {
+        if let Some(selected) = handlers.bulk.selected.borrow_mut().as_mut() {
+            if check.is_active() {
+                selected.insert(app_id);
+            } else {
+                selected.remove(&app_id);
+            }
         }
+        update_bulk_bar(&handlers);
     });
-    row.add_row(&permissions_row);
-
-    let reset_row = adw::ActionRow::builder()
-        .title("Reset Data")
-        .activatable(true)
-        .build();
-    reset_row.add_suffix(&gtk::Image::from_icon_name("edit-clear-all-symbolic"));
-    let app_reset = app.clone();
-    let handlers_reset = handlers.clone();
-    reset_row.connect_activated(move |_| {
-        confirm_reset(&handlers_reset, &app_reset);
-    });
-    row.add_row(&reset_row);
-
-    let remove_row = adw::ActionRow::builder()
-        .title("Remove App")
-        .activatable(true)
-        .css_classes(["destructive-action"])
-        .build();
-    remove_row.add_suffix(&gtk::Image::from_icon_name("user-trash-symbolic"));
-    let app_remove = app.clone();
-    let handlers_remove = handlers.clone();
-    remove_row.connect_activated(move |_| {
-        confirm_remove(&handlers_remove, &app_remove);
-    });
-    row.add_row(&remove_row);
+    Some(check)
+}
 
-    row.upcast()
+/// The check button [`make_tile`] puts above the icon in selection mode.
+fn tile_check(tile: &gtk::FlowBoxChild) -> Option<gtk::CheckButton> {
+    tile.child()?.first_child()?.downcast().ok()
 }
 
 fn icon_widget(app: &WebAppDefinition, paths: &AppPaths) -> gtk::Widget {
@@ -268,11 +708,23 @@ fn icon_widget(app: &WebAppDefinition, paths: &AppPaths) -> gtk::Widget {
     image.upcast()
 }
 
+fn describe_launcher(status: &LauncherStatus) -> String {
+    match status {
+        LauncherStatus::Unknown => "Not installed yet".to_string(),
+        LauncherStatus::Installed => "Installed".to_string(),
+        LauncherStatus::Exported { path } => format!("Exported to {}", path.display()),
+        LauncherStatus::Failed { error } => format!("Install failed: {error}"),
+    }
+}
+
 fn format_last_launched(app: &WebAppDefinition) -> String {
-    let Some(ts) = app.last_launched_at else {
-        return "Never".to_string();
-    };
+    match app.last_launched_at {
+        Some(ts) => format_relative(ts),
+        None => "Never".to_string(),
+    }
+}
 
+fn format_relative(ts: OffsetDateTime) -> String {
     let now = OffsetDateTime::now_utc();
     let diff = now - ts;
 
@@ -321,27 +773,62 @@ fn confirm_reset(handlers: &Handlers, app: &WebAppDefinition) {
     dialog.present();
 }
 
-fn confirm_remove(handlers: &Handlers, app: &WebAppDefinition) {
-    let dialog = adw::MessageDialog::builder()
-        .transient_for(&handlers.window)
-        .heading(format!("Remove {}?", app.name))
-        .body("This will remove the web app, its data, and its launcher.")
-        .build();
-    dialog.add_response("cancel", "Cancel");
-    dialog.add_response("remove", "Remove");
-    dialog.set_default_response(Some("cancel"));
-    dialog.set_close_response("cancel");
-    let handlers = handlers.clone();
-    let app = app.clone();
-    dialog.connect_response(None, move |d, resp| {
-        if resp == "remove" {
-            if let Err(err) = run_remove(&handlers, &app) {
+/// Moves `app` to the trash and offers to undo that in a toast.
+fn remove_with_undo(handlers: &Handlers, app: &WebAppDefinition) {
+    if let Err(err) = run_remove(handlers, app) {
+        tracing::error!(target: "ui", "remove failed: {err:?}");
+        show_message(handlers, "Remove failed", &format!("{err:#}"));
+        return;
+    }
+    offer_undo_removal(handlers, format!("“{}” removed", app.name), vec![app.id]);
+}
+
+/// Moves the ticked apps to the trash; a single toast offers to undo the whole batch.
+fn bulk_remove_with_undo(handlers: &Handlers, apps: Vec<WebAppDefinition>) {
+    let mut removed = Vec::new();
+    let mut errors = Vec::new();
+    for app in &apps {
+        match run_remove(handlers, app) {
+            Ok(()) => removed.push(app.id),
+            Err(err) => {
                 tracing::error!(target: "ui", "remove failed: {err:?}");
+                errors.push(format!("{}: {err:#}", app.name));
             }
         }
-        d.close();
+    }
+    if !errors.is_empty() {
+        show_message(handlers, "Remove failed", &errors.join("\n"));
+    }
+    if !removed.is_empty() {
+        let title = format!("{} removed", app_count(removed.len()));
+        offer_undo_removal(handlers, title, removed);
+    }
+}
+
+/// Shows a toast whose Undo button restores `app_ids` from the trash.
+fn offer_undo_removal(handlers: &Handlers, title: String, app_ids: Vec<WebAppId>) {
+    let toast = adw::Toast::builder()
+        .title(title)
+        .use_markup(false)
+        .button_label("Undo")
+        .priority(adw::ToastPriority::High)
+        .build();
+    let handlers_undo = handlers.clone();
+    toast.connect_button_clicked(move |_| {
+        let mut errors = Vec::new();
+        for &app_id in &app_ids {
+            if let Err(err) = restore_app(&handlers_undo, app_id) {
+                tracing::error!(target: "ui", "restore failed: {err:?}");
+                errors.push(format!("{err:#}"));
+            }
+        }
+        if !errors.is_empty() {
+            show_message(&handlers_undo, "Restore failed", &errors.join("\n"));
+        } else if let [app_id] = app_ids[..] {
+            select_app(&handlers_undo, app_id);
+        }
     });
-    dialog.present();
+    handlers.toasts.add_toast(toast);
 }
 
 fn run_reset(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
@@ -358,31 +845,93 @@ fn run_reset(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
     handlers
         .ctx
         .paths
-        .delete_icons_for(&app.icon_id)
+        .reset_icons_for(app)
         .context("delete icons")?;
 
     refresh_current(handlers);
+    // The permission rows still hold the store that was just deleted.
+    reload_detail(handlers, app.id);
+    load_storage_usage(handlers, app.id);
     Ok(())
 }
 
+/// Moves `app` and its data to the trash and removes its launcher.
 fn run_remove(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
-    run_reset(handlers, app)?;
-    handlers
-        .ctx
-        .registry
-        .delete(app.id)
-        .context("delete app registry")?;
-
+    handlers.ctx.trash.trash(app).context("move app to trash")?;
     handlers.apps.borrow_mut().retain(|a| a.id != app.id);
 
-    if let Err(err) = remove_launcher(&desktop_id_for(app)) {
-        tracing::warn!(target: "portal", "remove launcher failed: {err:?}");
+    if let LauncherStatus::Exported { path } = &app.launcher {
+        if let Err(err) = remove_exported_launcher(path, &app.icon_id) {
+            tracing::warn!(target: "ui", "remove exported launcher {path:?} failed: {err:?}");
+        }
+    } else {
+        let portals = Rc::clone(&handlers.ctx.portals);
+        let desktop_id = desktop_id_for(app);
+        glib::MainContext::default().spawn_local(async move {
+            if let Err(err) = portals.remove_launcher(&desktop_id).await {
+                tracing::warn!(target: "portal", "remove launcher failed: {err:?}");
+            }
+        });
     }
 
     refresh_current(handlers);
     Ok(())
 }
 
+/// Brings `app_id` back from the trash and reinstalls its launcher.
+fn restore_app(handlers: &Handlers, app_id: WebAppId) -> Result<WebAppDefinition> {
+    let app = handlers
+        .ctx
+        .trash
+        .restore(app_id)
+        .context("restore from trash")?;
+    handlers.apps.borrow_mut().push(app.clone());
+    refresh_current(handlers);
+    let handlers = handlers.clone();
+    glib::MainContext::default().spawn_local(async move {
+        sync_launcher(&handlers, app_id).await;
+    });
+    Ok(app)
+}
+
+/// Purges apps that have been in the trash longer than
+/// [`ManagerSettings::purge_after_days`]. Runs before the window is shown, so nothing
+/// can restore an entry while it is being deleted.
+fn purge_expired_trash(handlers: &Handlers) {
+    let days = handlers.settings.borrow().purge_after_days;
+    if days == 0 {
+        return;
+    }
+    let trash = &handlers.ctx.trash;
+    match trash.purge_older_than(Duration::days(days.into()), OffsetDateTime::now_utc()) {
+        Ok(purged) => {
+            for entry in purged {
+                tracing::info!(target: "ui", app = %entry.app.id, "purged removed app");
+            }
+        }
+        Err(err) => tracing::warn!(target: "ui", "purge trash failed: {err:?}"),
+    }
+}
+
+/// Deletes staging directories that create dialogs left behind, e.g. when the manager
+/// quit while a lookup was running.
+fn remove_draft_dirs(paths: &AppPaths) {
+    let Ok(entries) = fs::read_dir(paths.icons_cache_dir()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let is_draft = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(DRAFT_DIR_PREFIX));
+        if is_draft && entry.path().is_dir() {
+            if let Err(err) = fs::remove_dir_all(entry.path()) {
+                tracing::warn!(target: "ui", "remove {:?} failed: {err:?}", entry.path());
+            }
+        }
+    }
+}
+
 fn run_launch(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
     let mut app_updated = app.clone();
     app_updated.last_launched_at = Some(OffsetDateTime::now_utc());
@@ -402,38 +951,1595 @@ fn run_launch(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
     Ok(())
 }
 
-fn open_edit_window(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
-    let app_id = app.id;
-    let win = adw::Window::builder()
-        .application(&handlers.window.application().unwrap())
-        .transient_for(&handlers.window)
-        .modal(true)
-        .title("Edit Web App")
-        .default_width(420)
-        .default_height(260)
-        .build();
+/// Turns selection mode on or off. It starts with nothing ticked; once it ends, the app
+/// shown in the detail page is highlighted again.
+fn set_selection_mode(handlers: &Handlers, on: bool) {
+    handlers.bulk.selected.replace(on.then(HashSet::new));
+    let mode = if on {
+        gtk::SelectionMode::None
+    } else {
+        gtk::SelectionMode::Single
+    };
+    handlers.list.set_selection_mode(mode);
+    handlers.grid.set_selection_mode(mode);
+    handlers.bulk.bar.set_revealed(on);
+    refresh_current(handlers);
+}
 
-    let container = gtk::Box::builder()
+fn update_bulk_bar(handlers: &Handlers) {
+    let count = handlers
+        .bulk
+        .selected
+        .borrow()
+        .as_ref()
+        .map_or(0, HashSet::len);
+    handlers.bulk.label.set_label(&if count == 0 {
+        "No apps selected".to_string()
+    } else {
+        format!("{} selected", app_count(count))
+    });
+    handlers.bulk.actions_button.set_sensitive(count > 0);
+}
+
+fn app_count(count: usize) -> String {
+    if count == 1 {
+        "1 app".to_string()
+    } else {
+        format!("{count} apps")
+    }
+}
+
+/// The ticked apps, in the list's order.
+fn bulk_apps(handlers: &Handlers) -> Vec<WebAppDefinition> {
+    let selected = handlers.bulk.selected.borrow();
+    let Some(selected) = selected.as_ref() else {
+        return Vec::new();
+    };
+    let mut apps: Vec<_> = handlers
+        .apps
+        .borrow()
+        .iter()
+        .filter(|app| selected.contains(&app.id))
+        .cloned()
+        .collect();
+    handlers.settings.borrow().sort.sort(&mut apps);
+    apps
+}
+
+/// Adds the "manager.bulk-*" actions behind the selection mode's Actions menu.
+fn install_bulk_actions(handlers: &Handlers, actions: &gio::SimpleActionGroup) {
+    let bulk_actions: [(&str, fn(&Handlers, Vec<WebAppDefinition>)); 6] = [
+        ("bulk-launch", |handlers, apps| {
+            run_bulk(handlers, "Launch", apps, |handlers, app, _| {
+                launch_step(handlers, app)
+            })
+        }),
+        ("bulk-refresh-icons", |handlers, apps| {
+            run_bulk(handlers, "Refresh Icons", apps, refresh_icon_step)
+        }),
+        ("bulk-reinstall-launchers", |handlers, apps| {
+            run_bulk(handlers, "Reinstall Launchers", apps, |handlers, app, _| {
+                reinstall_launcher_step(handlers, app)
+            })
+        }),
+        ("bulk-export", bulk_export),
+        ("bulk-reset", confirm_bulk_reset),
+        ("bulk-remove", bulk_remove_with_undo),
+    ];
+    for (name, run) in bulk_actions {
+        let action = gio::SimpleAction::new(name, None);
+        action.connect_activate(glib::clone!(@strong handlers => move |_, _| {
+            let apps = bulk_apps(&handlers);
+            if !apps.is_empty() {
+                run(&handlers, apps);
+            }
+        }));
+        actions.add_action(&action);
+    }
+
+    let permissions = gio::SimpleAction::new("bulk-permissions", Some(glib::VariantTy::STRING));
+    permissions.connect_activate(glib::clone!(@strong handlers => move |_, target| {
+        let Some(preset) = target
+            .and_then(|target| target.str())
+            .and_then(PermissionPreset::from_key)
+        else {
+            return;
+        };
+        let apps = bulk_apps(&handlers);
+        if !apps.is_empty() {
+            run_bulk(&handlers, preset.label(), apps, move |handlers, app, _| {
+                permission_preset_step(handlers, app, preset)
+            });
+        }
+    }));
+    actions.add_action(&permissions);
+}
+
+/// Runs `step` on each app in turn behind a dialog showing the progress, then lists how
+/// it went for every app. `step` returns a short note for the app's result row and gets
+/// the flag the dialog's Cancel button sets, so long steps can stop early.
+fn run_bulk<F, Fut>(handlers: &Handlers, heading: &str, apps: Vec<WebAppDefinition>, step: F)
+where
+    F: Fn(Handlers, WebAppDefinition, Arc<AtomicBool>) -> Fut + 'static,
+    Fut: Future<Output = Result<String>> + 'static,
+{
+    let total = apps.len();
+    let progress = gtk::ProgressBar::new();
+    let results = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    let scroller = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(280)
+        .child(&results)
+        .visible(false)
+        .build();
+    let content = gtk::Box::builder()
         .orientation(gtk::Orientation::Vertical)
         .spacing(12)
-        .margin_top(18)
-        .margin_bottom(18)
-        .margin_start(18)
-        .margin_end(18)
         .build();
+    content.append(&progress);
+    content.append(&scroller);
 
-    let url_entry = gtk::Entry::builder()
-        .text(&app.start_url)
-        .input_purpose(gtk::InputPurpose::Url)
+    let dialog = adw::MessageDialog::builder()
+        .transient_for(&handlers.window)
+        .modal(true)
+        .heading(heading)
+        .extra_child(&content)
         .build();
-    let name_entry = gtk::Entry::builder().text(&app.name).build();
-
-    let open_external_switch = gtk::Switch::builder()
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("close", "Close");
+    dialog.set_close_response("cancel");
+    // Close stays off until every step is done; Cancel stops before the next app and
+    // interrupts downloads in flight.
+    dialog.set_response_enabled("close", false);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    dialog.connect_response(Some("cancel"), {
+        let cancelled = Arc::clone(&cancelled);
+        move |_, _| cancelled.store(true, Ordering::Relaxed)
+    });
+    dialog.present();
+
+    let handlers = handlers.clone();
+    let heading = heading.to_string();
+    glib::MainContext::default().spawn_local(async move {
+        let mut failed = 0;
+        let mut done = 0;
+        for (index, app) in apps.into_iter().enumerate() {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            dialog.set_body(&format!("{} ({} of {total})…", app.name, index + 1));
+            progress.set_fraction(index as f64 / total as f64);
+            let row = adw::ActionRow::builder()
+                .title(&app.name)
+                .use_markup(false)
+                .build();
+            let (icon, note) = match step(handlers.clone(), app, Arc::clone(&cancelled)).await {
+                Ok(note) => ("emblem-ok-symbolic", note),
+                Err(err) => {
+                    failed += 1;
+                    ("dialog-warning-symbolic", format!("{err:#}"))
+                }
+            };
+            done += 1;
+            row.add_prefix(&gtk::Image::from_icon_name(icon));
+            row.set_subtitle(&note);
+            results.append(&row);
+            scroller.set_visible(true);
+        }
+        refresh_current(&handlers);
+        if cancelled.load(Ordering::Relaxed) {
+            let toast = adw::Toast::builder()
+                .title(format!(
+                    "{heading} stopped after {done} of {}",
+                    app_count(total)
+                ))
+                .use_markup(false)
+                .build();
+            handlers.toasts.add_toast(toast);
+            return;
+        }
+        progress.set_fraction(1.0);
+        dialog.set_body(&if failed == 0 {
+            format!("Done for {}.", app_count(total))
+        } else {
+            format!("Failed for {failed} of {}.", app_count(total))
+        });
+        dialog.set_response_enabled("cancel", false);
+        dialog.set_response_enabled("close", true);
+        dialog.set_close_response("close");
+    });
+}
+
+/// Asks before deleting data for good; `on_confirm` runs once the user agrees.
+fn confirm_deletion(
+    parent: &impl IsA<gtk::Window>,
+    heading: &str,
+    body: &str,
+    label: &str,
+    on_confirm: impl Fn() + 'static,
+) {
+    let dialog = adw::MessageDialog::builder()
+        .transient_for(parent)
+        .heading(heading)
+        .body(body)
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("confirm", label);
+    dialog.set_response_appearance("confirm", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+    dialog.connect_response(None, move |d, resp| {
+        d.close();
+        if resp == "confirm" {
+            on_confirm();
+        }
+    });
+    dialog.present();
+}
+
+fn confirm_bulk_reset(handlers: &Handlers, apps: Vec<WebAppDefinition>) {
+    let heading = format!("Reset data for {}?", app_count(apps.len()));
+    let runner = handlers.clone();
+    confirm_deletion(
+        &handlers.window,
+        &heading,
+        "This will clear cookies, storage, cache, and permissions for each selected web app.",
+        "Reset",
+        move || {
+            run_bulk(&runner, "Reset Data", apps.clone(), |handlers, app, _| {
+                reset_step(handlers, app)
+            })
+        },
+    );
+}
+
+/// Asks for one folder and exports every app's launcher into it.
+fn bulk_export(handlers: &Handlers, apps: Vec<WebAppDefinition>) {
+    let handlers = handlers.clone();
+    glib::MainContext::default().spawn_local(async move {
+        match pick_export_dir(&handlers, "Export Launchers").await {
+            Ok(Some(dir)) => run_bulk(
+                &handlers,
+                "Export Launchers",
+                apps,
+                move |handlers, app, _| export_launcher_step(handlers, app, dir.clone()),
+            ),
+            Ok(None) => {}
+            Err(err) => {
+                tracing::error!(target: "ui", "export launchers failed: {err:?}");
+                show_message(&handlers, "Export failed", &format!("{err:#}"));
+            }
+        }
+    });
+}
+
+async fn launch_step(handlers: Handlers, app: WebAppDefinition) -> Result<String> {
+    run_launch(&handlers, &app)?;
+    Ok("Launched".to_string())
+}
+
+async fn refresh_icon_step(
+    handlers: Handlers,
+    app: WebAppDefinition,
+    cancelled: Arc<AtomicBool>,
+) -> Result<String> {
+    if matches!(app.icon_source, IconSource::Custom) {
+        return Ok("Custom icon, not refreshed".to_string());
+    }
+    if !check_icon(&handlers.ctx.paths, &app, cancelled).await? {
+        return Ok("Icon is up to date".to_string());
+    }
+    if let Some(LauncherStatus::Failed { error }) = sync_launcher(&handlers, app.id).await {
+        bail!("Icon updated, but the launcher was not: {error}");
+    }
+    Ok("Icon updated".to_string())
+}
+
+async fn reinstall_launcher_step(handlers: Handlers, app: WebAppDefinition) -> Result<String> {
+    match sync_launcher(&handlers, app.id).await {
+        Some(LauncherStatus::Failed { error }) => Err(anyhow!(error)),
+        Some(status) => Ok(describe_launcher(&status)),
+        None => bail!("The app no longer exists"),
+    }
+}
+
+async fn export_launcher_step(
+    handlers: Handlers,
+    app: WebAppDefinition,
+    dir: PathBuf,
+) -> Result<String> {
+    let descriptor = launcher_descriptor_for(&app, &handlers.ctx.paths);
+    let exported = export_launcher(&descriptor, &dir)?;
+    let note = format!("Saved {}", exported.desktop_file.display());
+    apply_launcher_status(
+        &handlers,
+        app.id,
+        LauncherStatus::Exported {
+            path: exported.desktop_file,
+        },
+    );
+    Ok(note)
+}
+
+async fn reset_step(handlers: Handlers, app: WebAppDefinition) -> Result<String> {
+    run_reset(&handlers, &app)?;
+    Ok("Website data and permissions cleared".to_string())
+}
+
+async fn permission_preset_step(
+    handlers: Handlers,
+    app: WebAppDefinition,
+    preset: PermissionPreset,
+) -> Result<String> {
+    let permissions = &handlers.ctx.permissions;
+    let mut store = permissions.load(app.id)?;
+    preset.apply(&mut store, &app.primary_origin);
+    permissions.save(app.id, &store)?;
+    reload_detail(&handlers, app.id);
+    Ok("Permissions updated".to_string())
+}
+
+/// Editable fields of a detail page, read back when the user saves.
+struct DetailForm {
+    name_row: adw::EntryRow,
+    url_row: adw::EntryRow,
+    tags_row: adw::EntryRow,
+    open_external_row: adw::SwitchRow,
+    show_nav_row: adw::SwitchRow,
+    background_row: adw::SwitchRow,
+    autostart_row: adw::SwitchRow,
+    color_scheme_row: adw::ComboRow,
+    icon_style_row: adw::ComboRow,
+    accent: Rc<RefCell<Option<String>>>,
+    shortcut_rows: PairRows,
+    scheme_rows: PairRows,
+    origins_entry: gtk::Entry,
+    claim_web_row: adw::SwitchRow,
+}
+
+impl DetailForm {
+    fn extras(&self) -> Result<EditExtras> {
+        Ok(EditExtras {
+            tags: parse_tags(&self.tags_row.text()),
+            shortcuts: collect_shortcuts(&self.shortcut_rows)?,
+            link_handlers: collect_link_handlers(
+                &self.scheme_rows,
+                &self.origins_entry.text(),
+                self.claim_web_row.is_active(),
+            )?,
+            open_external_links: self.open_external_row.is_active(),
+            show_navigation: self.show_nav_row.is_active(),
+            run_in_background: self.background_row.is_active(),
+            start_at_login: self.autostart_row.is_active(),
+            color_scheme: ColorSchemePreference::ALL
+                .get(self.color_scheme_row.selected() as usize)
+                .copied()
+                .unwrap_or_default(),
+            accent_color: self.accent.borrow().clone(),
+            icon_style: selected_icon_style(self.icon_style_row.selected()),
+        })
+    }
+}
+
+/// The selected app's detail page. Besides the form it keeps the widgets that follow
+/// changes made elsewhere: launcher installs, icon refreshes and launches.
+struct DetailView {
+    app_id: WebAppId,
+    title: adw::WindowTitle,
+    banner: adw::Banner,
+    icon_row: adw::ActionRow,
+    icon: RefCell<gtk::Widget>,
+    refresh_icon_btn: gtk::Button,
+    launcher_row: adw::ActionRow,
+    export_btn: gtk::Button,
+    last_launched_row: adw::ActionRow,
+    storage_row: adw::ActionRow,
+    page: adw::PreferencesPage,
+    /// Owns the permission groups, whose callbacks only hold them weakly.
+    permission_groups: Rc<RefCell<Vec<adw::PreferencesGroup>>>,
+    form: DetailForm,
+}
+
+fn find_app(handlers: &Handlers, app_id: WebAppId) -> Option<WebAppDefinition> {
+    handlers
+        .apps
+        .borrow()
+        .iter()
+        .find(|a| a.id == app_id)
+        .cloned()
+}
+
+/// The app a sidebar row or grid tile stands for; see [`make_row`] and [`make_tile`].
+fn row_app_id(row: &impl IsA<gtk::Widget>) -> Option<WebAppId> {
+    WebAppId::parse_str(&row.widget_name()).ok()
+}
+
+/// Shows `app_id`'s settings in the content pane. Unsaved edits on the previous page are
+/// dropped; selecting the app already shown keeps its page as it is.
+fn show_detail(handlers: &Handlers, app_id: WebAppId) {
+    if handlers
+        .detail
+        .borrow()
+        .as_ref()
+        .is_some_and(|detail| detail.app_id == app_id)
+    {
+        return;
+    }
+    let Some(app) = find_app(handlers, app_id) else {
+        return;
+    };
+    let (content, detail) = build_detail(handlers, &app);
+    handlers.detail_page.set_title(&app.name);
+    handlers.detail_page.set_child(Some(&content));
+    handlers.detail.replace(Some(Rc::new(detail)));
+    load_storage_usage(handlers, app_id);
+}
+
+/// Selects `app_id` in the sidebar and shows its detail page, also on narrow windows.
+fn select_app(handlers: &Handlers, app_id: WebAppId) {
+    show_detail(handlers, app_id);
+    refresh_current(handlers);
+    handlers.split_view.set_show_content(true);
+}
+
+/// Rebuilds the detail page if it shows `app_id`, for changes its widgets do not follow,
+/// such as permissions changed from selection mode.
+fn reload_detail(handlers: &Handlers, app_id: WebAppId) {
+    let shown = handlers
+        .detail
+        .borrow()
+        .as_ref()
+        .is_some_and(|detail| detail.app_id == app_id);
+    if shown {
+        handlers.detail.replace(None);
+        show_detail(handlers, app_id);
+    }
+}
+
+/// Puts the placeholder back once the shown app is gone.
+fn clear_detail(handlers: &Handlers) {
+    if handlers.detail.take().is_none() {
+        return;
+    }
+    handlers.detail_page.set_title("Web App");
+    handlers
+        .detail_page
+        .set_child(Some(&handlers.detail_placeholder));
+    handlers.split_view.set_show_content(false);
+}
+
+/// Brings the detail page's status rows in line with the app's current definition.
+fn update_detail(handlers: &Handlers) {
+    let Some(detail) = handlers.detail.borrow().clone() else {
+        return;
+    };
+    let Some(app) = find_app(handlers, detail.app_id) else {
+        clear_detail(handlers);
+        return;
+    };
+    handlers.detail_page.set_title(&app.name);
+    detail.title.set_title(&app.name);
+    detail.title.set_subtitle(&app.primary_origin);
+
+    let icon = icon_widget(&app, &handlers.ctx.paths);
+    detail.icon_row.remove(&*detail.icon.borrow());
+    detail.icon_row.add_prefix(&icon);
+    detail.icon.replace(icon);
+    detail
+        .refresh_icon_btn
+        .set_sensitive(!matches!(app.icon_source, IconSource::Custom));
+
+    detail
+        .launcher_row
+        .set_subtitle(&describe_launcher(&app.launcher));
+    if matches!(app.launcher, LauncherStatus::Failed { .. }) {
+        detail.export_btn.remove_css_class("flat");
+        detail.export_btn.add_css_class("suggested-action");
+    } else {
+        detail.export_btn.remove_css_class("suggested-action");
+        detail.export_btn.add_css_class("flat");
+    }
+    detail
+        .last_launched_row
+        .set_subtitle(&format_last_launched(&app));
+}
+
+/// Measures the app's website data on a worker thread and shows it on its detail page.
+fn load_storage_usage(handlers: &Handlers, app_id: WebAppId) {
+    let (sender, receiver) = async_channel::bounded::<u64>(1);
+    let paths = handlers.ctx.paths.clone();
+    thread::spawn(move || {
+        let _ = sender.send_blocking(paths.profile_size(app_id));
+    });
+    let detail = Rc::clone(&handlers.detail);
+    glib::MainContext::default().spawn_local(async move {
+        let Ok(size) = receiver.recv().await else {
+            return;
+        };
+        let Some(detail) = detail.borrow().clone() else {
+            return;
+        };
+        if detail.app_id != app_id {
+            return;
+        }
+        detail.storage_row.set_subtitle(&if size == 0 {
+            "No website data stored".to_string()
+        } else {
+            format!("{} of cookies, storage and cache", glib::format_size(size))
+        });
+    });
+}
+
+/// Runs `f` with the detail page's current app, if it still exists.
+fn with_detail_app(handlers: &Handlers, app_id: WebAppId, f: impl FnOnce(&WebAppDefinition)) {
+    match find_app(handlers, app_id) {
+        Some(app) => f(&app),
+        None => clear_detail(handlers),
+    }
+}
+
+fn build_detail(handlers: &Handlers, app: &WebAppDefinition) -> (adw::ToolbarView, DetailView) {
+    let app_id = app.id;
+    let title = adw::WindowTitle::new(&app.name, &app.primary_origin);
+    let header = adw::HeaderBar::builder().title_widget(&title).build();
+    let launch_btn = gtk::Button::builder()
+        .label("Launch")
+        .tooltip_text("Open the web app")
+        .build();
+    let save_btn = gtk::Button::builder()
+        .label("Save")
+        .css_classes(["suggested-action"])
+        .build();
+    header.pack_start(&launch_btn);
+    header.pack_end(&save_btn);
+
+    let banner = adw::Banner::builder().button_label("Dismiss").build();
+    banner.connect_button_clicked(|banner| banner.set_revealed(false));
+
+    let page = adw::PreferencesPage::new();
+    let toolbar = adw::ToolbarView::new();
+    toolbar.add_top_bar(&header);
+    toolbar.add_top_bar(&banner);
+    toolbar.set_content(Some(&page));
+
+    // General
+    let general = adw::PreferencesGroup::builder().title("General").build();
+    let name_row = adw::EntryRow::builder().title("Name").build();
+    name_row.set_text(&app.name);
+    let url_row = adw::EntryRow::builder()
+        .title("URL")
+        .input_purpose(gtk::InputPurpose::Url)
+        .build();
+    url_row.set_text(&app.start_url);
+    let tags_row = adw::EntryRow::builder()
+        .title("Tags (comma-separated)")
+        .build();
+    tags_row.set_text(&app.tags.join(", "));
+
+    let icon_row = adw::ActionRow::builder().title("Icon").build();
+    let icon = icon_widget(app, &handlers.ctx.paths);
+    icon_row.add_prefix(&icon);
+    let refresh_icon_btn = gtk::Button::builder()
+        .icon_name("view-refresh-symbolic")
+        .tooltip_text("Check the site for a new icon")
+        .valign(gtk::Align::Center)
+        .css_classes(["flat"])
+        .sensitive(!matches!(app.icon_source, IconSource::Custom))
+        .build();
+    let change_icon_btn = gtk::Button::builder()
+        .label("Change…")
+        .valign(gtk::Align::Center)
+        .build();
+    icon_row.add_suffix(&refresh_icon_btn);
+    icon_row.add_suffix(&change_icon_btn);
+
+    let icon_style_labels: Vec<&str> = IconStyle::ALL.iter().map(|style| style.label()).collect();
+    let icon_style_row = adw::ComboRow::builder()
+        .title("Icon style")
+        .model(&gtk::StringList::new(&icon_style_labels))
+        .build();
+    icon_style_row.set_selected(
+        IconStyle::ALL
+            .iter()
+            .position(|&style| style == app.icon_style)
+            .unwrap_or(0) as u32,
+    );
+
+    let accent = Rc::new(RefCell::new(app.accent_color.clone()));
+    let accent_button = gtk::ColorButton::builder()
+        .title("Header Color")
+        .use_alpha(false)
+        .valign(gtk::Align::Center)
+        .build();
+    if let Some(rgba) = app
+        .accent_color
+        .as_deref()
+        .and_then(|hex| gdk::RGBA::parse(hex).ok())
+    {
+        accent_button.set_rgba(&rgba);
+    }
+    accent_button.connect_color_set(glib::clone!(@strong accent => move |button| {
+        accent.replace(Some(rgba_to_hex(&button.rgba())));
+    }));
+    let accent_reset = gtk::Button::builder()
+        .label("Automatic")
+        .tooltip_text("Use the site's theme color or the icon's main color")
+        .valign(gtk::Align::Center)
+        .css_classes(["flat"])
+        .build();
+    accent_reset.connect_clicked(glib::clone!(@strong accent => move |_| {
+        accent.replace(None);
+    }));
+    let accent_row = adw::ActionRow::builder().title("Header color").build();
+    accent_row.add_suffix(&accent_reset);
+    accent_row.add_suffix(&accent_button);
+
+    general.add(&name_row);
+    general.add(&url_row);
+    general.add(&tags_row);
+    general.add(&icon_row);
+    general.add(&icon_style_row);
+    general.add(&accent_row);
+    page.add(&general);
+
+    // Behavior
+    let behavior = adw::PreferencesGroup::builder().title("Behavior").build();
+    let open_external_row = adw::SwitchRow::builder()
+        .title("Open external links in default browser")
         .active(app.behavior.open_external_links)
         .build();
-    let show_nav_switch = gtk::Switch::builder()
-        .active(app.behavior.show_navigation)
+    let show_nav_row = adw::SwitchRow::builder()
+        .title("Show navigation controls")
+        .active(app.behavior.show_navigation)
+        .build();
+    let background_row = adw::SwitchRow::builder()
+        .title("Keep running in background when closed")
+        .active(app.behavior.run_in_background)
+        .build();
+    let autostart_row = adw::SwitchRow::builder()
+        .title("Start at login")
+        .active(app.behavior.start_at_login)
+        .build();
+    let color_scheme_labels: Vec<&str> = ColorSchemePreference::ALL
+        .iter()
+        .map(|preference| preference.label())
+        .collect();
+    let color_scheme_row = adw::ComboRow::builder()
+        .title("Appearance")
+        .model(&gtk::StringList::new(&color_scheme_labels))
+        .build();
+    color_scheme_row.set_selected(
+        ColorSchemePreference::ALL
+            .iter()
+            .position(|&preference| preference == app.behavior.color_scheme)
+            .unwrap_or(0) as u32,
+    );
+    behavior.add(&open_external_row);
+    behavior.add(&show_nav_row);
+    behavior.add(&background_row);
+    behavior.add(&autostart_row);
+    behavior.add(&color_scheme_row);
+    page.add(&behavior);
+
+    // Shortcuts and link handling
+    let shortcuts = adw::PreferencesGroup::builder()
+        .title("Launcher Shortcuts")
+        .build();
+    let (shortcuts_editor, shortcut_rows) = build_pair_editor(
+        "Add Shortcut",
+        ("Label", "https://example.com/compose"),
+        app.shortcuts
+            .iter()
+            .map(|s| (s.label.clone(), s.url.clone())),
+    );
+    shortcuts.add(&shortcuts_editor);
+    page.add(&shortcuts);
+
+    let links = adw::PreferencesGroup::builder()
+        .title("Open Links With This App")
+        .build();
+    let (schemes_editor, scheme_rows) = build_pair_editor(
+        "Add Scheme",
+        ("mailto", "https://example.com/compose?to=%s"),
+        app.link_handlers
+            .schemes
+            .iter()
+            .map(|h| (h.scheme.clone(), h.url_template.clone())),
+    );
+    let origins_entry = gtk::Entry::builder()
+        .text(app.link_handlers.origins.join(", "))
+        .placeholder_text("Also capture links to: https://app.example.com, …")
+        .input_purpose(gtk::InputPurpose::Url)
+        .build();
+    schemes_editor.append(&origins_entry);
+    links.add(&schemes_editor);
+    let claim_web_row = adw::SwitchRow::builder()
+        .title("Offer as a web browser")
+        .subtitle("Lets the desktop send any web link here; others still open elsewhere")
+        .active(app.link_handlers.claim_web_links)
+        .build();
+    links.add(&claim_web_row);
+    page.add(&links);
+
+    // Desktop launcher
+    let launcher = adw::PreferencesGroup::builder()
+        .title("Desktop Launcher")
+        .build();
+    let launcher_row = adw::ActionRow::builder()
+        .title("Status")
+        .subtitle(describe_launcher(&app.launcher))
+        .build();
+    let export_btn = gtk::Button::builder()
+        .label("Export…")
+        .valign(gtk::Align::Center)
+        .tooltip_text("Save a .desktop file and icons to a folder")
+        .build();
+    if matches!(app.launcher, LauncherStatus::Failed { .. }) {
+        export_btn.add_css_class("suggested-action");
+    } else {
+        export_btn.add_css_class("flat");
+    }
+    launcher_row.add_suffix(&export_btn);
+    let last_launched_row = adw::ActionRow::builder()
+        .title("Last Launched")
+        .subtitle(format_last_launched(app))
+        .build();
+    launcher.add(&launcher_row);
+    launcher.add(&last_launched_row);
+    page.add(&launcher);
+
+    // Storage
+    let storage = adw::PreferencesGroup::builder().title("Storage").build();
+    let storage_row = adw::ActionRow::builder()
+        .title("Website Data")
+        .subtitle("Calculating…")
+        .build();
+    let reset_btn = gtk::Button::builder()
+        .label("Reset…")
+        .valign(gtk::Align::Center)
+        .tooltip_text("Clear cookies, storage, cache and permissions")
+        .build();
+    storage_row.add_suffix(&reset_btn);
+    let remove_row = adw::ActionRow::builder()
+        .title("Remove App")
+        .subtitle("Move the app and its data to Recently Removed")
+        .build();
+    let remove_btn = gtk::Button::builder()
+        .label("Remove")
+        .valign(gtk::Align::Center)
+        .css_classes(["destructive-action"])
+        .build();
+    remove_row.add_suffix(&remove_btn);
+    storage.add(&storage_row);
+    storage.add(&remove_row);
+    page.add(&storage);
+
+    // Permissions come last; their groups are rebuilt when an origin is added.
+    let permission_groups = Rc::new(RefCell::new(Vec::new()));
+    load_permission_groups(handlers, &page, &permission_groups, app);
+
+    launch_btn.connect_clicked(glib::clone!(@strong handlers => move |_| {
+        with_detail_app(&handlers, app_id, |app| {
+            if let Err(err) = run_launch(&handlers, app) {
+                tracing::error!(target: "ui", "launch failed: {err:?}");
+            }
+        });
+    }));
+    save_btn.connect_clicked(glib::clone!(@strong handlers => move |_| {
+        let Some(detail) = handlers.detail.borrow().clone() else {
+            return;
+        };
+        let result = detail.form.extras().and_then(|extras| {
+            handle_edit(
+                &handlers,
+                app_id,
+                &detail.form.url_row.text(),
+                &detail.form.name_row.text(),
+                extras,
+            )
+        });
+        match result {
+            Ok(()) => detail.banner.set_revealed(false),
+            Err(err) => {
+                detail.banner.set_title(&format!("{err}"));
+                detail.banner.set_revealed(true);
+            }
+        }
+    }));
+    refresh_icon_btn.connect_clicked(glib::clone!(@strong handlers => move |_| {
+        with_detail_app(&handlers, app_id, |app| run_icon_refresh(&handlers, app));
+    }));
+    change_icon_btn.connect_clicked(glib::clone!(@strong handlers => move |_| {
+        let on_applied = glib::clone!(@strong handlers => move |_: &WebAppDefinition| {
+            update_detail(&handlers);
+        });
+        open_icon_picker(&handlers, &handlers.window, app_id, on_applied);
+    }));
+    export_btn.connect_clicked(glib::clone!(@strong handlers => move |_| {
+        with_detail_app(&handlers, app_id, |app| export_launcher_interactive(&handlers, app));
+    }));
+    reset_btn.connect_clicked(glib::clone!(@strong handlers => move |_| {
+        with_detail_app(&handlers, app_id, |app| confirm_reset(&handlers, app));
+    }));
+    remove_btn.connect_clicked(glib::clone!(@strong handlers => move |_| {
+        with_detail_app(&handlers, app_id, |app| remove_with_undo(&handlers, app));
+    }));
+
+    let detail = DetailView {
+        app_id,
+        title,
+        banner,
+        icon_row,
+        icon: RefCell::new(icon),
+        refresh_icon_btn,
+        launcher_row,
+        export_btn,
+        last_launched_row,
+        storage_row,
+        page,
+        permission_groups,
+        form: DetailForm {
+            name_row,
+            url_row,
+            tags_row,
+            open_external_row,
+            show_nav_row,
+            background_row,
+            autostart_row,
+            color_scheme_row,
+            icon_style_row,
+            accent,
+            shortcut_rows,
+            scheme_rows,
+            origins_entry,
+            claim_web_row,
+        },
+    };
+    (toolbar, detail)
+}
+
+fn handle_edit(
+    handlers: &Handlers,
+    app_id: WebAppId,
+    url_text: &str,
+    name_text: &str,
+    extras: EditExtras,
+) -> Result<()> {
+    let url_text = url_text.trim();
+    if url_text.is_empty() {
+        bail!("Please enter a URL");
+    }
+    let parsed = normalize_url(url_text)?;
+    let mut name = name_text.trim().to_string();
+    if name.is_empty() {
+        name = parsed
+            .host_str()
+            .map(|h| h.to_string())
+            .unwrap_or_else(|| "Web App".to_string());
+    }
+
+    let mut apps_mut = handlers.apps.borrow_mut();
+    let Some(app) = apps_mut.iter_mut().find(|a| a.id == app_id) else {
+        bail!("app not found")
+    };
+    let url_changed = app.start_url != parsed.to_string();
+    let name_changed = app.name != name;
+    app.name = name.clone();
+    app.start_url = parsed.to_string();
+    app.primary_origin = parsed.origin().ascii_serialization();
+    app.tags = extras.tags;
+    app.behavior.open_external_links = extras.open_external_links;
+    app.behavior.show_navigation = extras.show_navigation;
+    app.shortcuts = extras.shortcuts;
+    app.link_handlers = extras.link_handlers;
+    let background_changed = app.behavior.run_in_background != extras.run_in_background
+        || app.behavior.start_at_login != extras.start_at_login;
+    app.behavior.run_in_background = extras.run_in_background;
+    app.behavior.start_at_login = extras.start_at_login;
+    app.behavior.color_scheme = extras.color_scheme;
+    app.accent_color = extras.accent_color;
+    let needs_accent = app.accent_color.is_none();
+    let style_changed = app.icon_style != extras.icon_style;
+    app.icon_style = extras.icon_style;
+    let normalization = normalization_for(app.icon_style);
+    let icon_source = app.icon_source.clone();
+    // Generated icons show the name's initials.
+    let icon_generated = matches!(icon_source, IconSource::Site)
+        && IconRecord::load(&handlers.ctx.paths.icons_cache_dir(), &app.icon_id)
+            .is_some_and(|record| record.url.is_none());
+
+    handlers.ctx.registry.save(app)?;
+    let icon_id = app.icon_id.clone();
+    let background_app = background_changed.then(|| app.clone());
+    drop(apps_mut);
+    refresh_current(handlers);
+
+    if let Some(app) = background_app {
+        let handlers = handlers.clone();
+        glib::MainContext::default().spawn_local(async move {
+            match sync_background(handlers.ctx.portals.as_ref(), &app).await {
+                Ok(None) => {}
+                Ok(Some(warning)) => show_message(&handlers, "Background access limited", warning),
+                Err(err) => {
+                    tracing::warn!(target: "portal", app = %app.id, "background request failed: {err:?}");
+                    show_message(
+                        &handlers,
+                        "Background access unavailable",
+                        &format!("{err}. The app will quit when its window is closed."),
+                    );
+                }
+            }
+        });
+    }
+
+    // background: refetch icon if URL or icon style changed, or the name of a generated
+    // icon, and derive an accent if there is none, then reinstall launcher with new metadata
+    let refetch_icon = url_changed || style_changed || (name_changed && icon_generated);
+    let icon_refresh = (refetch_icon || needs_accent).then(|| {
+        let (sender, receiver) = async_channel::bounded::<Option<String>>(1);
+        let paths = handlers.ctx.paths.clone();
+        thread::spawn(move || {
+            if refetch_icon {
+                let _ = cache_app_icon(
+                    &parsed,
+                    &name,
+                    &icon_id,
+                    &icon_source,
+                    normalization,
+                    &paths,
+                );
+            }
+            let accent = needs_accent
+                .then(|| {
+                    fetch_site_metadata(&parsed, &accent_icon_path(&paths, &icon_id)).accent_color
+                })
+                .flatten();
+            let _ = sender.send_blocking(accent);
+        });
+        receiver
+    });
+    let handlers = handlers.clone();
+    glib::MainContext::default().spawn_local(async move {
+        if let Some(receiver) = icon_refresh {
+            if let Ok(Some(accent)) = receiver.recv().await {
+                if let Some(app) = handlers
+                    .apps
+                    .borrow_mut()
+                    .iter_mut()
+                    .find(|a| a.id == app_id && a.accent_color.is_none())
+                {
+                    app.accent_color = Some(accent);
+                }
+            }
+        }
+        sync_launcher(&handlers, app_id).await;
+    });
+
+    Ok(())
+}
+
+/// "Icon style" label and a dropdown of [`IconStyle::ALL`] with `selected` chosen.
+fn icon_style_row(selected: IconStyle) -> (gtk::Box, gtk::DropDown) {
+    let labels: Vec<&str> = IconStyle::ALL.iter().map(|style| style.label()).collect();
+    let dropdown = gtk::DropDown::from_strings(&labels);
+    dropdown.set_selected(
+        IconStyle::ALL
+            .iter()
+            .position(|&style| style == selected)
+            .unwrap_or(0) as u32,
+    );
+    let row = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(6)
+        .hexpand(true)
+        .build();
+    row.append(
+        &gtk::Label::builder()
+            .label("Icon style")
+            .xalign(0.0)
+            .hexpand(true)
+            .build(),
+    );
+    row.append(&dropdown);
+    (row, dropdown)
+}
+
+/// The style at `selected` in a list built from [`IconStyle::ALL`].
+fn selected_icon_style(selected: u32) -> IconStyle {
+    IconStyle::ALL
+        .get(selected as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub(crate) fn normalization_for(style: IconStyle) -> Normalization {
+    match style {
+        IconStyle::Automatic => Normalization::Auto,
+        IconStyle::Original => Normalization::Original,
+        IconStyle::Plate => Normalization::Plate,
+    }
+}
+
+/// Renders the icon `source` points at; blocking, so call it from a worker thread.
+fn cache_app_icon(
+    start_url: &Url,
+    app_name: &str,
+    icon_id: &str,
+    source: &IconSource,
+    style: Normalization,
+    paths: &AppPaths,
+) -> Result<IconResult> {
+    let cache_dir = paths.icons_cache_dir();
+    match source {
+        IconSource::Site => fetch_and_cache_icon(start_url, app_name, icon_id, &cache_dir, style),
+        IconSource::Url { url, maskable } => {
+            let url = Url::parse(url).context("invalid icon URL")?;
+            cache_icon_from_url(&url, *maskable, icon_id, &cache_dir, style)
+        }
+        IconSource::Custom => cache_custom_icon(icon_id, &cache_dir, style),
+    }
+}
+
+/// Lets the user choose one of the site's icons or import an image file. The choice is
+/// applied right away and `on_applied` receives the updated app.
+fn open_icon_picker(
+    handlers: &Handlers,
+    parent: &impl IsA<gtk::Window>,
+    app_id: WebAppId,
+    on_applied: impl Fn(&WebAppDefinition) + 'static,
+) {
+    let Some(app) = handlers
+        .apps
+        .borrow()
+        .iter()
+        .find(|a| a.id == app_id)
+        .cloned()
+    else {
+        return;
+    };
+    let Ok(start_url) = Url::parse(&app.start_url) else {
+        return;
+    };
+    let win = adw::Window::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("App Icon")
+        .default_width(420)
+        .default_height(360)
+        .build();
+    let container = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .margin_top(18)
+        .margin_bottom(18)
+        .margin_start(18)
+        .margin_end(18)
+        .build();
+    let status_label = gtk::Label::builder()
+        .label(format!(
+            "Looking for icons on {}…",
+            start_url.host_str().unwrap_or_default()
+        ))
+        .xalign(0.0)
+        .wrap(true)
+        .build();
+    let flow = gtk::FlowBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .max_children_per_line(4)
+        .homogeneous(true)
+        .row_spacing(6)
+        .column_spacing(6)
+        .build();
+    let scrolled = gtk::ScrolledWindow::builder()
+        .vexpand(true)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .child(&flow)
+        .build();
+    let error_label = gtk::Label::builder()
+        .xalign(0.0)
+        .css_classes(["error"])
+        .wrap(true)
+        .build();
+    let button_row = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(6)
+        .halign(gtk::Align::End)
+        .build();
+    let site_btn = gtk::Button::builder()
+        .label("Use Site Icon")
+        .tooltip_text("Pick the best icon the site declares on every refresh")
+        .sensitive(!app.icon_source.is_site())
+        .build();
+    let import_btn = gtk::Button::with_label("Import File…");
+    button_row.append(&site_btn);
+    button_row.append(&import_btn);
+    container.append(&status_label);
+    container.append(&scrolled);
+    container.append(&error_label);
+    container.append(&button_row);
+    win.set_content(Some(&container));
+
+    let apply: Rc<dyn Fn(IconSource, Option<PathBuf>)> = {
+        let handlers = handlers.clone();
+        let on_applied = Rc::new(on_applied);
+        let win = win.downgrade();
+        let error_label = error_label.downgrade();
+        Rc::new(move |source: IconSource, import: Option<PathBuf>| {
+            let Some(window) = win.upgrade() else {
+                return;
+            };
+            window.set_sensitive(false);
+            let handlers = handlers.clone();
+            let on_applied = Rc::clone(&on_applied);
+            let win = win.clone();
+            let error_label = error_label.clone();
+            glib::MainContext::default().spawn_local(async move {
+                let result = apply_icon_source(&handlers, app_id, source, import).await;
+                let Some(window) = win.upgrade() else {
+                    return;
+                };
+                match result {
+                    Ok(app) => {
+                        on_applied(&app);
+                        window.close();
+                    }
+                    Err(err) => {
+                        window.set_sensitive(true);
+                        if let Some(label) = error_label.upgrade() {
+                            label.set_label(&format!("{err:#}"));
+                        }
+                    }
+                }
+            });
+        })
+    };
+
+    site_btn.connect_clicked(glib::clone!(@strong apply => move |_| {
+        apply(IconSource::Site, None);
+    }));
+    import_btn.connect_clicked(
+        glib::clone!(@weak win, @strong handlers, @strong apply => move |_| {
+            let handlers = handlers.clone();
+            let apply = Rc::clone(&apply);
+            glib::MainContext::default().spawn_local(async move {
+                let portals = Rc::clone(&handlers.ctx.portals);
+                let file = if portals.capabilities().await.has(PortalKind::FileChooser) {
+                    portals.pick_file(&icon_file_request()).await
+                } else {
+                    pick_icon_file_native(&win).await
+                };
+                match file {
+                    Ok(Some(path)) => apply(IconSource::Custom, Some(path)),
+                    Ok(None) => {}
+                    Err(err) => show_message(&handlers, "Could not open file", &format!("{err:#}")),
+                }
+            });
+        }),
+    );
+
+    let (sender, receiver) = async_channel::bounded::<Vec<IconPreview>>(1);
+    thread::spawn(move || {
+        let _ = sender.send_blocking(preview_candidates(&start_url, ICON_PREVIEW_LIMIT));
+    });
+    glib::MainContext::default().spawn_local(
+        glib::clone!(@weak flow, @weak status_label, @strong apply => async move {
+            let previews = receiver.recv().await.unwrap_or_default();
+            status_label.set_label(if previews.is_empty() {
+                "The site declares no usable icons. You can import an image file instead."
+            } else {
+                "Choose one of the site's icons, or import an image file."
+            });
+            for preview in previews {
+                flow.append(&icon_candidate_button(preview, &apply));
+            }
+        }),
+    );
+
+    win.present();
+}
+
+/// A picker button showing a candidate preview and its size.
+fn icon_candidate_button(
+    preview: IconPreview,
+    apply: &Rc<dyn Fn(IconSource, Option<PathBuf>)>,
+) -> gtk::Widget {
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(4)
+        .build();
+    let image = match gdk::Texture::from_bytes(&glib::Bytes::from_owned(preview.png)) {
+        Ok(texture) => gtk::Image::from_paintable(Some(&texture)),
+        Err(_) => gtk::Image::from_icon_name("image-missing"),
+    };
+    image.set_pixel_size(64);
+    content.append(&image);
+    let size = if preview.candidate.is_vector() {
+        "Vector".to_string()
+    } else {
+        match preview.resolution {
+            Some(px) => format!("{px} px"),
+            None => "Unknown size".to_string(),
+        }
+    };
+    content.append(
+        &gtk::Label::builder()
+            .label(size)
+            .css_classes(["caption", "dim-label"])
+            .build(),
+    );
+    let button = gtk::Button::builder()
+        .child(&content)
+        .tooltip_text(preview.candidate.url.as_str())
+        .css_classes(["flat"])
+        .build();
+    let source = IconSource::Url {
+        url: preview.candidate.url.to_string(),
+        maskable: preview.candidate.is_maskable_only(),
+    };
+    let apply = Rc::clone(apply);
+    button.connect_clicked(move |_| apply(source.clone(), None));
+    button.upcast()
+}
+
+/// Renders the chosen icon on a worker thread, stores the new source on the app and
+/// reinstalls its launcher.
+async fn apply_icon_source(
+    handlers: &Handlers,
+    app_id: WebAppId,
+    source: IconSource,
+    import: Option<PathBuf>,
+) -> Result<WebAppDefinition> {
+    let app = handlers
+        .apps
+        .borrow()
+        .iter()
+        .find(|a| a.id == app_id)
+        .cloned()
+        .context("app no longer exists")?;
+    let start_url = Url::parse(&app.start_url)?;
+    let normalization = normalization_for(app.icon_style);
+    let (sender, receiver) = async_channel::bounded::<Result<IconResult>>(1);
+    let paths = handlers.ctx.paths.clone();
+    let icon_id = app.icon_id.clone();
+    let app_name = app.name.clone();
+    let worker_source = source.clone();
+    thread::spawn(move || {
+        let result = match import {
+            Some(file) => {
+                import_custom_icon(&file, &icon_id, &paths.icons_cache_dir(), normalization)
+            }
+            None => cache_app_icon(
+                &start_url,
+                &app_name,
+                &icon_id,
+                &worker_source,
+                normalization,
+                &paths,
+            ),
+        };
+        let _ = sender.send_blocking(result);
+    });
+    receiver.recv().await.context("icon worker stopped")??;
+
+    let updated = {
+        let mut apps = handlers.apps.borrow_mut();
+        let app = apps
+            .iter_mut()
+            .find(|a| a.id == app_id)
+            .context("app no longer exists")?;
+        app.icon_source = source;
+        handlers.ctx.registry.save(app)?;
+        app.clone()
+    };
+    sync_launcher(handlers, app_id).await;
+    Ok(updated)
+}
+
+fn icon_file_request() -> PickFileRequest {
+    PickFileRequest {
+        title: "Choose Icon".into(),
+        accept_label: "Use Icon".into(),
+        filters: vec![(
+            "Images".into(),
+            ICON_FILE_TYPES
+                .iter()
+                .map(|mime| mime.to_string())
+                .collect(),
+        )],
+    }
+}
+
+/// Icon file picker for hosts without the FileChooser portal.
+async fn pick_icon_file_native(parent: &adw::Window) -> Result<Option<PathBuf>> {
+    let chooser = gtk::FileChooserNative::new(
+        Some("Choose Icon"),
+        Some(parent),
+        gtk::FileChooserAction::Open,
+        Some("Use Icon"),
+        Some("Cancel"),
+    );
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Images"));
+    for mime in ICON_FILE_TYPES {
+        filter.add_mime_type(mime);
+    }
+    chooser.add_filter(&filter);
+    let (sender, receiver) = async_channel::bounded::<Option<PathBuf>>(1);
+    chooser.connect_response(move |chooser, response| {
+        let file = if response == gtk::ResponseType::Accept {
+            chooser.file().and_then(|file| file.path())
+        } else {
+            None
+        };
+        let _ = sender.try_send(file);
+    });
+    chooser.show();
+    // Holding `chooser` across the await keeps the native dialog alive.
+    let file = receiver.recv().await.ok().flatten();
+    chooser.destroy();
+    Ok(file)
+}
+
+/// A list of two-entry rows with add/remove buttons, for placing in a preferences group.
+fn build_pair_editor(
+    add_label: &str,
+    placeholders: (&'static str, &'static str),
+    initial: impl IntoIterator<Item = (String, String)>,
+) -> (gtk::Box, PairRows) {
+    let editor = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .build();
+    let rows_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .build();
+    editor.append(&rows_box);
+
+    let rows: PairRows = Rc::new(RefCell::new(Vec::new()));
+    let add_row = {
+        let rows_box = rows_box.clone();
+        let rows = Rc::clone(&rows);
+        move |key: &str, value: &str| {
+            let row = gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(6)
+                .build();
+            let key_entry = gtk::Entry::builder()
+                .text(key)
+                .placeholder_text(placeholders.0)
+                .build();
+            let value_entry = gtk::Entry::builder()
+                .text(value)
+                .placeholder_text(placeholders.1)
+                .input_purpose(gtk::InputPurpose::Url)
+                .hexpand(true)
+                .build();
+            let remove_btn = gtk::Button::builder()
+                .icon_name("list-remove-symbolic")
+                .tooltip_text("Remove")
+                .css_classes(["flat"])
+                .build();
+            row.append(&key_entry);
+            row.append(&value_entry);
+            row.append(&remove_btn);
+            rows_box.append(&row);
+
+            let rows_remove = Rc::clone(&rows);
+            remove_btn.connect_clicked(glib::clone!(@weak rows_box, @weak row => move |_| {
+                rows_box.remove(&row);
+                rows_remove.borrow_mut().retain(|(r, _, _)| r != &row);
+            }));
+            rows.borrow_mut().push((row, key_entry, value_entry));
+        }
+    };
+
+    for (key, value) in initial {
+        add_row(&key, &value);
+    }
+
+    let add_btn = gtk::Button::builder()
+        .label(add_label)
+        .halign(gtk::Align::Start)
+        .css_classes(["flat"])
+        .build();
+    add_btn.connect_clicked(move |_| add_row("", ""));
+    editor.append(&add_btn);
+
+    (editor, rows)
+}
+
+/// Validates editor rows; rows with both fields empty are ignored.
+fn collect_shortcuts(rows: &PairRows) -> Result<Vec<AppShortcut>> {
+    let mut shortcuts = Vec::new();
+    for (_, label_entry, url_entry) in rows.borrow().iter() {
+        let label = label_entry.text().trim().to_string();
+        let url = url_entry.text().trim().to_string();
+        if label.is_empty() && url.is_empty() {
+            continue;
+        }
+        if label.is_empty() {
+            bail!("Shortcut for {url} needs a label");
+        }
+        let parsed = normalize_url(&url).with_context(|| format!("Invalid URL for {label}"))?;
+        shortcuts.push(AppShortcut {
+            label,
+            url: parsed.to_string(),
+        });
+    }
+    Ok(shortcuts)
+}
+
+/// Validates scheme handler rows and the comma-separated list of captured origins.
+fn collect_link_handlers(
+    rows: &PairRows,
+    origins: &str,
+    claim_web_links: bool,
+) -> Result<LinkHandlers> {
+    let mut handlers = LinkHandlers {
+        claim_web_links,
+        ..LinkHandlers::default()
+    };
+    for (_, scheme_entry, template_entry) in rows.borrow().iter() {
+        let scheme = scheme_entry
+            .text()
+            .trim()
+            .trim_end_matches(':')
+            .to_ascii_lowercase();
+        let template = template_entry.text().trim().to_string();
+        if scheme.is_empty() && template.is_empty() {
+            continue;
+        }
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid_scheme || matches!(scheme.as_str(), "http" | "https") {
+            bail!("\"{scheme}\" is not a scheme this app can handle");
+        }
+        if !template.contains("%s") {
+            bail!("Link template for {scheme}: needs a %s placeholder");
+        }
+        normalize_url(&template.replace("%s", ""))
+            .with_context(|| format!("Invalid link template for {scheme}:"))?;
+        handlers.schemes.push(SchemeHandler {
+            scheme,
+            url_template: template,
+        });
+    }
+    for origin in origins.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+        let parsed = normalize_url(origin).with_context(|| format!("Invalid origin {origin}"))?;
+        handlers.origins.push(parsed.origin().ascii_serialization());
+    }
+    handlers.origins.dedup();
+    Ok(handlers)
+}
+
+fn refresh_current(handlers: &Handlers) {
+    sync_tag_filter(handlers);
+    let filter = handlers.search_entry.text();
+    refresh_listbox(&handlers.list, &handlers.apps.borrow(), &filter, handlers);
+    update_detail(handlers);
+}
+
+/// The create dialog's widgets that a site lookup updates.
+#[derive(Clone)]
+struct CreateForm {
+    url_entry: gtk::Entry,
+    name_entry: gtk::Entry,
+    status_label: gtk::Label,
+    spinner: gtk::Spinner,
+    preview: gtk::Image,
+    icon_style_dropdown: gtk::DropDown,
+    create_btn: gtk::Button,
+}
+
+/// What a lookup in the create dialog found for one URL and icon style.
+struct SiteLookup {
+    url: Url,
+    /// The name the site gives itself, if any.
+    suggested_name: Option<String>,
+    manifest: Option<(Url, WebManifest)>,
+    /// Holds the icon rendered as [`DRAFT_ICON_ID`].
+    icon_dir: PathBuf,
+    /// The name the icon was rendered for; generated icons show its initials.
+    icon_name: String,
+    /// Whether the site had no usable icon, so initials were drawn instead.
+    icon_generated: bool,
+    /// Why the page could not be loaded.
+    error: Option<String>,
+}
+
+/// State of an open create dialog. Each lookup renders into its own numbered directory
+/// under `staging`; only the latest generation is kept, older results are discarded.
+struct CreateDraft {
+    handlers: Handlers,
+    staging: PathBuf,
+    generation: Cell<u64>,
+    /// Set to stop the current generation's lookup at its next download.
+    cancelled: RefCell<Arc<AtomicBool>>,
+    /// The delayed start of the next lookup while the user is still typing.
+    pending: RefCell<Option<glib::SourceId>>,
+    /// Whether the latest lookup is still running.
+    looking_up: Cell<bool>,
+    lookup: RefCell<Option<SiteLookup>>,
+    /// The name the latest lookup suggested; the name entry follows suggestions until edited.
+    suggested_name: RefCell<Option<String>>,
+    /// `None` once the dialog is closed.
+    form: RefCell<Option<CreateForm>>,
+    /// Set when the app was saved; the lookup's icon is moved into place once it is done.
+    created: Cell<Option<WebAppId>>,
+}
+
+impl CreateDraft {
+    fn form(&self) -> Option<CreateForm> {
+        self.form.borrow().clone()
+    }
+
+    /// Discards the results of lookups still running and stops their downloads.
+    fn next_generation(&self) -> u64 {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        self.cancelled
+            .replace(Arc::new(AtomicBool::new(false)))
+            .store(true, Ordering::Relaxed);
+        generation
+    }
+}
+
+fn open_create_window(
+    app: &adw::Application,
+    parent: &adw::ApplicationWindow,
+    handlers: Handlers,
+) -> Result<()> {
+    let win = adw::Window::builder()
+        .application(app)
+        .transient_for(parent)
+        .modal(true)
+        .title("Create Web App")
+        .default_width(420)
+        .default_height(320)
+        .build();
+
+    let container = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .margin_top(18)
+        .margin_bottom(18)
+        .margin_start(18)
+        .margin_end(18)
+        .build();
+
+    let preview = gtk::Image::from_icon_name("applications-internet");
+    preview.set_pixel_size(64);
+    preview.set_halign(gtk::Align::Center);
+
+    let url_entry = gtk::Entry::builder()
+        .placeholder_text("https://example.com")
+        .input_purpose(gtk::InputPurpose::Url)
+        .build();
+    let name_entry = gtk::Entry::builder().placeholder_text("App name").build();
+
+    let spinner = gtk::Spinner::new();
+    let status_label = gtk::Label::builder()
+        .xalign(0.0)
+        .hexpand(true)
+        .wrap(true)
+        .css_classes(["dim-label"])
+        .build();
+    let status_row = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(6)
         .build();
+    status_row.append(&spinner);
+    status_row.append(&status_label);
+
+    let open_external_switch = gtk::Switch::builder().active(true).build();
+    let show_nav_switch = gtk::Switch::builder().active(false).build();
     let error_label = gtk::Label::builder()
         .xalign(0.0)
         .css_classes(["error"])
@@ -446,13 +2552,16 @@ fn open_edit_window(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
         .halign(gtk::Align::End)
         .build();
     let cancel_btn = gtk::Button::with_label("Cancel");
-    let save_btn = gtk::Button::with_label("Save");
-    save_btn.add_css_class("suggested-action");
+    let create_btn = gtk::Button::with_label("Create");
+    create_btn.add_css_class("suggested-action");
+    create_btn.set_sensitive(false);
     button_row.append(&cancel_btn);
-    button_row.append(&save_btn);
+    button_row.append(&create_btn);
 
+    container.append(&preview);
     container.append(&gtk::Label::builder().label("URL").xalign(0.0).build());
     container.append(&url_entry);
+    container.append(&status_row);
     container.append(&gtk::Label::builder().label("Name").xalign(0.0).build());
     container.append(&name_entry);
 
@@ -484,346 +2593,1203 @@ fn open_edit_window(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
     );
     show_nav_row.append(&show_nav_switch);
 
+    let (icon_style_row, icon_style_dropdown) = icon_style_row(IconStyle::default());
+
     container.append(&open_external_row);
     container.append(&show_nav_row);
+    container.append(&icon_style_row);
     container.append(&error_label);
     container.append(&button_row);
     win.set_content(Some(&container));
 
+    let draft = Rc::new(CreateDraft {
+        staging: handlers
+            .ctx
+            .paths
+            .icons_cache_dir()
+            .join(format!("{DRAFT_DIR_PREFIX}{}", glib::real_time())),
+        handlers,
+        generation: Cell::new(0),
+        cancelled: RefCell::new(Arc::new(AtomicBool::new(false))),
+        pending: RefCell::new(None),
+        looking_up: Cell::new(false),
+        lookup: RefCell::new(None),
+        suggested_name: RefCell::new(None),
+        form: RefCell::new(Some(CreateForm {
+            url_entry: url_entry.clone(),
+            name_entry: name_entry.clone(),
+            status_label,
+            spinner,
+            preview,
+            icon_style_dropdown: icon_style_dropdown.clone(),
+            create_btn: create_btn.clone(),
+        })),
+        created: Cell::new(None),
+    });
+
+    url_entry.connect_changed(glib::clone!(@strong draft => move |_| schedule_lookup(&draft)));
+    icon_style_dropdown
+        .connect_selected_notify(glib::clone!(@strong draft => move |_| schedule_lookup(&draft)));
+
+    // The form holds the widgets whose handlers hold the draft; dropping it on close
+    // breaks that cycle. Lookups finishing later only clean up or finish the creation.
+    win.connect_close_request(glib::clone!(@strong draft => move |_| {
+        draft.form.take();
+        if let Some(source) = draft.pending.take() {
+            source.remove();
+        }
+        if draft.created.get().is_none() {
+            draft.next_generation();
+            let _ = fs::remove_dir_all(&draft.staging);
+        }
+        glib::Propagation::Proceed
+    }));
+
     cancel_btn.connect_clicked(glib::clone!(@weak win => move |_| win.close()));
 
-    save_btn.connect_clicked(glib::clone!(@weak win, @weak url_entry, @weak name_entry, @weak open_external_switch, @weak show_nav_switch, @weak error_label, @strong handlers, @strong app_id => move |_| {
-        if let Err(err) = handle_edit(&handlers, app_id, &url_entry, &name_entry, &open_external_switch, &show_nav_switch) {
+    create_btn.connect_clicked(glib::clone!(@weak win, @weak open_external_switch, @weak show_nav_switch, @weak error_label, @strong draft => move |_| {
+        if draft.created.get().is_some() {
+            return;
+        }
+        if let Err(err) = handle_create(&draft, &open_external_switch, &show_nav_switch) {
             error_label.set_label(&format!("{err}"));
             return;
         }
         win.close();
     }));
 
-    // Avoid double submit: enter key should not invoke twice; rely on button only.
+    // Avoid double submit via Enter by not setting a default widget and guarding re-entry.
 
     win.present();
     Ok(())
 }
 
-fn handle_edit(
-    handlers: &Handlers,
-    app_id: WebAppId,
-    url_entry: &gtk::Entry,
-    name_entry: &gtk::Entry,
+/// The URL typed into the create dialog, if an app can be made from it.
+fn draft_url(text: &str) -> Result<Url> {
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("Please enter a URL");
+    }
+    let url = normalize_url(text)?;
+    if url.host_str().is_none_or(str::is_empty) {
+        bail!("The URL has no host name");
+    }
+    Ok(url)
+}
+
+/// Validates the URL after an edit and, when it is usable, looks the site up once the
+/// user stops typing for [`CREATE_LOOKUP_DELAY`].
+fn schedule_lookup(draft: &Rc<CreateDraft>) {
+    draft.next_generation();
+    if let Some(source) = draft.pending.take() {
+        source.remove();
+    }
+    let Some(form) = draft.form() else {
+        return;
+    };
+    form.status_label.remove_css_class("error");
+    form.status_label.remove_css_class("warning");
+    let text = form.url_entry.text();
+    let url = match draft_url(&text) {
+        Ok(url) => url,
+        Err(err) => {
+            draft.looking_up.set(false);
+            form.spinner.stop();
+            form.create_btn.set_sensitive(false);
+            if text.trim().is_empty() {
+                form.url_entry.remove_css_class("error");
+                form.status_label.set_label("");
+            } else {
+                form.url_entry.add_css_class("error");
+                form.status_label.add_css_class("error");
+                form.status_label.set_label(&format!("{err:#}"));
+            }
+            return;
+        }
+    };
+    form.url_entry.remove_css_class("error");
+    form.create_btn.set_sensitive(true);
+    form.spinner.start();
+    form.status_label.set_label(&format!(
+        "Looking up {}…",
+        url.host_str().unwrap_or_default()
+    ));
+    draft.looking_up.set(true);
+    let weak = Rc::downgrade(draft);
+    let source = glib::timeout_add_local_once(CREATE_LOOKUP_DELAY, move || {
+        if let Some(draft) = weak.upgrade() {
+            draft.pending.take();
+            start_lookup(&draft);
+        }
+    });
+    draft.pending.replace(Some(source));
+}
+
+/// Looks the site up on a worker thread for the URL and icon style in the form.
+fn start_lookup(draft: &Rc<CreateDraft>) {
+    let Some(form) = draft.form() else {
+        return;
+    };
+    let Ok(url) = draft_url(&form.url_entry.text()) else {
+        return;
+    };
+    let generation = draft.generation.get();
+    let icon_dir = draft.staging.join(generation.to_string());
+    // A name the user typed is kept for the icon; a previous suggestion is not.
+    let typed = form.name_entry.text().trim().to_string();
+    let name = (!typed.is_empty() && draft.suggested_name.borrow().as_deref() != Some(&typed))
+        .then_some(typed);
+    let style = normalization_for(selected_icon_style(form.icon_style_dropdown.selected()));
+    let cancelled = Arc::clone(&draft.cancelled.borrow());
+    let (sender, receiver) = async_channel::bounded::<SiteLookup>(1);
+    thread::spawn(move || {
+        let _ = sender.send_blocking(look_up_site(url, name, style, icon_dir, &cancelled));
+    });
+    let draft = Rc::clone(draft);
+    glib::MainContext::default().spawn_local(async move {
+        if let Ok(lookup) = receiver.recv().await {
+            lookup_finished(&draft, generation, lookup);
+        }
+    });
+}
+
+/// Fetches the site's name and manifest and renders its icon into `icon_dir`. Blocking,
+/// so call it from a worker thread. An unreachable site still gets a generated icon.
+/// Once `cancelled` is set, no further downloads start and no icon is rendered.
+fn look_up_site(
+    url: Url,
+    name: Option<String>,
+    style: Normalization,
+    icon_dir: PathBuf,
+    cancelled: &Arc<AtomicBool>,
+) -> SiteLookup {
+    let info_fetcher = default_fetcher();
+    let fetcher = Cancellable::new(&info_fetcher, Arc::clone(cancelled));
+    let (info, error) = match fetch_site_info_with(&fetcher, &url) {
+        Ok(info) => (info, None),
+        Err(err) => {
+            tracing::debug!(target: "icons", "site lookup failed: {err:?}");
+            (SiteInfo::default(), Some(format!("{err:#}")))
+        }
+    };
+    let icon_name = name
+        .or_else(|| info.name.clone())
+        .unwrap_or_else(|| url.host_str().unwrap_or_default().to_string());
+    if !cancelled.load(Ordering::Relaxed) {
+        // The icon downloads get a budget of their own; the page is not loaded again.
+        let icon_fetcher = default_fetcher();
+        let fetcher = Cancellable::new(&icon_fetcher, Arc::clone(cancelled));
+        let icon = if error.is_none() {
+            cache_icon_for_site_with(
+                &fetcher,
+                &info,
+                &url,
+                &icon_name,
+                DRAFT_ICON_ID,
+                &icon_dir,
+                style,
+            )
+        } else {
+            generate_fallback_icon(&url, &icon_name, DRAFT_ICON_ID, &icon_dir)
+        };
+        if let Err(err) = icon {
+            tracing::warn!(target: "icons", "draft icon failed: {err:?}");
+        }
+    }
+    let icon_generated =
+        IconRecord::load(&icon_dir, DRAFT_ICON_ID).is_some_and(|record| record.url.is_none());
+    SiteLookup {
+        url,
+        suggested_name: info.name,
+        manifest: info.manifest,
+        icon_dir,
+        icon_name,
+        icon_generated,
+        error,
+    }
+}
+
+/// Shows a lookup's results in the dialog, or finishes the app created while it ran.
+fn lookup_finished(draft: &Rc<CreateDraft>, generation: u64, lookup: SiteLookup) {
+    if generation != draft.generation.get() {
+        let _ = fs::remove_dir_all(&lookup.icon_dir);
+        return;
+    }
+    draft.looking_up.set(false);
+    if draft.created.get().is_some() {
+        finish_create(draft, lookup);
+        return;
+    }
+    let Some(form) = draft.form() else {
+        let _ = fs::remove_dir_all(&draft.staging);
+        return;
+    };
+
+    form.spinner.stop();
+    let host = lookup.url.host_str().unwrap_or_default();
+    match &lookup.error {
+        Some(err) => {
+            form.status_label.add_css_class("warning");
+            form.status_label.set_label(&format!(
+                "Couldn't reach {host}. The app can still be created with a generated icon."
+            ));
+            form.status_label.set_tooltip_text(Some(err.as_str()));
+        }
+        None => {
+            form.status_label.set_label("");
+            form.status_label.set_tooltip_text(None);
+        }
+    }
+
+    let preview_path = lookup.icon_dir.join(format!("{DRAFT_ICON_ID}-128x128.png"));
+    match gdk::Texture::from_file(&gio::File::for_path(&preview_path)) {
+        Ok(texture) => form.preview.set_paintable(Some(&texture)),
+        Err(_) => form.preview.set_icon_name(Some("applications-internet")),
+    }
+
+    let current = form.name_entry.text();
+    let untouched = current.trim().is_empty()
+        || draft.suggested_name.borrow().as_deref() == Some(current.as_str());
+    if untouched {
+        form.name_entry
+            .set_text(lookup.suggested_name.as_deref().unwrap_or_default());
+    }
+    draft.suggested_name.replace(lookup.suggested_name.clone());
+
+    if let Some(previous) = draft.lookup.replace(Some(lookup)) {
+        let _ = fs::remove_dir_all(previous.icon_dir);
+    }
+}
+
+/// Saves the app right away so it shows up in the list; its icon, shortcuts and
+/// launcher follow once the site lookup is done.
+fn handle_create(
+    draft: &Rc<CreateDraft>,
     open_external_switch: &gtk::Switch,
     show_nav_switch: &gtk::Switch,
 ) -> Result<()> {
-    let url_text = url_entry.text().trim().to_string();
-    if url_text.is_empty() {
-        bail!("Please enter a URL");
+    let Some(form) = draft.form() else {
+        bail!("the dialog is closed");
+    };
+    let parsed = draft_url(&form.url_entry.text())?;
+    let mut name = form.name_entry.text().trim().to_string();
+    if name.is_empty() {
+        name = parsed
+            .host_str()
+            .map(|h| h.to_string())
+            .unwrap_or_else(|| "Web App".to_string());
+    }
+    let mut app_def = WebAppDefinition::new(name, parsed);
+    app_def.behavior.open_external_links = open_external_switch.state();
+    app_def.behavior.show_navigation = show_nav_switch.state();
+    app_def.icon_style = selected_icon_style(form.icon_style_dropdown.selected());
+
+    let handlers = &draft.handlers;
+    handlers.ctx.registry.save(&app_def)?;
+    handlers.apps.borrow_mut().push(app_def.clone());
+    select_app(handlers, app_def.id);
+    draft.created.set(Some(app_def.id));
+
+    // Clicking Create before typing settles starts the lookup now.
+    if let Some(source) = draft.pending.take() {
+        source.remove();
+        start_lookup(draft);
+    } else if !draft.looking_up.get() {
+        match draft.lookup.take() {
+            Some(lookup) => finish_create(draft, lookup),
+            None => start_lookup(draft),
+        }
+    }
+    Ok(())
+}
+
+/// Moves the lookup's icon into place for the created app, seeds shortcuts and the
+/// accent from the manifest, and installs the launcher.
+fn finish_create(draft: &Rc<CreateDraft>, lookup: SiteLookup) {
+    let handlers = draft.handlers.clone();
+    let Some(app) = draft.created.get().and_then(|app_id| {
+        handlers
+            .apps
+            .borrow()
+            .iter()
+            .find(|a| a.id == app_id)
+            .cloned()
+    }) else {
+        let _ = fs::remove_dir_all(&draft.staging);
+        return;
+    };
+    let staging = draft.staging.clone();
+    let paths = handlers.ctx.paths.clone();
+    let (sender, receiver) = async_channel::bounded::<SiteMetadata>(1);
+    thread::spawn(move || {
+        if let Err(err) = adopt_draft_icon(&lookup, &app, &paths.icons_cache_dir()) {
+            tracing::warn!(target: "icons", app = %app.id, "caching the new app's icon failed: {err:?}");
+        }
+        let _ = fs::remove_dir_all(staging);
+        let _ = sender.send_blocking(site_metadata(
+            lookup.manifest.as_ref(),
+            &accent_icon_path(&paths, &app.icon_id),
+        ));
+    });
+
+    let app_id = app.id;
+    glib::MainContext::default().spawn_local(async move {
+        let metadata = receiver.recv().await.unwrap_or_default();
+        if let Some(app) = handlers
+            .apps
+            .borrow_mut()
+            .iter_mut()
+            .find(|a| a.id == app_id)
+        {
+            if app.shortcuts.is_empty() {
+                app.shortcuts = metadata.shortcuts;
+            }
+            if app.accent_color.is_none() {
+                app.accent_color = metadata.accent_color;
+            }
+        }
+        // Saves the app and redraws its row with the new icon.
+        sync_launcher(&handlers, app_id).await;
+    });
+}
+
+/// Renames the draft icon files to `app`'s icon id. A generated icon is redrawn when
+/// the app was named differently from what the lookup drew.
+fn adopt_draft_icon(lookup: &SiteLookup, app: &WebAppDefinition, cache_dir: &Path) -> Result<()> {
+    if lookup.icon_generated && lookup.icon_name != app.name {
+        generate_fallback_icon(&lookup.url, &app.name, &app.icon_id, cache_dir)?;
+        return Ok(());
+    }
+    fs::create_dir_all(cache_dir)?;
+    for entry in fs::read_dir(&lookup.icon_dir).context("read draft icons")? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(suffix) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(DRAFT_ICON_ID))
+        else {
+            continue;
+        };
+        let target = cache_dir.join(format!("{}{suffix}", app.icon_id));
+        fs::rename(entry.path(), &target).with_context(|| format!("move icon to {target:?}"))?;
+    }
+    Ok(())
+}
+
+/// Reads `app`'s permissions from disk into fresh groups on its detail page.
+fn load_permission_groups(
+    handlers: &Handlers,
+    page: &adw::PreferencesPage,
+    groups: &Rc<RefCell<Vec<adw::PreferencesGroup>>>,
+    app: &WebAppDefinition,
+) {
+    match handlers.ctx.permissions.load(app.id) {
+        Ok(mut store) => {
+            store.get_or_default_mut(&app.primary_origin);
+            rebuild_permission_groups(
+                page,
+                groups,
+                &Rc::new(RefCell::new(store)),
+                &handlers.ctx.permissions,
+                app.id,
+            );
+        }
+        Err(err) => tracing::error!(target: "ui", "load permissions failed: {err:?}"),
+    }
+}
+
+/// Re-reads the shown app's permissions, which its shell may have saved since the
+/// page was built; the rows would otherwise write the old store back.
+fn refresh_detail_permissions(handlers: &Handlers) {
+    let Some(detail) = handlers.detail.borrow().clone() else {
+        return;
+    };
+    if let Some(app) = find_app(handlers, detail.app_id) {
+        load_permission_groups(handlers, &detail.page, &detail.permission_groups, &app);
+    }
+}
+
+/// Replaces the permission groups on a detail page (tracked in `groups`) with one group
+/// per origin in `store`, followed by the group for adding an origin.
+fn rebuild_permission_groups(
+    page: &adw::PreferencesPage,
+    groups: &Rc<RefCell<Vec<adw::PreferencesGroup>>>,
+    store: &Rc<RefCell<sitewrap_model::PermissionStore>>,
+    repo: &sitewrap_model::PermissionRepository,
+    app_id: WebAppId,
+) {
+    for group in groups.borrow_mut().drain(..) {
+        page.remove(&group);
+    }
+
+    let mut origins: Vec<(String, sitewrap_model::PerOriginPermissions)> = store
+        .borrow()
+        .origins
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    origins.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (origin, snapshot) in origins.into_iter() {
+        let group = adw::PreferencesGroup::builder()
+            .title(origin.as_str())
+            .build();
+        add_permission_row(
+            &group,
+            "Notifications",
+            snapshot.notifications,
+            PermissionField::Notifications,
+            Rc::clone(store),
+            origin.clone(),
+            repo.clone(),
+            app_id,
+        );
+        add_permission_row(
+            &group,
+            "Camera",
+            snapshot.camera,
+            PermissionField::Camera,
+            Rc::clone(store),
+            origin.clone(),
+            repo.clone(),
+            app_id,
+        );
+        add_permission_row(
+            &group,
+            "Microphone",
+            snapshot.microphone,
+            PermissionField::Microphone,
+            Rc::clone(store),
+            origin.clone(),
+            repo.clone(),
+            app_id,
+        );
+        add_permission_row(
+            &group,
+            "Location",
+            snapshot.location,
+            PermissionField::Location,
+            Rc::clone(store),
+            origin,
+            repo.clone(),
+            app_id,
+        );
+        page.add(&group);
+        groups.borrow_mut().push(group);
+    }
+
+    // Weak, as the groups hold this callback; the detail view owns them.
+    let page_weak = page.downgrade();
+    let groups_weak = Rc::downgrade(groups);
+    let store_clone = Rc::clone(store);
+    let repo_clone = repo.clone();
+    let origin_group = origin_group(move |origin| {
+        store_clone.borrow_mut().get_or_default_mut(origin);
+        if let Err(err) = repo_clone.save(app_id, &store_clone.borrow()) {
+            tracing::error!(target: "ui", "save permissions failed: {err:?}");
+        }
+        if let (Some(page), Some(groups)) = (page_weak.upgrade(), groups_weak.upgrade()) {
+            rebuild_permission_groups(&page, &groups, &store_clone, &repo_clone, app_id);
+        }
+    });
+    page.add(&origin_group);
+    groups.borrow_mut().push(origin_group);
+}
+
+/// Installs or updates the launcher, keeping manually exported launchers in their folder.
+async fn refresh_launcher(
+    portals: &dyn Portals,
+    previous: &LauncherStatus,
+    descriptor: &LauncherDescriptor,
+) -> LauncherStatus {
+    if let LauncherStatus::Exported { path } = previous {
+        if let Some(dir) = path.parent().and_then(|p| p.parent()) {
+            return match export_launcher(descriptor, dir) {
+                Ok(exported) => LauncherStatus::Exported {
+                    path: exported.desktop_file,
+                },
+                Err(err) => {
+                    tracing::warn!(target: "ui", "re-export launcher failed: {err:?}");
+                    LauncherStatus::Failed {
+                        error: format!("{err:#}"),
+                    }
+                }
+            };
+        }
     }
-    let parsed = normalize_url(&url_text)?;
-    let mut name = name_entry.text().trim().to_string();
-    if name.is_empty() {
-        name = parsed
-            .host_str()
-            .map(|h| h.to_string())
-            .unwrap_or_else(|| "Web App".to_string());
+    match portals.install_launcher(descriptor).await {
+        Ok(()) => LauncherStatus::Installed,
+        Err(err) => {
+            tracing::warn!(target: "portal", "install launcher failed: {err:?}");
+            LauncherStatus::Failed {
+                error: format!("{err:#}"),
+            }
+        }
     }
+}
 
-    let mut apps_mut = handlers.apps.borrow_mut();
-    let Some(app) = apps_mut.iter_mut().find(|a| a.id == app_id) else {
-        bail!("app not found")
-    };
-    let url_changed = app.start_url != parsed.to_string();
-    app.name = name.clone();
-    app.start_url = parsed.to_string();
-    app.primary_origin = parsed.origin().ascii_serialization();
-    app.behavior.open_external_links = open_external_switch.state();
-    app.behavior.show_navigation = show_nav_switch.state();
+/// Re-checks the app's icon on a worker thread and reinstalls the launcher if it changed.
+fn run_icon_refresh(handlers: &Handlers, app: &WebAppDefinition) {
+    let handlers = handlers.clone();
+    let app = app.clone();
+    let app_id = app.id;
+    glib::MainContext::default().spawn_local(async move {
+        match check_icon(&handlers.ctx.paths, &app, Arc::default()).await {
+            Ok(true) => {
+                sync_launcher(&handlers, app_id).await;
+                show_message(
+                    &handlers,
+                    "Icon updated",
+                    "The site has a new icon; the launcher now uses it.",
+                );
+            }
+            Ok(false) => show_message(
+                &handlers,
+                "Icon is up to date",
+                "The site still uses the same icon.",
+            ),
+            Err(err) => {
+                tracing::warn!(target: "ui", app = %app_id, "icon refresh failed: {err:?}");
+                show_message(&handlers, "Icon refresh failed", &format!("{err:#}"));
+            }
+        }
+    });
+}
 
-    handlers.ctx.registry.save(app)?;
-    drop(apps_mut);
-    refresh_current(handlers);
+/// Runs [`refresh_app_icon_with`] on a worker thread; setting `cancelled` stops its
+/// downloads.
+async fn check_icon(
+    paths: &AppPaths,
+    app: &WebAppDefinition,
+    cancelled: Arc<AtomicBool>,
+) -> Result<bool> {
+    let (sender, receiver) = async_channel::bounded::<Result<bool>>(1);
+    let paths = paths.clone();
+    let app = app.clone();
+    thread::spawn(move || {
+        let fetcher = default_fetcher();
+        let fetcher = Cancellable::new(&fetcher, cancelled);
+        let _ = sender.send_blocking(refresh_app_icon_with(&fetcher, &app, &paths));
+    });
+    receiver.recv().await.context("icon worker stopped")?
+}
 
-    // background: refetch icon if URL changed, reinstall launcher with new metadata
-    let app_clone = handlers
+/// Re-checks icons not checked within [`ICON_REFRESH_INTERVAL`], one app at a time, and
+/// reinstalls the launchers of those that changed.
+fn refresh_stale_icons(handlers: &Handlers) {
+    let cache_dir = handlers.ctx.paths.icons_cache_dir();
+    let stale: Vec<_> = handlers
         .apps
         .borrow()
         .iter()
-        .find(|a| a.id == app_id)
+        .filter(|app| {
+            !matches!(app.icon_source, IconSource::Custom)
+                && needs_refresh(&cache_dir, &app.icon_id, ICON_REFRESH_INTERVAL)
+        })
         .cloned()
-        .unwrap();
+        .collect();
+    if stale.is_empty() {
+        return;
+    }
+    let (sender, receiver) = async_channel::unbounded::<WebAppId>();
     let paths = handlers.ctx.paths.clone();
     thread::spawn(move || {
-        if url_changed {
-            if let Ok(url) = Url::parse(&app_clone.start_url) {
-                let _ = fetch_and_cache_icon(&url, &app_clone.icon_id, &paths.icons_cache_dir());
+        for app in stale {
+            match refresh_app_icon(&app, &paths) {
+                Ok(true) => {
+                    let _ = sender.send_blocking(app.id);
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    tracing::warn!(target: "ui", app = %app.id, "icon refresh failed: {err:?}")
+                }
             }
         }
-        let descriptor = launcher_descriptor_for(&app_clone, &paths);
-        let _ = install_launcher(&descriptor);
     });
-
-    Ok(())
+    let handlers = handlers.clone();
+    glib::MainContext::default().spawn_local(async move {
+        while let Ok(app_id) = receiver.recv().await {
+            sync_launcher(&handlers, app_id).await;
+        }
+    });
 }
 
-fn refresh_current(handlers: &Handlers) {
-    let filter = handlers.search_entry.text();
-    refresh_listbox(&handlers.list, &handlers.apps.borrow(), &filter, handlers);
+/// Brings the app's launcher in line with its current definition and records the result,
+/// which is also returned. `None` when the app is gone.
+async fn sync_launcher(handlers: &Handlers, app_id: WebAppId) -> Option<LauncherStatus> {
+    let app = handlers
+        .apps
+        .borrow()
+        .iter()
+        .find(|a| a.id == app_id)
+        .cloned()?;
+    let descriptor = launcher_descriptor_for(&app, &handlers.ctx.paths);
+    let status = refresh_launcher(handlers.ctx.portals.as_ref(), &app.launcher, &descriptor).await;
+    apply_launcher_status(handlers, app_id, status.clone());
+    Some(status)
 }
 
-fn open_create_window(
-    app: &adw::Application,
-    parent: &adw::ApplicationWindow,
-    handlers: Handlers,
-) -> Result<()> {
-    let win = adw::Window::builder()
-        .application(app)
-        .transient_for(parent)
-        .modal(true)
-        .title("Create Web App")
-        .default_width(420)
-        .default_height(260)
-        .build();
-
-    let container = gtk::Box::builder()
-        .orientation(gtk::Orientation::Vertical)
-        .spacing(12)
-        .margin_top(18)
-        .margin_bottom(18)
-        .margin_start(18)
-        .margin_end(18)
-        .build();
-
-    let url_entry = gtk::Entry::builder()
-        .placeholder_text("https://example.com")
-        .input_purpose(gtk::InputPurpose::Url)
-        .build();
-    let name_entry = gtk::Entry::builder().placeholder_text("App name").build();
+/// Asks the Background portal for what `app` needs now. Turning start at login off
+/// re-requests without autostart, which removes the autostart entry. Returns a message
+/// when the desktop granted less than requested.
+async fn sync_background(
+    portals: &dyn Portals,
+    app: &WebAppDefinition,
+) -> Result<Option<&'static str>> {
+    let grant = portals
+        .request_background(&background_request_for(app))
+        .await?;
+    if app.behavior.start_at_login && !grant.autostart {
+        return Ok(Some(
+            "Starting at login was not allowed by the desktop. You can change this in the system's app settings.",
+        ));
+    }
+    if app.behavior.run_in_background && !grant.run_in_background {
+        return Ok(Some(
+            "Running in the background was not allowed; the app will quit when its window is closed.",
+        ));
+    }
+    Ok(None)
+}
 
-    let open_external_switch = gtk::Switch::builder().active(true).build();
-    let show_nav_switch = gtk::Switch::builder().active(false).build();
-    let error_label = gtk::Label::builder()
-        .xalign(0.0)
-        .css_classes(["error"])
-        .wrap(true)
-        .build();
+/// Records a launcher install result on the app and refreshes the list.
+fn apply_launcher_status(handlers: &Handlers, app_id: WebAppId, status: LauncherStatus) {
+    {
+        let mut apps = handlers.apps.borrow_mut();
+        let Some(app) = apps.iter_mut().find(|a| a.id == app_id) else {
+            return;
+        };
+        app.launcher = status;
+        if let Err(err) = handlers.ctx.registry.save(app) {
+            tracing::error!(target: "ui", "save launcher status failed: {err:?}");
+        }
+    }
+    refresh_current(handlers);
+}
 
-    let button_row = gtk::Box::builder()
-        .orientation(gtk::Orientation::Horizontal)
-        .spacing(6)
-        .halign(gtk::Align::End)
-        .build();
-    let cancel_btn = gtk::Button::with_label("Cancel");
-    let create_btn = gtk::Button::with_label("Create");
-    create_btn.add_css_class("suggested-action");
-    button_row.append(&cancel_btn);
-    button_row.append(&create_btn);
+/// Manual fallback for desktops without the DynamicLauncher portal: asks for a folder
+/// (through the FileChooser portal when available) and writes the launcher there.
+fn export_launcher_interactive(handlers: &Handlers, app: &WebAppDefinition) {
+    let handlers = handlers.clone();
+    let app = app.clone();
+    glib::MainContext::default().spawn_local(async move {
+        let dir = pick_export_dir(&handlers, "Export Launcher").await;
+        let descriptor = launcher_descriptor_for(&app, &handlers.ctx.paths);
+        let result = dir.and_then(|dir| {
+            dir.map(|dir| export_launcher(&descriptor, &dir))
+                .transpose()
+        });
+        match result {
+            Ok(Some(exported)) => {
+                show_message(
+                    &handlers,
+                    "Launcher exported",
+                    &format!(
+                        "Saved {}. If it does not appear in your app grid, copy the folder's contents into ~/.local/share.",
+                        exported.desktop_file.display()
+                    ),
+                );
+                apply_launcher_status(
+                    &handlers,
+                    app.id,
+                    LauncherStatus::Exported {
+                        path: exported.desktop_file,
+                    },
+                );
+            }
+            Ok(None) => {}
+            Err(err) => {
+                tracing::error!(target: "ui", "export launcher failed: {err:?}");
+                show_message(&handlers, "Export failed", &format!("{err:#}"));
+            }
+        }
+    });
+}
 
-    container.append(&gtk::Label::builder().label("URL").xalign(0.0).build());
-    container.append(&url_entry);
-    container.append(&gtk::Label::builder().label("Name").xalign(0.0).build());
-    container.append(&name_entry);
+/// Asks for a folder to export launchers to, through the FileChooser portal when available.
+async fn pick_export_dir(handlers: &Handlers, title: &str) -> Result<Option<PathBuf>> {
+    if handlers
+        .ctx
+        .portals
+        .capabilities()
+        .await
+        .has(PortalKind::FileChooser)
+    {
+        handlers.ctx.portals.pick_directory(title).await
+    } else {
+        pick_directory_native(&handlers.window, title).await
+    }
+}
 
-    let open_external_row = gtk::Box::builder()
-        .orientation(gtk::Orientation::Horizontal)
-        .spacing(6)
-        .hexpand(true)
-        .build();
-    open_external_row.append(
-        &gtk::Label::builder()
-            .label("Open external links in default browser")
-            .xalign(0.0)
-            .hexpand(true)
-            .build(),
+/// Folder picker for hosts without the FileChooser portal.
+async fn pick_directory_native(
+    parent: &adw::ApplicationWindow,
+    title: &str,
+) -> Result<Option<PathBuf>> {
+    let chooser = gtk::FileChooserNative::new(
+        Some(title),
+        Some(parent),
+        gtk::FileChooserAction::SelectFolder,
+        Some("Export"),
+        Some("Cancel"),
     );
-    open_external_row.append(&open_external_switch);
+    // Exporting into the data dir installs the launcher for the current user.
+    let _ = chooser.set_current_folder(Some(&gio::File::for_path(glib::user_data_dir())));
+    let (sender, receiver) = async_channel::bounded::<Option<PathBuf>>(1);
+    chooser.connect_response(move |chooser, response| {
+        let dir = if response == gtk::ResponseType::Accept {
+            chooser.file().and_then(|file| file.path())
+        } else {
+            None
+        };
+        let _ = sender.try_send(dir);
+    });
+    chooser.show();
+    // Holding `chooser` across the await keeps the native dialog alive.
+    let dir = receiver.recv().await.ok().flatten();
+    chooser.destroy();
+    Ok(dir)
+}
 
-    let show_nav_row = gtk::Box::builder()
-        .orientation(gtk::Orientation::Horizontal)
-        .spacing(6)
-        .hexpand(true)
+fn show_message(handlers: &Handlers, heading: &str, body: &str) {
+    let dialog = adw::MessageDialog::builder()
+        .transient_for(&handlers.window)
+        .heading(heading)
+        .body(body)
         .build();
-    show_nav_row.append(
-        &gtk::Label::builder()
-            .label("Show navigation controls")
-            .xalign(0.0)
-            .hexpand(true)
-            .build(),
-    );
-    show_nav_row.append(&show_nav_switch);
-
-    container.append(&open_external_row);
-    container.append(&show_nav_row);
-    container.append(&error_label);
-    container.append(&button_row);
-    win.set_content(Some(&container));
-
-    cancel_btn.connect_clicked(glib::clone!(@weak win => move |_| win.close()));
+    dialog.add_response("close", "OK");
+    dialog.set_default_response(Some("close"));
+    dialog.set_close_response("close");
+    dialog.connect_response(None, |d, _| d.close());
+    dialog.present();
+}
 
-    let submitting = std::rc::Rc::new(std::cell::Cell::new(false));
-    create_btn.connect_clicked(glib::clone!(@weak win, @weak url_entry, @weak name_entry, @weak open_external_switch, @weak show_nav_switch, @weak error_label, @strong handlers, @strong submitting => move |_| {
-        if submitting.get() {
-            return;
-        }
-        submitting.set(true);
-        if let Err(err) = handle_create(&handlers, &url_entry, &name_entry, &open_external_switch, &show_nav_switch) {
-            error_label.set_label(&format!("{err}"));
-            submitting.set(false);
-            return;
-        }
-        win.close();
-    }));
+/// [`site_metadata`] for the manifest the page at `start_url` links to.
+fn fetch_site_metadata(start_url: &Url, icon_path: &Path) -> SiteMetadata {
+    let manifest = fetch_manifest(start_url).unwrap_or_else(|err| {
+        tracing::debug!(target: "icons", "manifest fetch failed: {err:?}");
+        None
+    });
+    site_metadata(manifest.as_ref(), icon_path)
+}
 
-    // Avoid double submit via Enter by not setting a default widget and guarding re-entry.
+/// Shortcuts declared in the site's web app manifest, and an accent from its
+/// `theme_color` or, failing that, from the cached icon at `icon_path`.
+fn site_metadata(manifest: Option<&(Url, WebManifest)>, icon_path: &Path) -> SiteMetadata {
+    let shortcuts = manifest
+        .map(|(manifest_url, manifest)| {
+            manifest
+                .resolved_shortcuts(manifest_url)
+                .into_iter()
+                .map(|(label, url)| AppShortcut {
+                    label,
+                    url: url.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let theme_color = manifest.and_then(|(_, manifest)| manifest.theme_color.as_deref());
+    SiteMetadata {
+        shortcuts,
+        accent_color: pick_accent(theme_color, icon_path).map(Rgb::to_hex),
+    }
+}
 
-    win.present();
-    Ok(())
+/// The icon rendition sampled for the accent; small enough to decode quickly.
+fn accent_icon_path(paths: &AppPaths, icon_id: &str) -> PathBuf {
+    paths.icons_cache_dir().join(format!("{icon_id}-64x64.png"))
 }
 
-fn open_permissions_window_manager(handlers: &Handlers, app: &WebAppDefinition) -> Result<()> {
-    let mut store = handlers
-        .ctx
-        .permissions
-        .load(app.id)
-        .context("load permissions")?;
-    store.get_or_default_mut(&app.primary_origin);
+fn rgba_to_hex(rgba: &gdk::RGBA) -> String {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb(
+        channel(rgba.red()),
+        channel(rgba.green()),
+        channel(rgba.blue()),
+    )
+    .to_hex()
+}
 
+fn open_privacy_dashboard(handlers: &Handlers) -> Result<()> {
     let window = adw::PreferencesWindow::builder()
         .transient_for(&handlers.window)
         .modal(true)
-        .title(format!("Permissions - {}", app.name))
-        .default_width(520)
-        .default_height(480)
+        .title("Privacy Dashboard")
+        .default_width(600)
+        .default_height(560)
+        .search_enabled(false)
+        .build();
+    let page = adw::PreferencesPage::builder()
+        .title("Recent Activity")
+        .icon_name("security-high-symbolic")
         .build();
-    let page = adw::PreferencesPage::builder().title("Permissions").build();
-
-    let store_rc = Rc::new(RefCell::new(store));
-    let repo = handlers.ctx.permissions.clone();
-    let app_id = app.id;
 
-    fn rebuild_page(
-        page: &adw::PreferencesPage,
-        store: &Rc<RefCell<sitewrap_model::PermissionStore>>,
-        repo: &sitewrap_model::PermissionRepository,
-        app_id: WebAppId,
-    ) {
-        let mut child_opt = page.first_child();
-        while let Some(child) = child_opt {
-            let next = child.next_sibling();
-            if let Some(group) = child.downcast_ref::<adw::PreferencesGroup>() {
-                page.remove(group);
+    let since = OffsetDateTime::now_utc() - Duration::days(PRIVACY_WINDOW_DAYS);
+    let mut apps = handlers.apps.borrow().clone();
+    apps.sort_by_key(|a| a.name.to_lowercase());
+
+    let mut any_activity = false;
+    for app in apps.iter() {
+        let entries = match handlers.ctx.audit.read(app.id) {
+            Ok(entries) => entries,
+            Err(err) => {
+                tracing::warn!(target: "audit", app = %app.id, "read audit log failed: {err:?}");
+                continue;
             }
-            child_opt = next;
+        };
+        let summary = AuditLog::summarize(&entries, since);
+        if summary.is_empty() {
+            continue;
         }
+        any_activity = true;
 
-        let mut origins: Vec<(String, sitewrap_model::PerOriginPermissions)> = store
-            .borrow()
-            .origins
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect();
-        origins.sort_by(|a, b| a.0.cmp(&b.0));
-
-        for (origin, snapshot) in origins.into_iter() {
-            let group = adw::PreferencesGroup::builder()
-                .title(origin.as_str())
+        let group = adw::PreferencesGroup::builder()
+            .title(app.name.as_str())
+            .description(format!("Last {PRIVACY_WINDOW_DAYS} days"))
+            .build();
+        let edit_btn = gtk::Button::builder()
+            .label("Permissions")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+        let handlers_edit = handlers.clone();
+        let app_id = app.id;
+        edit_btn.connect_clicked(glib::clone!(@weak window => move |_| {
+            window.close();
+            select_app(&handlers_edit, app_id);
+        }));
+        group.set_header_suffix(Some(&edit_btn));
+
+        for activity in summary {
+            let row = adw::ExpanderRow::builder()
+                .title(activity.origin.as_str())
+                .subtitle(format!(
+                    "{} requests · {} prompts · {} allowed · {} denied · {}",
+                    activity.requests,
+                    activity.prompts,
+                    activity.allowed,
+                    activity.denied,
+                    activity
+                        .last_activity
+                        .map(format_relative)
+                        .unwrap_or_else(|| "Never".to_string())
+                ))
                 .build();
-            add_permission_row(
-                &group,
-                "Notifications",
-                snapshot.notifications,
-                PermissionField::Notifications,
-                Rc::clone(store),
-                origin.clone(),
-                repo.clone(),
-                app_id,
-            );
-            add_permission_row(
-                &group,
-                "Camera",
-                snapshot.camera,
-                PermissionField::Camera,
-                Rc::clone(store),
-                origin.clone(),
-                repo.clone(),
-                app_id,
-            );
-            add_permission_row(
-                &group,
-                "Microphone",
-                snapshot.microphone,
-                PermissionField::Microphone,
-                Rc::clone(store),
-                origin.clone(),
-                repo.clone(),
-                app_id,
-            );
-            add_permission_row(
-                &group,
-                "Location",
-                snapshot.location,
-                PermissionField::Location,
-                Rc::clone(store),
-                origin,
-                repo.clone(),
-                app_id,
-            );
-            page.add(&group);
-        }
-
-        let page_clone = page.clone();
-        let store_clone = Rc::clone(store);
-        let repo_clone = repo.clone();
-        add_origin_row(page.clone(), move |origin| {
-            store_clone.borrow_mut().get_or_default_mut(origin);
-            if let Err(err) = repo_clone.save(app_id, &store_clone.borrow()) {
-                tracing::error!(target: "ui", "save permissions failed: {err:?}");
+            let recent = entries
+                .iter()
+                .rev()
+                .filter(|e| e.origin == activity.origin && e.at >= since)
+                .take(PRIVACY_RECENT_EVENTS);
+            for entry in recent {
+                row.add_row(
+                    &adw::ActionRow::builder()
+                        .title(describe_audit_entry(entry))
+                        .subtitle(format_relative(entry.at))
+                        .build(),
+                );
             }
-            rebuild_page(&page_clone, &store_clone, &repo_clone, app_id);
-        });
+            group.add(&row);
+        }
+        page.add(&group);
     }
 
-    rebuild_page(&page, &store_rc, &repo, app_id);
+    if !any_activity {
+        let group = adw::PreferencesGroup::new();
+        group.add(
+            &adw::ActionRow::builder()
+                .title("No permission activity recorded")
+                .subtitle("Requests and decisions from your web apps will appear here")
+                .build(),
+        );
+        page.add(&group);
+    }
 
     window.add(&page);
     window.present();
     Ok(())
 }
 
-fn handle_create(
+/// Lists removed apps for restoring or deleting them for good, with the setting for how
+/// long they are kept.
+fn open_trash_window(handlers: &Handlers) {
+    let window = adw::PreferencesWindow::builder()
+        .transient_for(&handlers.window)
+        .modal(true)
+        .title("Recently Removed")
+        .default_width(560)
+        .default_height(520)
+        .search_enabled(false)
+        .build();
+    let page = adw::PreferencesPage::new();
+
+    let retention = adw::PreferencesGroup::new();
+    let purge_row = adw::SpinRow::with_range(0.0, 365.0, 1.0);
+    purge_row.set_title("Delete permanently after (days)");
+    purge_row.set_subtitle("0 keeps removed apps until you delete them");
+    purge_row.set_value(f64::from(handlers.settings.borrow().purge_after_days));
+    purge_row.connect_value_notify(glib::clone!(@strong handlers => move |row| {
+        handlers.settings.borrow_mut().purge_after_days = row.value() as u32;
+        save_settings(&handlers);
+    }));
+    retention.add(&purge_row);
+    page.add(&retention);
+
+    let list_group = Rc::new(RefCell::new(None));
+    rebuild_trash_group(handlers, &window, &page, &list_group);
+    // The new age applies once the window closes, not on every step of the spin row.
+    window.connect_close_request(
+        glib::clone!(@strong handlers, @strong list_group => move |_| {
+            // Frees the list, whose buttons hold `list_group`.
+            list_group.take();
+            purge_expired_trash(&handlers);
+            glib::Propagation::Proceed
+        }),
+    );
+
+    window.add(&page);
+    window.present();
+}
+
+/// Replaces the trash window's list of removed apps, tracked in `slot`.
+fn rebuild_trash_group(
     handlers: &Handlers,
-    url_entry: &gtk::Entry,
-    name_entry: &gtk::Entry,
-    open_external_switch: &gtk::Switch,
-    show_nav_switch: &gtk::Switch,
-) -> Result<()> {
-    let url_text = url_entry.text().trim().to_string();
-    if url_text.is_empty() {
-        bail!("Please enter a URL");
+    window: &adw::PreferencesWindow,
+    page: &adw::PreferencesPage,
+    slot: &Rc<RefCell<Option<adw::PreferencesGroup>>>,
+) {
+    if let Some(group) = slot.take() {
+        page.remove(&group);
     }
-    let parsed = normalize_url(&url_text)?;
-    let mut name = name_entry.text().trim().to_string();
-    if name.is_empty() {
-        name = parsed
-            .host_str()
-            .map(|h| h.to_string())
-            .unwrap_or_else(|| "Web App".to_string());
+    let trashed = handlers.ctx.trash.list().unwrap_or_else(|err| {
+        tracing::error!(target: "ui", "list trash failed: {err:?}");
+        Vec::new()
+    });
+    let group = adw::PreferencesGroup::builder()
+        .title("Removed Apps")
+        .build();
+
+    // The group's buttons hold this callback, and through it `slot`, which holds the
+    // group; the window empties `slot` when it closes.
+    let rebuild: Rc<dyn Fn()> = {
+        let handlers = handlers.clone();
+        let window = window.downgrade();
+        let page = page.downgrade();
+        let slot = Rc::clone(slot);
+        Rc::new(move || {
+            if let (Some(window), Some(page)) = (window.upgrade(), page.upgrade()) {
+                rebuild_trash_group(&handlers, &window, &page, &slot);
+            }
+        })
+    };
+
+    if trashed.is_empty() {
+        group.add(
+            &adw::ActionRow::builder()
+                .title("No recently removed apps")
+                .subtitle("Removed apps can be restored from here")
+                .build(),
+        );
+    } else {
+        let delete_all_btn = gtk::Button::builder()
+            .label("Delete All…")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat", "destructive-action"])
+            .build();
+        let ids: Vec<WebAppId> = trashed.iter().map(|entry| entry.app.id).collect();
+        delete_all_btn.connect_clicked(
+            glib::clone!(@strong handlers, @weak window, @strong rebuild => move |_| {
+                let handlers = handlers.clone();
+                let ids = ids.clone();
+                let rebuild = Rc::clone(&rebuild);
+                confirm_deletion(
+                    &window,
+                    &format!("Delete {} permanently?", app_count(ids.len())),
+                    "Their website data, permissions and icons cannot be recovered.",
+                    "Delete",
+                    move || {
+                        for id in &ids {
+                            if let Err(err) = handlers.ctx.trash.purge(*id) {
+                                tracing::error!(target: "ui", app = %id, "purge failed: {err:?}");
+                            }
+                        }
+                        rebuild();
+                    },
+                );
+            }),
+        );
+        group.set_header_suffix(Some(&delete_all_btn));
     }
-    let mut app_def = WebAppDefinition::new(name, parsed.clone());
-    app_def.behavior.open_external_links = open_external_switch.state();
-    app_def.behavior.show_navigation = show_nav_switch.state();
 
-    handlers.ctx.registry.save(&app_def)?;
-    handlers.apps.borrow_mut().push(app_def.clone());
-    refresh_current(handlers);
+    for entry in trashed {
+        let app_id = entry.app.id;
+        let row = adw::ActionRow::builder()
+            .title(entry.app.name.as_str())
+            .subtitle(format_relative(entry.removed_at))
+            .use_markup(false)
+            .build();
+        row.add_prefix(&icon_widget(&entry.app, &handlers.ctx.paths));
+        let restore_btn = gtk::Button::builder()
+            .label("Restore")
+            .valign(gtk::Align::Center)
+            .build();
+        let delete_btn = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text("Delete Permanently")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+        row.add_suffix(&restore_btn);
+        row.add_suffix(&delete_btn);
+
+        restore_btn.connect_clicked(
+            glib::clone!(@strong handlers, @weak window, @strong rebuild => move |_| {
+                let title = match restore_app(&handlers, app_id) {
+                    Ok(app) => format!("“{}” restored", app.name),
+                    Err(err) => {
+                        tracing::error!(target: "ui", "restore failed: {err:?}");
+                        format!("Restore failed: {err:#}")
+                    }
+                };
+                window.add_toast(adw::Toast::builder().title(title).use_markup(false).build());
+                rebuild();
+            }),
+        );
+        let name = entry.app.name.clone();
+        delete_btn.connect_clicked(
+            glib::clone!(@strong handlers, @weak window, @strong rebuild => move |_| {
+                let handlers = handlers.clone();
+                let rebuild = Rc::clone(&rebuild);
+                confirm_deletion(
+                    &window,
+                    &format!("Delete {name} permanently?"),
+                    "Its website data, permissions and icons cannot be recovered.",
+                    "Delete",
+                    move || {
+                        if let Err(err) = handlers.ctx.trash.purge(app_id) {
+                            tracing::error!(target: "ui", app = %app_id, "purge failed: {err:?}");
+                        }
+                        rebuild();
+                    },
+                );
+            }),
+        );
+        group.add(&row);
+    }
 
-    let paths = handlers.ctx.paths.clone();
-    let app_clone = app_def.clone();
-    thread::spawn(move || {
-        if let Ok(url) = Url::parse(&app_clone.start_url) {
-            let _ = fetch_and_cache_icon(&url, &app_clone.icon_id, &paths.icons_cache_dir());
+    page.add(&group);
+    slot.replace(Some(group));
+}
+
+fn describe_audit_entry(entry: &AuditEntry) -> String {
+    let permission = match entry.permission {
+        PermissionKind::Notifications => "Notifications",
+        PermissionKind::Camera => "Camera",
+        PermissionKind::Microphone => "Microphone",
+        PermissionKind::Location => "Location",
+    };
+    let action = match (entry.action, &entry.decision) {
+        (AuditAction::Requested, _) => "requested".to_string(),
+        (AuditAction::Prompted, _) => "prompted".to_string(),
+        (AuditAction::AutoAllowed, _) => "allowed automatically".to_string(),
+        (AuditAction::AutoDenied, _) => "denied automatically".to_string(),
+        (AuditAction::Decided, Some(PermissionState::Allow)) => "allowed by user".to_string(),
+        (AuditAction::Decided, Some(PermissionState::Block)) => "blocked by user".to_string(),
+        (AuditAction::Decided, _) => "dismissed".to_string(),
+    };
+    match &entry.note {
+        Some(note) => format!("{permission} {action} ({note})"),
+        None => format!("{permission} {action}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sitewrap_portal::mock::{MockPortals, PortalCall};
+
+    use super::*;
+
+    fn descriptor() -> LauncherDescriptor {
+        LauncherDescriptor {
+            desktop_id: "demo.desktop".into(),
+            name: "Demo".into(),
+            exec: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+            icon_name: "demo".into(),
+            ..Default::default()
         }
-        let descriptor = launcher_descriptor_for(&app_clone, &paths);
-        let _ = install_launcher(&descriptor);
-    });
+    }
 
-    Ok(())
+    #[test]
+    fn refresh_launcher_records_portal_result() {
+        let portals = MockPortals::new();
+        let ctx = glib::MainContext::new();
+
+        let status = ctx.block_on(refresh_launcher(
+            &portals,
+            &LauncherStatus::Unknown,
+            &descriptor(),
+        ));
+        assert_eq!(status, LauncherStatus::Installed);
+
+        portals.fail_with("portal missing");
+        let status = ctx.block_on(refresh_launcher(
+            &portals,
+            &LauncherStatus::Installed,
+            &descriptor(),
+        ));
+        assert!(
+            matches!(&status, LauncherStatus::Failed { error } if error.contains("portal missing"))
+        );
+        assert!(portals
+            .calls()
+            .iter()
+            .all(|call| matches!(call, PortalCall::InstallLauncher { .. })));
+        assert_eq!(portals.calls().len(), 2);
+    }
+
+    #[test]
+    fn refresh_launcher_reexports_manual_launchers() {
+        let dir = std::env::temp_dir().join(format!(
+            "sitewrap-app-export-{}",
+            OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+        let previous = LauncherStatus::Exported {
+            path: dir.join("applications/demo.desktop"),
+        };
+        let portals = MockPortals::new();
+
+        let status =
+            glib::MainContext::new().block_on(refresh_launcher(&portals, &previous, &descriptor()));
+        assert_eq!(status, previous);
+        assert!(dir.join("applications/demo.desktop").exists());
+        assert!(portals.calls().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sync_background_requests_autostart_shell() {
+        let portals = MockPortals::new();
+        let mut app =
+            WebAppDefinition::new("Chat".into(), Url::parse("https://chat.example").unwrap());
+        app.behavior.run_in_background = true;
+        app.behavior.start_at_login = true;
+
+        let warning = glib::MainContext::new()
+            .block_on(sync_background(&portals, &app))
+            .unwrap();
+        assert_eq!(warning, None);
+        let calls = portals.calls();
+        let [PortalCall::RequestBackground(request)] = calls.as_slice() else {
+            panic!("expected one background request");
+        };
+        assert!(request.autostart);
+        assert_eq!(
+            request.commandline,
+            vec![
+                "sitewrap".to_string(),
+                "--shell".to_string(),
+                app.id.to_string()
+            ]
+        );
+    }
 }
@@ -4,8 +4,10 @@ use adw::prelude::*;
 use gtk4 as gtk;
 use gtk4::glib;
 use sitewrap_model::{
-    PerOriginPermissions, PermissionRepository, PermissionState, PermissionStore, WebAppId,
+    PerOriginPermissions, PermissionKind, PermissionRepository, PermissionState, PermissionStore,
+    WebAppId,
 };
+use time::OffsetDateTime;
 use url::Url;
 
 #[derive(Clone, Copy)]
@@ -16,6 +18,17 @@ pub enum PermissionField {
     Location,
 }
 
+impl From<PermissionField> for PermissionKind {
+    fn from(field: PermissionField) -> Self {
+        match field {
+            PermissionField::Notifications => PermissionKind::Notifications,
+            PermissionField::Camera => PermissionKind::Camera,
+            PermissionField::Microphone => PermissionKind::Microphone,
+            PermissionField::Location => PermissionKind::Location,
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn add_permission_row(
     group: &adw::PreferencesGroup,
@@ -48,12 +61,22 @@ pub fn add_permission_row(
     };
     row.add_prefix(&gtk::Image::from_icon_name(icon_name));
 
+    let expires_at = store
+        .borrow()
+        .origins
+        .get(&origin)
+        .and_then(|entry| entry.expires.get(field.into()));
+    if let Some(expires_at) = expires_at {
+        row.set_subtitle(&format_expiry(expires_at));
+    }
+
     row.set_selected(permission_state_to_index(current));
     row.connect_selected_notify(move |row| {
         let state = index_to_permission_state(row.selected());
         let mut store = store.borrow_mut();
         let entry = store.get_or_default_mut(&origin);
         set_permission_field(entry, field, state);
+        row.set_subtitle("");
         if let Err(err) = repo.save(app_id, &store) {
             tracing::error!(target: "ui", "save permissions failed: {err:?}");
         }
@@ -62,6 +85,11 @@ pub fn add_permission_row(
 }
 
 pub fn add_origin_row(page: adw::PreferencesPage, build_group: impl Fn(&str) + 'static) {
+    page.add(&origin_group(build_group));
+}
+
+/// The "Add Origin" group; `build_group` receives the origin of each entered URL.
+pub fn origin_group(build_group: impl Fn(&str) + 'static) -> adw::PreferencesGroup {
     let group = adw::PreferencesGroup::builder()
         .title("Add Origin")
         .description("Allow additional websites to request permissions")
@@ -110,20 +138,40 @@ pub fn add_origin_row(page: adw::PreferencesPage, build_group: impl Fn(&str) + '
 
     row.add_suffix(&button);
     group.add(&row);
-    page.add(&group);
+    group
 }
 
+/// Manual edits are always permanent, so any pending expiry is dropped.
 fn set_permission_field(
     entry: &mut PerOriginPermissions,
     field: PermissionField,
     state: PermissionState,
 ) {
-    match field {
-        PermissionField::Notifications => entry.notifications = state,
-        PermissionField::Camera => entry.camera = state,
-        PermissionField::Microphone => entry.microphone = state,
-        PermissionField::Location => entry.location = state,
+    entry.set(field.into(), state);
+}
+
+fn format_expiry(expires_at: OffsetDateTime) -> String {
+    let remaining = expires_at - OffsetDateTime::now_utc();
+    if remaining.is_negative() {
+        return "Expired; will ask again".to_string();
+    }
+    if remaining.whole_hours() < 1 {
+        let mins = remaining.whole_minutes().max(1);
+        return format!(
+            "Expires in {} minute{}",
+            mins,
+            if mins == 1 { "" } else { "s" }
+        );
+    }
+    if remaining.whole_hours() < 48 {
+        let hours = remaining.whole_hours();
+        return format!(
+            "Expires in {} hour{}",
+            hours,
+            if hours == 1 { "" } else { "s" }
+        );
     }
+    format!("Expires on {}", expires_at.date())
 }
 
 fn permission_state_to_index(state: PermissionState) -> u32 {
@@ -1,4 +1,9 @@
-use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    rc::{Rc, Weak},
+};
 
 use adw::prelude::*;
 use anyhow::{anyhow, Context, Result};
@@ -6,16 +11,28 @@ use gdk4 as gdk;
 use gtk4 as gtk;
 use gtk4::gio;
 use gtk4::glib;
-use sitewrap_engine::{Engine, EngineConfig};
-use sitewrap_model::{PermissionState, PermissionStore, WebAppDefinition, WebAppId};
-use sitewrap_portal::{self, NotificationRequest};
-use time::OffsetDateTime;
+use sitewrap_engine::{Engine, EngineConfig, PageAppearance};
+use sitewrap_icons::Rgb;
+use sitewrap_model::{
+    AuditAction, AuditEntry, ColorSchemePreference, PermissionKind, PermissionState,
+    PermissionStore, SessionGrants, WebAppDefinition, WebAppId,
+};
+use sitewrap_portal::{NotificationRequest, PortalKind, Portals, SaveFileRequest};
+use time::{Duration, OffsetDateTime};
 use url::Url;
 
-use crate::{builder_from_resource, permissions_ui::*, AppContext};
+use crate::{
+    background_request_for, builder_from_resource, diagnostics::open_diagnostics_window,
+    permissions_ui::*, AppContext,
+};
 
 const SHELL_UI: &str = "/xyz/andriishafar/sitewrap/ui/shell_window.ui";
 
+thread_local! {
+    /// Shells with a window in this process, so a later launch can hand them its deep link.
+    static OPEN_SHELLS: RefCell<HashMap<WebAppId, Weak<ShellState>>> = RefCell::default();
+}
+
 struct ShellState {
     ctx: Rc<AppContext>,
     app_def: WebAppDefinition,
@@ -26,9 +43,58 @@ struct ShellState {
     engine: RefCell<Rc<Engine>>,
     current_url: RefCell<String>,
     view: RefCell<gtk::Widget>,
+    session_grants: RefCell<SessionGrants>,
+}
+
+/// How long a prompt decision should be kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GrantLifetime {
+    /// Kept in memory until the shell exits; never persisted.
+    Session,
+    /// Persisted, reverting to Ask after the given duration.
+    For(Duration),
+    Permanent,
+}
+
+impl GrantLifetime {
+    fn describe(self) -> String {
+        match self {
+            GrantLifetime::Session => "until app closes".to_string(),
+            GrantLifetime::For(duration) => format!("for {} hours", duration.whole_hours()),
+            GrantLifetime::Permanent => "permanent".to_string(),
+        }
+    }
+}
+
+struct PermissionDecision {
+    state: PermissionState,
+    lifetime: GrantLifetime,
 }
 
-pub fn show(app: &adw::Application, ctx: Rc<AppContext>, app_id: WebAppId) -> Result<()> {
+const GRANT_DURATIONS: &[(&str, Option<i64>)] = &[
+    ("Forever", None),
+    ("For 1 hour", Some(1)),
+    ("For 24 hours", Some(24)),
+];
+
+pub fn show(
+    app: &adw::Application,
+    ctx: Rc<AppContext>,
+    app_id: WebAppId,
+    deep_link: Option<&str>,
+) -> Result<()> {
+    // A shell kept running in the background only hid its window; bring it back and
+    // follow the deep link the new launch brought along.
+    let running = OPEN_SHELLS.with(|shells| shells.borrow().get(&app_id).and_then(Weak::upgrade));
+    if let Some(state) = running {
+        state.window.present();
+        if let Some(link) = deep_link {
+            open_deep_link(&state, link)?;
+        }
+        return Ok(());
+    }
+    let window_name = format!("shell-{app_id}");
+
     let mut app_def = ctx
         .registry
         .load(app_id)
@@ -55,6 +121,7 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>, app_id: WebAppId) -> Re
         .context("shell_toast_overlay missing in blueprint")?;
 
     window.set_title(Some(&app_def.name));
+    window.set_widget_name(&window_name);
     window.set_application(Some(app));
     title.set_title(&app_def.name);
     title.set_subtitle(&app_def.primary_origin);
@@ -63,15 +130,23 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>, app_id: WebAppId) -> Re
     app_def.last_launched_at = Some(OffsetDateTime::now_utc());
     ctx.registry.save(&app_def)?;
 
-    let engine = Rc::new(Engine::new(EngineConfig::new(
-        ctx.paths.profile_dir(app_id),
-    ))?);
+    let mut engine_config = EngineConfig::new(ctx.paths.profile_dir(app_id));
+    // libadwaita already knows the system preference; the portal watch refines it later.
+    engine_config.appearance.dark = app_def
+        .behavior
+        .color_scheme
+        .is_dark(adw::StyleManager::default().is_dark());
+    let engine = Rc::new(Engine::new(engine_config)?);
     let engine_for_nav = Rc::clone(&engine);
+    let (initial_url, forwarded_link) = match initial_target(&app_def, deep_link) {
+        InitialTarget::Load(url) => (url, None),
+        InitialTarget::Forward(link) => (view_url(&app_def), Some(link)),
+    };
     let state_placeholder = Rc::new(RefCell::new(None::<Rc<ShellState>>));
     let view = {
         let state_placeholder = Rc::clone(&state_placeholder);
         engine_for_nav.build_web_view_with_handler(
-            &app_def.start_url,
+            &initial_url,
             move |target| {
                 if let Some(state) = state_placeholder.borrow().as_ref() {
                     if let Err(err) = handle_navigation_request(state, &target) {
@@ -86,7 +161,7 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>, app_id: WebAppId) -> Re
     view.set_vexpand(true);
     content.append(&view);
 
-    let current_url = view_url(&app_def);
+    let current_url = initial_url;
     let state = Rc::new(ShellState {
         ctx,
         app_def,
@@ -97,20 +172,186 @@ pub fn show(app: &adw::Application, ctx: Rc<AppContext>, app_id: WebAppId) -> Re
         engine: RefCell::new(engine),
         current_url: RefCell::new(current_url),
         view: RefCell::new(view),
+        session_grants: RefCell::new(SessionGrants::default()),
     });
     state_placeholder.replace(Some(Rc::clone(&state)));
+    OPEN_SHELLS.with(|shells| shells.borrow_mut().insert(app_id, Rc::downgrade(&state)));
+    window.connect_destroy(move |_| {
+        OPEN_SHELLS.with(|shells| shells.borrow_mut().remove(&app_id));
+    });
 
     setup_menu(&state, &menu_button);
     setup_nav_bar(&state);
+    setup_background(&state);
+    setup_appearance(&state);
+    setup_accent(&state);
 
     window.present();
+    if let Some(link) = forwarded_link {
+        forward_deep_link(&state, link);
+    }
     Ok(())
 }
 
+/// Sends a deep link for a running shell to its window, or to the desktop when the app
+/// does not handle it.
+fn open_deep_link(state: &Rc<ShellState>, link: &str) -> Result<()> {
+    match initial_target(&state.app_def, Some(link)) {
+        InitialTarget::Load(url) => load_url(state, &url),
+        InitialTarget::Forward(link) => {
+            forward_deep_link(state, link);
+            Ok(())
+        }
+    }
+}
+
+fn forward_deep_link(state: &Rc<ShellState>, link: String) {
+    tracing::info!(target: "ui", link, "deep link is outside this app; opening it on the desktop");
+    let portals = Rc::clone(&state.ctx.portals);
+    spawn_portal_task(state, "Open link failed", async move {
+        portals
+            .open_uri(&link)
+            .await
+            .context("open deep link via portal")
+    });
+}
+
+/// Applies the app's color scheme override to the chrome and keeps pages in sync with the
+/// desktop's color scheme and accent from the Settings portal.
+fn setup_appearance(state: &Rc<ShellState>) {
+    let preference = state.app_def.behavior.color_scheme;
+    adw::StyleManager::default().set_color_scheme(match preference {
+        ColorSchemePreference::FollowSystem => adw::ColorScheme::Default,
+        ColorSchemePreference::ForceLight => adw::ColorScheme::ForceLight,
+        ColorSchemePreference::ForceDark => adw::ColorScheme::ForceDark,
+    });
+
+    let mut watch = state.ctx.portals.watch_appearance();
+    let state = Rc::downgrade(state);
+    glib::MainContext::default().spawn_local(async move {
+        while let Some(appearance) = watch.next().await {
+            // Dropping the watch when the window is gone ends the portal subscription.
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            state.engine.borrow().set_appearance(PageAppearance {
+                dark: preference.is_dark(appearance.color_scheme.prefers_dark()),
+                accent: appearance
+                    .accent_color
+                    .map(|color| (color.red, color.green, color.blue)),
+            });
+        }
+    });
+}
+
+/// Tints this window's header bar with the app's accent color, if it has one.
+fn setup_accent(state: &ShellState) {
+    let Some(accent) = state
+        .app_def
+        .accent_color
+        .as_deref()
+        .and_then(Rgb::parse_css)
+    else {
+        return;
+    };
+    let provider = gtk::CssProvider::new();
+    provider.load_from_data(&header_bar_css(&state.window.widget_name(), accent));
+    gtk::style_context_add_provider_for_display(
+        &state.window.display(),
+        &provider,
+        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+}
+
+/// CSS scoped to the shell window named `window_name`, so other shells in the same
+/// process keep their own colors.
+fn header_bar_css(window_name: &str, accent: Rgb) -> String {
+    let foreground = if accent.prefers_light_text() {
+        "#ffffff"
+    } else {
+        "rgba(0, 0, 0, 0.8)"
+    };
+    format!(
+        "window#{window_name} headerbar {{ background-color: {background}; color: {foreground}; }}\n\
+         window#{window_name} headerbar button {{ color: {foreground}; }}\n",
+        background = accent.to_hex(),
+    )
+}
+
+/// With "run in background" on, closing hides the window so the app keeps delivering
+/// notifications; the next launch presents it again. Quitting stays available in the menu.
+fn setup_background(state: &Rc<ShellState>) {
+    if !state.app_def.behavior.needs_background() {
+        return;
+    }
+    let keep_running = Rc::new(Cell::new(false));
+    {
+        let keep_running = Rc::clone(&keep_running);
+        state.window.connect_close_request(move |window| {
+            if keep_running.get() {
+                window.set_visible(false);
+                glib::Propagation::Stop
+            } else {
+                glib::Propagation::Proceed
+            }
+        });
+    }
+
+    let state = Rc::clone(state);
+    glib::MainContext::default().spawn_local(async move {
+        let request = background_request_for(&state.app_def);
+        match state.ctx.portals.request_background(&request).await {
+            Ok(grant) => {
+                keep_running
+                    .set(state.app_def.behavior.run_in_background && grant.run_in_background);
+                if state.app_def.behavior.run_in_background && !grant.run_in_background {
+                    show_toast(&state, "Running in the background was not allowed");
+                }
+            }
+            Err(err) => {
+                tracing::warn!(target: "portal", "background request failed: {err:?}");
+            }
+        }
+    });
+}
+
 fn view_url(app_def: &WebAppDefinition) -> String {
     app_def.start_url.clone()
 }
 
+/// Where the shell starts for a deep link from `--url` or the launcher's `%u`.
+#[derive(Debug, PartialEq, Eq)]
+enum InitialTarget {
+    Load(String),
+    /// An http(s) link this app does not handle; it goes to the desktop and the
+    /// shell opens at its start URL.
+    Forward(String),
+}
+
+/// Loads the deep link when it belongs to the app's origin or a handled origin or scheme.
+fn initial_target(app_def: &WebAppDefinition, deep_link: Option<&str>) -> InitialTarget {
+    let Some(link) = deep_link else {
+        return InitialTarget::Load(view_url(app_def));
+    };
+    let Ok(url) = Url::parse(link) else {
+        tracing::warn!(target: "ui", link, "ignoring invalid deep link; using start URL");
+        return InitialTarget::Load(view_url(app_def));
+    };
+    let is_web = matches!(url.scheme(), "http" | "https");
+    if is_web && url.origin().ascii_serialization() == app_def.primary_origin {
+        return InitialTarget::Load(url.to_string());
+    }
+    // Handled origins and registered schemes, e.g. `mailto:` forwarded from `%u`.
+    match app_def.resolve_handled_uri(&url) {
+        Some(target) => InitialTarget::Load(target.to_string()),
+        None if is_web => InitialTarget::Forward(url.to_string()),
+        None => {
+            tracing::warn!(target: "ui", link, "ignoring unhandled deep link; using start URL");
+            InitialTarget::Load(view_url(app_def))
+        }
+    }
+}
+
 fn is_external_navigation(app_def: &WebAppDefinition, target: &str) -> bool {
     if !app_def.behavior.open_external_links {
         return false;
@@ -118,6 +359,11 @@ fn is_external_navigation(app_def: &WebAppDefinition, target: &str) -> bool {
     if let Ok(url) = Url::parse(target) {
         let origin = url.origin().ascii_serialization();
         origin != app_def.primary_origin
+            && !app_def
+                .link_handlers
+                .origins
+                .iter()
+                .any(|o| o.trim_end_matches('/') == origin)
     } else {
         false
     }
@@ -201,21 +447,61 @@ fn setup_menu(state: &Rc<ShellState>, menu_button: &gtk::MenuButton) {
     });
     state.window.add_action(&notify_action);
 
-    // Gracefully handle missing portals.
-    let portal_ok = sitewrap_portal::is_supported();
-    let file_portal_ok = sitewrap_portal::is_file_chooser_supported();
-    let open_uri_ok = sitewrap_portal::is_open_uri_supported();
-    if !portal_ok {
-        permissions_action.set_enabled(false);
-        notify_action.set_enabled(false);
-        show_toast(state, "Desktop portals unavailable; some actions disabled");
-    }
-    if !open_uri_ok {
-        open_action.set_enabled(false);
-    }
-    if !file_portal_ok {
-        save_action.set_enabled(false);
-    }
+    // Unlike closing, this really ends the shell even when it runs in the background.
+    let quit_action = gio::SimpleAction::new("quit", None);
+    let window_quit = state.window.clone();
+    quit_action.connect_activate(move |_, _| window_quit.destroy());
+    state.window.add_action(&quit_action);
+
+    let diagnostics_action = gio::SimpleAction::new("diagnostics", None);
+    let state_diagnostics = Rc::clone(state);
+    diagnostics_action.connect_activate(move |_, _| {
+        open_diagnostics_window(
+            &state_diagnostics.window,
+            Rc::clone(&state_diagnostics.ctx.portals),
+        );
+    });
+    state.window.add_action(&diagnostics_action);
+
+    // Each action depends only on its own portal; probing happens off the main loop.
+    let state = Rc::clone(state);
+    glib::MainContext::default().spawn_local(async move {
+        let capabilities = state.ctx.portals.capabilities().await;
+        open_action.set_enabled(capabilities.has(PortalKind::OpenUri));
+        notify_action.set_enabled(capabilities.has(PortalKind::Notification));
+        save_action.set_enabled(capabilities.has(PortalKind::FileChooser));
+        // Site permissions only matter if at least one portal can back them.
+        permissions_action.set_enabled(
+            [
+                PortalKind::Notification,
+                PortalKind::Camera,
+                PortalKind::Location,
+            ]
+            .into_iter()
+            .any(|kind| capabilities.has(kind)),
+        );
+
+        let missing = capabilities.missing();
+        if !missing.is_empty() {
+            tracing::warn!(target: "portal", ?missing, "some desktop portals are unavailable");
+        }
+        // Only nag about portals this window's menu actually relies on.
+        let disabled = [
+            PortalKind::OpenUri,
+            PortalKind::Notification,
+            PortalKind::FileChooser,
+        ]
+        .into_iter()
+        .any(|kind| !capabilities.has(kind));
+        if disabled {
+            let toast = adw::Toast::builder()
+                .title("Some desktop integration is unavailable")
+                .button_label("Details")
+                .action_name("win.diagnostics")
+                .build();
+            state.toast_overlay.add_toast(toast);
+        }
+    });
 }
 
 fn build_shell_menu() -> gio::Menu {
@@ -238,7 +524,9 @@ fn build_shell_menu() -> gio::Menu {
     menu.append_section(None, &settings);
 
     let about = gio::Menu::new();
+    about.append(Some("Desktop Integration"), Some("win.diagnostics"));
     about.append(Some("About"), Some("win.about"));
+    about.append(Some("Quit"), Some("win.quit"));
     menu.append_section(None, &about);
 
     menu
@@ -289,8 +577,15 @@ fn setup_nav_bar(state: &Rc<ShellState>) {
 
 fn reload_view(state: &ShellState) -> Result<()> {
     let url = state.current_url.borrow().clone();
+    load_url(state, &url)?;
+    show_toast(state, "Reloaded");
+    Ok(())
+}
+
+/// Replaces the page with a fresh view of `url`.
+fn load_url(state: &ShellState, url: &str) -> Result<()> {
     let engine = state.engine.borrow().clone();
-    let view = engine.build_web_view(&url)?;
+    let view = engine.build_web_view(url)?;
     view.set_hexpand(true);
     view.set_vexpand(true);
     while let Some(child) = state.content.first_child() {
@@ -298,7 +593,7 @@ fn reload_view(state: &ShellState) -> Result<()> {
     }
     state.content.append(&view);
     state.view.replace(view);
-    show_toast(state, "Reloaded");
+    state.current_url.replace(url.to_string());
     Ok(())
 }
 
@@ -310,16 +605,43 @@ fn copy_link(state: &ShellState) -> Result<()> {
     Ok(())
 }
 
-fn open_in_browser(state: &ShellState) -> Result<()> {
+/// Awaits a portal request on the main context, reporting failures like synchronous actions do.
+fn spawn_portal_task(
+    state: &Rc<ShellState>,
+    heading: &'static str,
+    task: impl Future<Output = Result<()>> + 'static,
+) {
+    let state = Rc::clone(state);
+    glib::MainContext::default().spawn_local(async move {
+        if let Err(err) = task.await {
+            tracing::error!(target: "ui", "{heading}: {err:?}");
+            show_error_dialog(&state, heading, &err);
+        }
+    });
+}
+
+fn open_in_browser(state: &Rc<ShellState>) -> Result<()> {
     let url = state.current_url.borrow().clone();
-    sitewrap_portal::open_uri(&url).context("open uri")?;
+    let portals = Rc::clone(&state.ctx.portals);
+    spawn_portal_task(state, "Open in default browser failed", async move {
+        portals.open_uri(&url).await.context("open uri")
+    });
     Ok(())
 }
 
-fn handle_navigation_request(state: &ShellState, target: &str) -> Result<()> {
+fn handle_navigation_request(state: &Rc<ShellState>, target: &str) -> Result<()> {
     if is_external_navigation(&state.app_def, target) {
-        sitewrap_portal::open_uri(target).context("open external via portal")?;
-        show_toast(state, "Opened externally");
+        let portals = Rc::clone(&state.ctx.portals);
+        let target = target.to_string();
+        let state_toast = Rc::clone(state);
+        spawn_portal_task(state, "Open link failed", async move {
+            portals
+                .open_uri(&target)
+                .await
+                .context("open external via portal")?;
+            show_toast(&state_toast, "Opened externally");
+            Ok(())
+        });
     } else {
         // With a real engine we would load in place; stub just records.
         state.current_url.replace(target.to_string());
@@ -335,17 +657,64 @@ fn trigger_notification(state: &Rc<ShellState>) -> Result<()> {
         .permissions
         .load(state.app_def.id)
         .context("load permissions")?;
-    let entry = store.get_or_default_mut(&origin);
+    let now = OffsetDateTime::now_utc();
+    if store.prune_expired(now) {
+        state
+            .ctx
+            .permissions
+            .save(state.app_def.id, &store)
+            .context("save permissions")?;
+    }
+    let current =
+        state
+            .session_grants
+            .borrow()
+            .resolve(&store, &origin, PermissionKind::Notifications, now);
+    record_audit(
+        state,
+        AuditEntry::new(
+            &origin,
+            PermissionKind::Notifications,
+            AuditAction::Requested,
+        ),
+    );
 
-    match entry.notifications {
+    match current {
         PermissionState::Allow => {
+            record_audit(
+                state,
+                AuditEntry::new(
+                    &origin,
+                    PermissionKind::Notifications,
+                    AuditAction::AutoAllowed,
+                ),
+            );
             show_toast(state, "Notifications allowed (sending)");
-            send_sample_notification(state, &origin)?;
+            let state_send = Rc::clone(state);
+            spawn_portal_task(state, "Notification failed", async move {
+                send_sample_notification(&state_send, &origin).await
+            });
         }
         PermissionState::Block => {
+            record_audit(
+                state,
+                AuditEntry::new(
+                    &origin,
+                    PermissionKind::Notifications,
+                    AuditAction::AutoDenied,
+                ),
+            );
             show_toast(state, "Notifications blocked (change in Permissions)");
         }
         PermissionState::Ask => {
+            record_audit(
+                state,
+                AuditEntry::new(
+                    &origin,
+                    PermissionKind::Notifications,
+                    AuditAction::Prompted,
+                ),
+            );
             let state_clone = Rc::clone(state);
             let origin_clone = origin.clone();
             glib::MainContext::default().spawn_local(async move {
@@ -359,8 +728,8 @@ fn trigger_notification(state: &Rc<ShellState>) -> Result<()> {
     Ok(())
 }
 
-fn trigger_dummy_save(state: &ShellState) -> Result<()> {
-    let request = sitewrap_portal::SaveFileRequest {
+fn trigger_dummy_save(state: &Rc<ShellState>) -> Result<()> {
+    let request = SaveFileRequest {
         title: format!("Save page - {}", state.app_def.name),
         suggested_name: format!("{}-page.txt", state.app_def.name.replace(' ', "_")),
         default_directory: None,
@@ -371,33 +740,30 @@ fn trigger_dummy_save(state: &ShellState) -> Result<()> {
         )
         .into_bytes(),
     };
-    match sitewrap_portal::save_file(&request) {
-        Ok(()) => show_toast(state, "Saved placeholder export"),
-        Err(err) => show_error_dialog(state, "Save failed", &err),
-    }
+    let portals = Rc::clone(&state.ctx.portals);
+    let state_toast = Rc::clone(state);
+    spawn_portal_task(state, "Save failed", async move {
+        portals.save_file(&request).await?;
+        show_toast(&state_toast, "Saved placeholder export");
+        Ok(())
+    });
     Ok(())
 }
 
 async fn handle_notification_prompt_async(state: Rc<ShellState>, origin: String) -> Result<()> {
     let decision = prompt_notification_permission_async(&state, &origin).await?;
+    record_decision(&state, &origin, PermissionKind::Notifications, &decision)?;
+    record_audit(
+        &state,
+        AuditEntry::new(&origin, PermissionKind::Notifications, AuditAction::Decided)
+            .with_decision(decision.state.clone())
+            .with_note(decision.lifetime.describe()),
+    );
 
-    let mut store = state
-        .ctx
-        .permissions
-        .load(state.app_def.id)
-        .context("load permissions")?;
-    let entry = store.get_or_default_mut(&origin);
-    entry.notifications = decision.clone();
-    state
-        .ctx
-        .permissions
-        .save(state.app_def.id, &store)
-        .context("save permissions")?;
-
-    match decision {
+    match decision.state {
         PermissionState::Allow => {
             show_toast(&state, "Notifications allowed (sending)");
-            send_sample_notification(&state, &origin)?;
+            send_sample_notification(&state, &origin).await?;
         }
         PermissionState::Block => {
             show_toast(&state, "Notifications blocked (change in Permissions)");
@@ -409,10 +775,54 @@ async fn handle_notification_prompt_async(state: Rc<ShellState>, origin: String)
     Ok(())
 }
 
+/// Audit failures are logged but never block the permission flow.
+fn record_audit(state: &ShellState, entry: AuditEntry) {
+    if let Err(err) = state.ctx.audit.append(state.app_def.id, &entry) {
+        tracing::warn!(target: "audit", "append audit entry failed: {err:?}");
+    }
+}
+
+/// Persists a prompt decision according to its lifetime, or keeps it in memory for session grants.
+fn record_decision(
+    state: &ShellState,
+    origin: &str,
+    kind: PermissionKind,
+    decision: &PermissionDecision,
+) -> Result<()> {
+    if decision.lifetime == GrantLifetime::Session {
+        state
+            .session_grants
+            .borrow_mut()
+            .set(origin, kind, decision.state.clone());
+        return Ok(());
+    }
+
+    let mut store = state
+        .ctx
+        .permissions
+        .load(state.app_def.id)
+        .context("load permissions")?;
+    let entry = store.get_or_default_mut(origin);
+    match decision.lifetime {
+        GrantLifetime::For(duration) => entry.set_until(
+            kind,
+            decision.state.clone(),
+            OffsetDateTime::now_utc() + duration,
+        ),
+        _ => entry.set(kind, decision.state.clone()),
+    }
+    state
+        .ctx
+        .permissions
+        .save(state.app_def.id, &store)
+        .context("save permissions")?;
+    Ok(())
+}
+
 async fn prompt_notification_permission_async(
     state: &Rc<ShellState>,
     origin: &str,
-) -> Result<PermissionState> {
+) -> Result<PermissionDecision> {
     let dialog = adw::MessageDialog::builder()
         .transient_for(&state.window)
         .heading(format!("Allow notifications for {}?", origin))
@@ -423,6 +833,26 @@ async fn prompt_notification_permission_async(
     dialog.set_default_response(Some("allow"));
     dialog.set_close_response("block");
 
+    let remember_check = gtk::CheckButton::builder()
+        .label("Remember this decision")
+        .active(true)
+        .build();
+    let labels: Vec<&str> = GRANT_DURATIONS.iter().map(|(label, _)| *label).collect();
+    let duration_dropdown = gtk::DropDown::from_strings(&labels);
+    duration_dropdown.set_tooltip_text(Some("How long to remember this decision"));
+    remember_check
+        .bind_property("active", &duration_dropdown, "sensitive")
+        .sync_create()
+        .build();
+    let options = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(12)
+        .halign(gtk::Align::Center)
+        .build();
+    options.append(&remember_check);
+    options.append(&duration_dropdown);
+    dialog.set_extra_child(Some(&options));
+
     let (sender, receiver) = async_channel::bounded::<String>(1);
     dialog.connect_response(None, move |d, resp| {
         let _ = sender.send_blocking(resp.to_string());
@@ -430,23 +860,39 @@ async fn prompt_notification_permission_async(
     });
     dialog.present();
 
-    let response = receiver.recv().await.unwrap_or_else(|_| "block".to_string());
-    let decision = if response == "allow" {
+    let response = receiver
+        .recv()
+        .await
+        .unwrap_or_else(|_| "block".to_string());
+    let state = if response == "allow" {
         PermissionState::Allow
     } else {
         PermissionState::Block
     };
-    Ok(decision)
+    let lifetime = if !remember_check.is_active() {
+        GrantLifetime::Session
+    } else {
+        match GRANT_DURATIONS.get(duration_dropdown.selected() as usize) {
+            Some((_, Some(hours))) => GrantLifetime::For(Duration::hours(*hours)),
+            _ => GrantLifetime::Permanent,
+        }
+    };
+    Ok(PermissionDecision { state, lifetime })
 }
 
-fn send_sample_notification(state: &ShellState, origin: &str) -> Result<()> {
+async fn send_sample_notification(state: &ShellState, origin: &str) -> Result<()> {
     let request = NotificationRequest {
         app_id: state.app_def.icon_id.clone(),
         title: format!("{} says hi", state.app_def.name),
         body: format!("Sample notification for {}", origin),
         icon: Some(state.app_def.icon_id.clone()),
     };
-    sitewrap_portal::send_notification(&request).context("send notification")?;
+    state
+        .ctx
+        .portals
+        .send_notification(&request)
+        .await
+        .context("send notification")?;
     show_toast(state, "Notification sent (placeholder)");
     Ok(())
 }
@@ -613,6 +1059,7 @@ fn run_clear_data(state: &ShellState) -> Result<()> {
         .permissions
         .delete(state.app_def.id)
         .context("delete permissions")?;
+    state.session_grants.borrow_mut().clear();
     state
         .ctx
         .paths
@@ -621,13 +1068,15 @@ fn run_clear_data(state: &ShellState) -> Result<()> {
     state
         .ctx
         .paths
-        .delete_icons_for(&state.app_def.icon_id)
+        .reset_icons_for(&state.app_def)
         .context("delete icons")?;
 
     // Recreate engine/profile so subsequent loads use a clean profile.
     let profile_dir = state.ctx.paths.profile_dir(state.app_def.id);
     std::fs::create_dir_all(&profile_dir).context("create profile dir")?;
-    let new_engine = Rc::new(Engine::new(EngineConfig::new(profile_dir))?);
+    let mut config = EngineConfig::new(profile_dir);
+    config.appearance = state.engine.borrow().appearance();
+    let new_engine = Rc::new(Engine::new(config)?);
     let url = view_url(&state.app_def);
     let view = new_engine.build_web_view(&url)?;
     view.set_hexpand(true);
@@ -679,3 +1128,39 @@ struct PermissionSnapshot {
     microphone: PermissionState,
     location: PermissionState,
 }
+
+#[cfg(test)]
+mod tests {
+    use sitewrap_model::SchemeHandler;
+
+    use super::*;
+
+    #[test]
+    fn deep_links_for_a_running_shell_load_or_forward() {
+        let mut app =
+            WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        app.link_handlers.schemes.push(SchemeHandler {
+            scheme: "mailto".into(),
+            url_template: "https://mail.example/compose?uri=%s".into(),
+        });
+
+        assert_eq!(
+            initial_target(&app, Some("mailto:ann@example.org")),
+            InitialTarget::Load(
+                "https://mail.example/compose?uri=mailto%3Aann%40example.org".into()
+            )
+        );
+        assert_eq!(
+            initial_target(&app, Some("https://mail.example/inbox/42")),
+            InitialTarget::Load("https://mail.example/inbox/42".into())
+        );
+        assert_eq!(
+            initial_target(&app, Some("https://news.example/story")),
+            InitialTarget::Forward("https://news.example/story".into())
+        );
+        assert_eq!(
+            initial_target(&app, Some("tel:+1555")),
+            InitialTarget::Load(app.start_url.clone())
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use sitewrap_app::{AppMode, APP_ID};
 use tracing::Level;
 use uuid::Uuid;
@@ -7,16 +7,39 @@ use uuid::Uuid;
 /// Sitewrap command-line entrypoint.
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Run Sitewrap manager or a specific web app shell", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Launch shell mode for the given web app id.
     #[arg(long)]
     shell: Option<Uuid>,
 
+    /// Open the shell at this URL instead of the app's start URL (deep link).
+    #[arg(long, requires = "shell")]
+    url: Option<String>,
+
+    /// URI forwarded by the desktop to a handler launcher (`%u`); treated like `--url`.
+    #[arg(requires = "shell", conflicts_with = "url")]
+    uri: Option<String>,
+
     /// Force manager mode even if other args are present.
     #[arg(long)]
     manager: bool,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Open a URI in the web app registered to handle it, or the default browser otherwise.
+    Open { uri: String },
+    /// Re-download app icons that changed on their sites and update the launchers.
+    RefreshIcons {
+        /// Only refresh this web app.
+        id: Option<Uuid>,
+    },
+}
+
 fn init_tracing() {
     // Default to info unless the user sets RUST_LOG.
     let env = tracing_subscriber::EnvFilter::try_from_default_env()
@@ -31,12 +54,17 @@ fn main() -> Result<()> {
     init_tracing();
     let args = Args::parse();
 
-    let mode = if args.manager {
-        AppMode::Manager
-    } else if let Some(id) = args.shell {
-        AppMode::Shell(id)
-    } else {
-        AppMode::Manager
+    let mode = match args.command {
+        Some(Command::Open { uri }) => AppMode::Open(uri),
+        Some(Command::RefreshIcons { id }) => AppMode::RefreshIcons(id),
+        None if args.manager => AppMode::Manager,
+        None => match args.shell {
+            Some(id) => AppMode::Shell {
+                id,
+                url: args.url.or(args.uri),
+            },
+            None => AppMode::Manager,
+        },
     };
 
     sitewrap_app::run(mode)
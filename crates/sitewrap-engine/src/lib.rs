@@ -1,10 +1,11 @@
+use std::cell::Cell;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::{Mutex, OnceLock};
 
 use anyhow::Result;
 use gtk4::{prelude::*, Box as GtkBox, Button, Label, Orientation};
-use tracing::info;
+use tracing::{debug, info};
 
 #[cfg(feature = "cef")]
 mod cef_backend;
@@ -14,6 +15,8 @@ pub struct EngineConfig {
     pub profile_dir: PathBuf,
     /// Optional root directory containing CEF binaries/assets (libcef.so, locales, pak files).
     pub cef_root: Option<PathBuf>,
+    /// Appearance pages start with; later changes go through [`Engine::set_appearance`].
+    pub appearance: PageAppearance,
 }
 
 impl EngineConfig {
@@ -23,10 +26,19 @@ impl EngineConfig {
             cef_root: std::env::var_os("SITEWRAP_CEF_ROOT")
                 .or_else(|| std::env::var_os("CEF_ROOT"))
                 .map(PathBuf::from),
+            appearance: PageAppearance::default(),
         }
     }
 }
 
+/// What pages see of the desktop theme: `prefers-color-scheme` and the system accent
+/// (as an sRGB triple in `0.0..=1.0`, used for CSS `AccentColor`).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PageAppearance {
+    pub dark: bool,
+    pub accent: Option<(f64, f64, f64)>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum EngineMode {
     Stub,
@@ -36,6 +48,7 @@ enum EngineMode {
 
 pub struct Engine {
     backend: Box<dyn EngineBackend>,
+    appearance: Cell<PageAppearance>,
 }
 
 #[allow(clippy::type_complexity)]
@@ -52,6 +65,7 @@ impl Engine {
             "initializing engine"
         );
 
+        let appearance = config.appearance;
         let backend: Box<dyn EngineBackend> = match mode {
             #[cfg(feature = "cef")]
             EngineMode::CefReady if cef_backend::CefBackend::available(&config.cef_root) => {
@@ -63,7 +77,21 @@ impl Engine {
         let hook_slot = TICK_HOOK.get_or_init(|| Mutex::new(None));
         *hook_slot.lock().unwrap() = backend.tick_hook();
 
-        Ok(Self { backend })
+        Ok(Self {
+            backend,
+            appearance: Cell::new(appearance),
+        })
+    }
+
+    /// Updates the color scheme and accent of every page; no-op when nothing changed.
+    pub fn set_appearance(&self, appearance: PageAppearance) {
+        if self.appearance.replace(appearance) != appearance {
+            self.backend.apply_appearance(appearance);
+        }
+    }
+
+    pub fn appearance(&self) -> PageAppearance {
+        self.appearance.get()
     }
 
     pub fn build_web_view(&self, start_url: &str) -> Result<gtk4::Widget> {
@@ -133,6 +161,11 @@ trait EngineBackend: 'static {
         on_permission: Box<dyn Fn(PermissionKind) + 'static>,
     ) -> Result<gtk4::Widget>;
 
+    /// Forward a theme change to live pages (`prefers-color-scheme` media queries re-evaluate).
+    fn apply_appearance(&self, appearance: PageAppearance) {
+        debug!(target: "engine", ?appearance, "appearance change not supported by backend");
+    }
+
     /// Optional per-backend message loop tick hook; called every ~16ms from the main loop.
     fn tick_hook(&self) -> Option<Box<dyn Fn() + Send + Sync>> {
         None
@@ -0,0 +1,260 @@
+//! Icon candidates declared by a page and its manifest, ranked by how good an app icon
+//! they are likely to make.
+
+use std::cmp::Reverse;
+
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::manifest::WebManifest;
+
+/// Largest rendition written to the cache; bigger declared sizes gain nothing.
+const LARGEST_USEFUL: u32 = 512;
+/// Assumed size of a `/favicon.ico` or an icon link without `sizes`.
+const FAVICON_GUESS: u32 = 32;
+/// iOS home screen icons are 180×180 unless declared otherwise.
+const APPLE_TOUCH_GUESS: u32 = 180;
+
+/// Where a candidate was declared, in the order sites usually care about them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CandidateSource {
+    Manifest,
+    LinkIcon,
+    AppleTouchIcon,
+    Favicon,
+}
+
+/// Manifest `purpose` keywords; `<link>` icons are always `Any`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconPurpose {
+    Any,
+    /// Full-bleed artwork meant to be cropped to a shape by the platform.
+    Maskable,
+    /// A single-color silhouette; unusable as an app icon.
+    Monochrome,
+}
+
+/// One entry of a `sizes` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclaredSize {
+    /// `any`: a vector image.
+    Any,
+    Pixels(u32, u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconCandidate {
+    pub url: Url,
+    pub source: CandidateSource,
+    pub sizes: Vec<DeclaredSize>,
+    /// Declared MIME type, lowercased.
+    pub mime: Option<String>,
+    pub purposes: Vec<IconPurpose>,
+}
+
+impl IconCandidate {
+    fn new(url: Url, source: CandidateSource) -> Self {
+        Self {
+            url,
+            source,
+            sizes: Vec::new(),
+            mime: None,
+            purposes: vec![IconPurpose::Any],
+        }
+    }
+
+    pub fn is_vector(&self) -> bool {
+        self.mime.as_deref() == Some("image/svg+xml")
+            || self.sizes.contains(&DeclaredSize::Any)
+            || self.url.path().to_ascii_lowercase().ends_with(".svg")
+    }
+
+    /// Best square edge this candidate can provide, by declaration or a per-source guess.
+    pub fn declared_size(&self) -> u32 {
+        if self.is_vector() {
+            return LARGEST_USEFUL;
+        }
+        self.sizes
+            .iter()
+            .filter_map(|size| match *size {
+                DeclaredSize::Pixels(width, height) => Some(width.min(height)),
+                DeclaredSize::Any => None,
+            })
+            .max()
+            .unwrap_or(match self.source {
+                CandidateSource::AppleTouchIcon => APPLE_TOUCH_GUESS,
+                _ => FAVICON_GUESS,
+            })
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        let mime = self.mime.as_deref();
+        let path = self.url.path().to_ascii_lowercase();
+        !(matches!(mime, Some("image/jpeg" | "image/jpg"))
+            || path.ends_with(".jpg")
+            || path.ends_with(".jpeg"))
+    }
+
+    /// Whether it can be an app icon at all; monochrome-only icons cannot.
+    pub fn is_usable(&self) -> bool {
+        self.purposes
+            .iter()
+            .any(|purpose| *purpose != IconPurpose::Monochrome)
+    }
+
+    /// Full-bleed art that needs cropping to its safe zone before use.
+    pub fn is_maskable_only(&self) -> bool {
+        self.purposes.contains(&IconPurpose::Maskable) && !self.purposes.contains(&IconPurpose::Any)
+    }
+
+    fn rank_key(&self) -> impl Ord {
+        (
+            self.declared_size().min(LARGEST_USEFUL),
+            self.is_vector(),
+            self.is_lossless(),
+            // Maskable art has a wide safe-zone margin; prefer the plain variant.
+            self.purposes.contains(&IconPurpose::Any),
+            Reverse(self.source),
+        )
+    }
+}
+
+/// Parses a `sizes` value such as `"16x16 32x32"` or `"any"`; junk entries are dropped.
+pub fn parse_sizes(value: &str) -> Vec<DeclaredSize> {
+    value
+        .split_ascii_whitespace()
+        .filter_map(|token| {
+            let token = token.to_ascii_lowercase();
+            if token == "any" {
+                return Some(DeclaredSize::Any);
+            }
+            let (width, height) = token.split_once('x')?;
+            Some(DeclaredSize::Pixels(
+                width.parse().ok()?,
+                height.parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Parses a manifest `purpose`; unknown keywords are ignored and an empty value means `any`.
+pub fn parse_purposes(value: Option<&str>) -> Vec<IconPurpose> {
+    let purposes: Vec<_> = value
+        .unwrap_or_default()
+        .split_ascii_whitespace()
+        .filter_map(|token| match token.to_ascii_lowercase().as_str() {
+            "any" => Some(IconPurpose::Any),
+            "maskable" => Some(IconPurpose::Maskable),
+            "monochrome" => Some(IconPurpose::Monochrome),
+            _ => None,
+        })
+        .collect();
+    if purposes.is_empty() {
+        vec![IconPurpose::Any]
+    } else {
+        purposes
+    }
+}
+
+/// `<link rel="icon">` and `<link rel="apple-touch-icon">` entries in document order,
+/// followed by `/favicon.ico`.
+pub fn discover_candidates(html: &str, base: &Url) -> Vec<IconCandidate> {
+    let mut candidates = Vec::new();
+    if !html.is_empty() {
+        let doc = Html::parse_document(html);
+        let selector = Selector::parse("link[rel][href]").unwrap();
+        for el in doc.select(&selector) {
+            let rel = el
+                .value()
+                .attr("rel")
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            let rels: Vec<_> = rel.split_ascii_whitespace().collect();
+            let source = if rels.contains(&"icon") {
+                CandidateSource::LinkIcon
+            } else if rels.contains(&"apple-touch-icon")
+                || rels.contains(&"apple-touch-icon-precomposed")
+            {
+                CandidateSource::AppleTouchIcon
+            } else {
+                continue;
+            };
+            let Some(url) = el
+                .value()
+                .attr("href")
+                .and_then(|href| base.join(href).ok())
+            else {
+                continue;
+            };
+            let mut candidate = IconCandidate::new(url, source);
+            candidate.sizes = el
+                .value()
+                .attr("sizes")
+                .map(parse_sizes)
+                .unwrap_or_default();
+            candidate.mime = el
+                .value()
+                .attr("type")
+                .map(|t| t.trim().to_ascii_lowercase());
+            candidates.push(candidate);
+        }
+    }
+    if let Ok(url) = base.join("/favicon.ico") {
+        candidates.push(IconCandidate::new(url, CandidateSource::Favicon));
+    }
+    candidates
+}
+
+/// Icons declared in a manifest, resolved against the manifest URL.
+pub fn manifest_candidates(manifest: &WebManifest, manifest_url: &Url) -> Vec<IconCandidate> {
+    manifest
+        .icons
+        .iter()
+        .filter_map(|icon| {
+            let url = manifest_url.join(&icon.src).ok()?;
+            let mut candidate = IconCandidate::new(url, CandidateSource::Manifest);
+            candidate.sizes = icon.sizes.as_deref().map(parse_sizes).unwrap_or_default();
+            candidate.mime = icon.mime.as_deref().map(|t| t.trim().to_ascii_lowercase());
+            candidate.purposes = parse_purposes(icon.purpose.as_deref());
+            Some(candidate)
+        })
+        .collect()
+}
+
+/// Best first: largest usable size, then vector, lossless, non-maskable, and finally the
+/// source order of [`CandidateSource`]. Unusable and duplicate URLs are dropped; the
+/// first declaration of a URL wins.
+pub fn rank_candidates(candidates: Vec<IconCandidate>) -> Vec<IconCandidate> {
+    let mut ranked: Vec<IconCandidate> = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if candidate.is_usable() && !ranked.iter().any(|seen| seen.url == candidate.url) {
+            ranked.push(candidate);
+        }
+    }
+    // Stable, so equal candidates keep document order.
+    ranked.sort_by_key(|candidate| Reverse(candidate.rank_key()));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes_and_purposes() {
+        assert_eq!(
+            parse_sizes("16x16 32X32 any bogus 0x"),
+            [
+                DeclaredSize::Pixels(16, 16),
+                DeclaredSize::Pixels(32, 32),
+                DeclaredSize::Any
+            ]
+        );
+        assert_eq!(parse_purposes(None), [IconPurpose::Any]);
+        assert_eq!(
+            parse_purposes(Some("maskable monochrome")),
+            [IconPurpose::Maskable, IconPurpose::Monochrome]
+        );
+        assert_eq!(parse_purposes(Some("badge")), [IconPurpose::Any]);
+    }
+}
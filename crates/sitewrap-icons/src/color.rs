@@ -0,0 +1,211 @@
+//! Accent colors for wrapped apps: parsed from a manifest `theme_color` or picked
+//! from the rendered icon.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, Result};
+use image::{imageops::FilterType, DynamicImage};
+
+/// An opaque sRGB color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Parses the CSS forms manifests use in practice: `#rgb`, `#rgba`, `#rrggbb`,
+    /// `#rrggbbaa`, `rgb()`/`rgba()` with integer channels, and a few keywords.
+    /// Alpha is ignored.
+    pub fn parse_css(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        if let Some(hex) = value.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        if let Some(args) = value
+            .strip_prefix("rgba(")
+            .or_else(|| value.strip_prefix("rgb("))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let channels: Vec<u8> = args
+                .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+                .filter(|part| !part.is_empty())
+                .take(3)
+                .map(|part| part.parse::<u8>().ok())
+                .collect::<Option<_>>()?;
+            return match channels[..] {
+                [r, g, b] => Some(Rgb(r, g, b)),
+                _ => None,
+            };
+        }
+        match value.as_str() {
+            "black" => Some(Rgb(0, 0, 0)),
+            "white" => Some(Rgb(255, 255, 255)),
+            "red" => Some(Rgb(255, 0, 0)),
+            "green" => Some(Rgb(0, 128, 0)),
+            "blue" => Some(Rgb(0, 0, 255)),
+            _ => None,
+        }
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    /// WCAG relative luminance, 0.0 (black) to 1.0 (white).
+    pub fn relative_luminance(self) -> f64 {
+        fn linear(channel: u8) -> f64 {
+            let c = f64::from(channel) / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        }
+        0.2126 * linear(self.0) + 0.7152 * linear(self.1) + 0.0722 * linear(self.2)
+    }
+
+    /// WCAG contrast ratio between two colors, from 1.0 to 21.0.
+    pub fn contrast_ratio(self, other: Rgb) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Whether white text reads better than dark text on this background.
+    pub fn prefers_light_text(self) -> bool {
+        // Contrast against white equals contrast against black at L ≈ 0.179.
+        self.relative_luminance() < 0.179
+    }
+
+    /// Spread between the strongest and weakest channel; 0 for grays.
+    fn chroma(self) -> u8 {
+        let max = self.0.max(self.1).max(self.2);
+        let min = self.0.min(self.1).min(self.2);
+        max - min
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Rgb> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match hex.len() {
+        3 | 4 => {
+            let digit = |i: usize| channel(&hex[i..=i]).map(|v| v * 17);
+            Some(Rgb(digit(0)?, digit(1)?, digit(2)?))
+        }
+        6 | 8 => Some(Rgb(
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        )),
+        _ => None,
+    }
+}
+
+/// Most common color of an icon, ignoring transparent pixels. Colorful pixels win
+/// over white, black and grays, which are usually background or outline; an icon
+/// with no color at all yields its most common gray.
+pub fn dominant_color(image: &DynamicImage) -> Option<Rgb> {
+    let small = image
+        .resize_exact(32, 32, FilterType::Triangle)
+        .into_rgba8();
+    // Bucket by the top 4 bits per channel and average inside the winning bucket.
+    let mut colorful: HashMap<(u8, u8, u8), (u32, [u32; 3])> = HashMap::new();
+    let mut neutral: HashMap<(u8, u8, u8), (u32, [u32; 3])> = HashMap::new();
+    for pixel in small.pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a < 128 {
+            continue;
+        }
+        let color = Rgb(r, g, b);
+        let buckets = if color.chroma() >= 48 {
+            &mut colorful
+        } else {
+            &mut neutral
+        };
+        let entry = buckets.entry((r >> 4, g >> 4, b >> 4)).or_default();
+        entry.0 += 1;
+        entry.1[0] += u32::from(r);
+        entry.1[1] += u32::from(g);
+        entry.1[2] += u32::from(b);
+    }
+    let buckets = if colorful.is_empty() {
+        neutral
+    } else {
+        colorful
+    };
+    buckets
+        .into_values()
+        .max_by_key(|(count, sums)| (*count, sums[0] + sums[1] + sums[2]))
+        .map(|(count, sums)| {
+            Rgb(
+                (sums[0] / count) as u8,
+                (sums[1] / count) as u8,
+                (sums[2] / count) as u8,
+            )
+        })
+}
+
+/// [`dominant_color`] of a cached icon rendition.
+pub fn dominant_color_of_file(path: &Path) -> Result<Option<Rgb>> {
+    let image = image::open(path).with_context(|| format!("open icon {path:?}"))?;
+    Ok(dominant_color(&image))
+}
+
+/// Accent for a new app: the manifest's `theme_color` when it parses, else the icon's
+/// dominant color.
+pub fn pick_accent(theme_color: Option<&str>, icon_path: &Path) -> Option<Rgb> {
+    theme_color
+        .and_then(Rgb::parse_css)
+        .or_else(|| dominant_color_of_file(icon_path).ok().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_manifest_theme_colors() {
+        assert_eq!(Rgb::parse_css("#1A73E8"), Some(Rgb(0x1a, 0x73, 0xe8)));
+        assert_eq!(Rgb::parse_css("#fa0"), Some(Rgb(0xff, 0xaa, 0x00)));
+        assert_eq!(Rgb::parse_css("#1a73e8cc"), Some(Rgb(0x1a, 0x73, 0xe8)));
+        assert_eq!(Rgb::parse_css(" rgb(10, 20, 30) "), Some(Rgb(10, 20, 30)));
+        assert_eq!(
+            Rgb::parse_css("rgba(10 20 30 / 50%)"),
+            Some(Rgb(10, 20, 30))
+        );
+        assert_eq!(Rgb::parse_css("White"), Some(Rgb(255, 255, 255)));
+        assert_eq!(Rgb::parse_css("#12345"), None);
+        assert_eq!(Rgb::parse_css("hsl(0, 0%, 0%)"), None);
+        assert_eq!(Rgb(0x1a, 0x73, 0xe8).to_hex(), "#1a73e8");
+    }
+
+    #[test]
+    fn dominant_color_prefers_colorful_pixels() {
+        // Mostly white background with a red mark and a transparent corner.
+        let mut icon = image::RgbaImage::from_pixel(64, 64, image::Rgba([255, 255, 255, 255]));
+        for (x, y, pixel) in icon.enumerate_pixels_mut() {
+            if (16..40).contains(&x) && (16..40).contains(&y) {
+                *pixel = image::Rgba([200, 30, 40, 255]);
+            } else if x < 8 && y < 8 {
+                *pixel = image::Rgba([0, 0, 255, 0]);
+            }
+        }
+        let color = dominant_color(&DynamicImage::ImageRgba8(icon)).unwrap();
+        assert!(color.0 > 180 && color.1 < 60 && color.2 < 60, "{color:?}");
+
+        let gray = image::RgbaImage::from_pixel(8, 8, image::Rgba([90, 90, 90, 255]));
+        assert_eq!(
+            dominant_color(&DynamicImage::ImageRgba8(gray)),
+            Some(Rgb(90, 90, 90))
+        );
+    }
+
+    #[test]
+    fn text_color_follows_luminance() {
+        assert!(Rgb(0x3f, 0x51, 0xb5).prefers_light_text());
+        assert!(!Rgb(0xff, 0xd6, 0x00).prefers_light_text());
+        let white = Rgb(0xff, 0xff, 0xff);
+        assert_eq!(Rgb(0, 0, 0).contrast_ratio(white), 21.0);
+        assert_eq!(white.contrast_ratio(white), 1.0);
+    }
+}
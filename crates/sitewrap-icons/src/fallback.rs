@@ -0,0 +1,567 @@
+//! Generated icons for apps whose site offers none: the app's initials on a rounded
+//! plate, in a color picked from a fixed palette by the name.
+//!
+//! Initials are whole grapheme clusters, so accents, emoji sequences and flags are not
+//! split. The bundled Roboto covers Latin, Greek and Cyrillic; other scripts and emoji
+//! are drawn with an installed font that has outlines for them, if there is one.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use image::{Rgba, RgbaImage};
+use rusttype::{point, Font, Point, PositionedGlyph, Rect, Scale};
+use sha2::{Digest, Sha256};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    color::Rgb,
+    normalize::{keyline, rounded_square, MASTER, SQUARE_FRACTION},
+};
+
+/// GNOME palette colors that keep 4.5:1 contrast with white or [`DARK_TEXT`].
+const PALETTE: [Rgb; 10] = [
+    Rgb(0x1c, 0x71, 0xd8), // Blue 4
+    Rgb(0x26, 0xa2, 0x69), // Green 5
+    Rgb(0x0f, 0x7b, 0x8a), // Teal
+    Rgb(0xf6, 0xd3, 0x2d), // Yellow 2
+    Rgb(0xff, 0x78, 0x00), // Orange 3
+    Rgb(0xc6, 0x46, 0x00), // Orange 5
+    Rgb(0xc0, 0x1c, 0x28), // Red 4
+    Rgb(0x81, 0x3d, 0x9c), // Purple 4
+    Rgb(0x86, 0x5e, 0x3c), // Brown 4
+    Rgb(0x5e, 0x5c, 0x64), // Dark 2
+];
+const LIGHT_TEXT: Rgb = Rgb(0xff, 0xff, 0xff);
+const DARK_TEXT: Rgb = Rgb(0x24, 0x1f, 0x31);
+/// Largest box the initials are fitted into, relative to the plate.
+const TEXT_WIDTH_FRACTION: f32 = 0.66;
+const TEXT_HEIGHT_FRACTION: f32 = 0.46;
+/// Installed font files larger than this are not read.
+const MAX_FONT_BYTES: u64 = 64 * 1024 * 1024;
+/// `cmap` tables larger than this are not indexed.
+const MAX_CMAP_BYTES: u32 = 4 * 1024 * 1024;
+
+const ZWJ: char = '\u{200d}';
+
+/// A generated icon, ready to render.
+pub struct FallbackIcon {
+    label: String,
+    background: Rgb,
+    foreground: Rgb,
+    /// Each grapheme of the label with the font it is drawn in.
+    glyphs: Vec<(String, Font<'static>)>,
+}
+
+impl FallbackIcon {
+    /// Initials of `name`, or of `host` when the name has nothing drawable, using the
+    /// bundled font and the fonts installed on the system.
+    pub fn new(name: &str, host: &str) -> Self {
+        static SYSTEM_FONTS: OnceLock<FontIndex> = OnceLock::new();
+        let fonts = SYSTEM_FONTS.get_or_init(|| FontIndex::build(&system_font_dirs()));
+        Self::with_fonts(name, host, fonts)
+    }
+
+    /// [`FallbackIcon::new`] looking only in `font_dirs` for fonts beyond the bundled one.
+    pub fn with_font_dirs(name: &str, host: &str, font_dirs: &[PathBuf]) -> Self {
+        Self::with_fonts(name, host, &FontIndex::build(font_dirs))
+    }
+
+    fn with_fonts(name: &str, host: &str, fonts: &FontIndex) -> Self {
+        let bundled = Font::try_from_bytes(include_bytes!("./fonts/Roboto-Regular.ttf"))
+            .expect("bundled font parses");
+        let mut resolve = |grapheme: &str| {
+            let c = drawn_chars(grapheme).next()?;
+            if has_outline(&bundled, c) {
+                return Some(bundled.clone());
+            }
+            fonts.font_for(c, is_emoji(grapheme))
+        };
+
+        let host = host.trim_start_matches("www.");
+        let host_label = host.split('.').next().unwrap_or_default();
+        let (source, glyphs) = [name.trim(), host_label]
+            .into_iter()
+            .find_map(|text| {
+                let glyphs = initials(text, &mut resolve);
+                (!glyphs.is_empty()).then_some((text, glyphs))
+            })
+            .unwrap_or_else(|| ("?", vec![("?".to_string(), bundled.clone())]));
+
+        let background = PALETTE[palette_index(source)];
+        let foreground = [LIGHT_TEXT, DARK_TEXT]
+            .into_iter()
+            .max_by(|a, b| {
+                background
+                    .contrast_ratio(*a)
+                    .total_cmp(&background.contrast_ratio(*b))
+            })
+            .unwrap();
+        Self {
+            label: glyphs
+                .iter()
+                .map(|(grapheme, _)| grapheme.as_str())
+                .collect(),
+            background,
+            foreground,
+            glyphs,
+        }
+    }
+
+    /// The one or two graphemes drawn.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn background(&self) -> Rgb {
+        self.background
+    }
+
+    pub fn foreground(&self) -> Rgb {
+        self.foreground
+    }
+
+    /// The square master image, 512 px, with the plate on the GNOME square keyline.
+    pub fn render(&self) -> RgbaImage {
+        let edge = keyline(SQUARE_FRACTION);
+        let Rgb(r, g, b) = self.background;
+        let mut canvas = rounded_square(&RgbaImage::from_pixel(edge, edge, Rgba([r, g, b, 255])));
+
+        // Measure at a nominal size, then scale the initials into the text box.
+        let nominal = 100.0;
+        let Some(bounds) = bounds(&self.layout(nominal, point(0.0, 0.0))) else {
+            return canvas;
+        };
+        let (width, height) = (bounds.max.x - bounds.min.x, bounds.max.y - bounds.min.y);
+        let fit = (edge as f32 * TEXT_WIDTH_FRACTION / width)
+            .min(edge as f32 * TEXT_HEIGHT_FRACTION / height);
+        let size = nominal * fit;
+        let center = MASTER as f32 / 2.0;
+        let origin = point(
+            center - (bounds.min.x + width / 2.0) * fit,
+            center - (bounds.min.y + height / 2.0) * fit,
+        );
+
+        let Rgb(fr, fg, fb) = self.foreground;
+        for glyph in self.layout(size, origin) {
+            let Some(pixels) = glyph.pixel_bounding_box() else {
+                continue;
+            };
+            // `draw` reports coordinates relative to the glyph's own bounding box.
+            glyph.draw(|gx, gy, coverage| {
+                let (Ok(x), Ok(y)) = (
+                    u32::try_from(pixels.min.x + gx as i32),
+                    u32::try_from(pixels.min.y + gy as i32),
+                ) else {
+                    return;
+                };
+                let Some(pixel) = canvas.get_pixel_mut_checked(x, y) else {
+                    return;
+                };
+                let [br, bg, bb, alpha] = pixel.0;
+                let blend = |from: u8, to: u8| {
+                    (f32::from(from) + (f32::from(to) - f32::from(from)) * coverage).round() as u8
+                };
+                *pixel = Rgba([blend(br, fr), blend(bg, fg), blend(bb, fb), alpha]);
+            });
+        }
+        canvas
+    }
+
+    /// The label's glyphs at `size` px, left to right from a baseline at `origin`.
+    fn layout(&self, size: f32, origin: Point<f32>) -> Vec<PositionedGlyph<'static>> {
+        let scale = Scale::uniform(size);
+        let mut pen = origin;
+        let mut glyphs = Vec::new();
+        for (grapheme, font) in &self.glyphs {
+            for c in drawn_chars(grapheme) {
+                let glyph = font.glyph(c).scaled(scale);
+                let advance = glyph.h_metrics().advance_width;
+                glyphs.push(glyph.positioned(pen));
+                pen.x += advance;
+            }
+        }
+        glyphs
+    }
+}
+
+/// The graphemes to draw for `text`: a leading emoji on its own, otherwise the first
+/// grapheme of each of the first two words, upper-cased where the script has case.
+/// Graphemes `resolve` finds no font for are skipped.
+fn initials(
+    text: &str,
+    resolve: &mut impl FnMut(&str) -> Option<Font<'static>>,
+) -> Vec<(String, Font<'static>)> {
+    let mut glyphs = Vec::new();
+    for word in text
+        .split(is_word_separator)
+        .filter(|word| !word.is_empty())
+    {
+        let Some(first) = word
+            .graphemes(true)
+            .find(|grapheme| is_emoji(grapheme) || grapheme.starts_with(char::is_alphanumeric))
+        else {
+            continue;
+        };
+        let emoji = is_emoji(first);
+        if emoji && !glyphs.is_empty() {
+            continue;
+        }
+        let first = to_upper(first);
+        let Some(font) = resolve(&first) else {
+            continue;
+        };
+        glyphs.push((first, font));
+        if emoji || glyphs.len() == 2 {
+            break;
+        }
+    }
+    glyphs
+}
+
+fn is_word_separator(c: char) -> bool {
+    c.is_whitespace()
+        || matches!(
+            c,
+            '-' | '_' | '.' | ',' | ':' | ';' | '/' | '|' | '&' | '+' | '·' | '–' | '—'
+        )
+}
+
+/// Upper-cases each character that has a single-character upper case; `ß` stays `ß`.
+fn to_upper(grapheme: &str) -> String {
+    grapheme
+        .chars()
+        .map(|c| {
+            let mut upper = c.to_uppercase();
+            match (upper.next(), upper.next()) {
+                (Some(u), None) => u,
+                _ => c,
+            }
+        })
+        .collect()
+}
+
+fn is_pictographic(c: char) -> bool {
+    matches!(c,
+        '\u{2194}'..='\u{21aa}'
+        | '\u{231a}'..='\u{23ff}'
+        | '\u{25aa}'..='\u{25fe}'
+        | '\u{2600}'..='\u{27bf}'
+        | '\u{2b05}'..='\u{2b55}'
+        | '\u{1f000}'..='\u{1f1e5}'
+        | '\u{1f200}'..='\u{1faff}'
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1f1e6}'..='\u{1f1ff}')
+}
+
+fn is_emoji(grapheme: &str) -> bool {
+    grapheme.chars().any(|c| {
+        is_pictographic(c) || is_regional_indicator(c) || c == '\u{fe0f}' || c == '\u{20e3}'
+    })
+}
+
+/// The characters of `grapheme` that get a glyph. Without text shaping, emoji sequences
+/// cannot be combined, so only their first character is drawn.
+fn drawn_chars(grapheme: &str) -> impl Iterator<Item = char> + '_ {
+    let emoji = is_emoji(grapheme);
+    grapheme
+        .chars()
+        .filter(|&c| !matches!(c, ZWJ | '\u{fe00}'..='\u{fe0f}' | '\u{e0020}'..='\u{e007f}'))
+        .take(if emoji { 1 } else { usize::MAX })
+}
+
+/// Whether `font` draws `c`, rather than lacking it or having only a color bitmap.
+fn has_outline(font: &Font<'_>, c: char) -> bool {
+    let glyph = font.glyph(c);
+    glyph.id().0 != 0
+        && glyph
+            .scaled(Scale::uniform(64.0))
+            .exact_bounding_box()
+            .is_some()
+}
+
+/// Union of the glyphs' outline boxes.
+fn bounds(glyphs: &[PositionedGlyph<'_>]) -> Option<Rect<f32>> {
+    glyphs
+        .iter()
+        .filter_map(|glyph| {
+            let bb = glyph.unpositioned().exact_bounding_box()?;
+            let at = glyph.position();
+            Some(Rect {
+                min: point(at.x + bb.min.x, at.y + bb.min.y),
+                max: point(at.x + bb.max.x, at.y + bb.max.y),
+            })
+        })
+        .reduce(|a, b| Rect {
+            min: point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+            max: point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+        })
+}
+
+/// The installed fonts by the characters their `cmap` maps. Building it reads only each
+/// file's table directory and `cmap`; a font is read whole once it is a candidate.
+struct FontIndex {
+    faces: Vec<IndexedFace>,
+    /// Fonts read so far, by file and face; `None` when the file did not parse.
+    loaded: Mutex<HashMap<(PathBuf, u32), Option<Font<'static>>>>,
+}
+
+/// Sorted, non-overlapping inclusive code point ranges.
+type CodepointRanges = Vec<(u32, u32)>;
+
+struct IndexedFace {
+    path: PathBuf,
+    /// The face within a collection; 0 for single fonts.
+    index: u32,
+    emoji: bool,
+    ranges: CodepointRanges,
+}
+
+impl FontIndex {
+    fn build(dirs: &[PathBuf]) -> Self {
+        let mut files = Vec::new();
+        for dir in dirs {
+            collect_font_files(dir, 4, &mut files);
+        }
+        files.sort();
+        files.dedup();
+        let faces = files
+            .into_iter()
+            .flat_map(|path| {
+                let emoji = path.to_string_lossy().to_lowercase().contains("emoji");
+                cmap_ranges(&path)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(move |(index, ranges)| IndexedFace {
+                        path: path.clone(),
+                        index,
+                        emoji,
+                        ranges,
+                    })
+            })
+            .collect();
+        Self {
+            faces,
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// An installed font with an outline for `c`; emoji fonts are tried first for emoji.
+    fn font_for(&self, c: char, emoji: bool) -> Option<Font<'static>> {
+        let mut candidates: Vec<&IndexedFace> =
+            self.faces.iter().filter(|face| face.maps(c)).collect();
+        if emoji {
+            candidates.sort_by_key(|face| !face.emoji);
+        }
+        candidates.into_iter().find_map(|face| {
+            let font = self.load(face)?;
+            has_outline(&font, c).then_some(font)
+        })
+    }
+
+    fn load(&self, face: &IndexedFace) -> Option<Font<'static>> {
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        loaded
+            .entry((face.path.clone(), face.index))
+            .or_insert_with(|| {
+                let data = fs::read(&face.path).ok()?;
+                Font::try_from_vec_and_index(data, face.index)
+            })
+            .clone()
+    }
+}
+
+impl IndexedFace {
+    fn maps(&self, c: char) -> bool {
+        let c = u32::from(c);
+        let at = self.ranges.partition_point(|&(_, end)| end < c);
+        self.ranges.get(at).is_some_and(|&(start, _)| start <= c)
+    }
+}
+
+/// The Unicode code points each face in the font file at `path` maps, read from its
+/// `cmap` table without loading the rest of the file.
+fn cmap_ranges(path: &Path) -> Option<Vec<(u32, CodepointRanges)>> {
+    let mut file = File::open(path).ok()?;
+    let header = read_at(&mut file, 0, 12)?;
+    let face_offsets = if &header[0..4] == b"ttcf" {
+        let count = be_u32(&header, 8)?.min(256);
+        let offsets = read_at(&mut file, 12, count as usize * 4)?;
+        (0..count as usize)
+            .map(|i| be_u32(&offsets, i * 4))
+            .collect::<Option<Vec<_>>>()?
+    } else {
+        vec![0]
+    };
+
+    let mut faces = Vec::new();
+    for (index, offset) in face_offsets.into_iter().enumerate() {
+        let Some(ranges) = face_cmap_ranges(&mut file, offset) else {
+            continue;
+        };
+        faces.push((index as u32, ranges));
+    }
+    Some(faces)
+}
+
+fn face_cmap_ranges(file: &mut File, offset: u32) -> Option<CodepointRanges> {
+    let header = read_at(file, offset.into(), 12)?;
+    let tables = u16::from_be_bytes([header[4], header[5]]) as usize;
+    let records = read_at(file, u64::from(offset) + 12, tables * 16)?;
+    let record = records
+        .chunks_exact(16)
+        .find(|record| &record[0..4] == b"cmap")?;
+    let (cmap_offset, cmap_len) = (be_u32(record, 8)?, be_u32(record, 12)?);
+    if cmap_len > MAX_CMAP_BYTES {
+        return None;
+    }
+    let data = read_at(file, cmap_offset.into(), cmap_len as usize)?;
+    let cmap = ttf_parser::cmap::Table::parse(&data)?;
+
+    let mut points = Vec::new();
+    for subtable in cmap.subtables.into_iter().filter(|s| s.is_unicode()) {
+        subtable.codepoints(|cp| {
+            if subtable.glyph_index(cp).is_some_and(|id| id.0 != 0) {
+                points.push(cp);
+            }
+        });
+    }
+    points.sort_unstable();
+    points.dedup();
+    let mut ranges = CodepointRanges::new();
+    for cp in points {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cp => *end = cp,
+            _ => ranges.push((cp, cp)),
+        }
+    }
+    Some(ranges)
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let mut buf = vec![0; len];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn collect_font_files(dir: &Path, depth: u32, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() && depth > 0 {
+            collect_font_files(&path, depth - 1, files);
+        } else if meta.len() <= MAX_FONT_BYTES
+            && path.extension().is_some_and(|ext| {
+                ["ttf", "otf", "ttc"]
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+            })
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Font directories as fontconfig would search them, plus the host's fonts that
+/// Flatpak exposes under `/run/host`.
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(base) = directories::BaseDirs::new() {
+        dirs.push(base.data_dir().join("fonts"));
+        dirs.push(base.home_dir().join(".fonts"));
+    }
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| Path::new(dir).join("fonts")),
+    );
+    dirs.extend(
+        [
+            "/run/host/fonts",
+            "/run/host/user-fonts",
+            "/run/host/local-fonts",
+        ]
+        .map(PathBuf::from),
+    );
+    dirs
+}
+
+fn palette_index(text: &str) -> usize {
+    let digest = Sha256::digest(text.to_lowercase().as_bytes());
+    let seed = u64::from_le_bytes(digest[0..8].try_into().unwrap());
+    (seed % PALETTE.len() as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initials_come_from_the_first_two_words() {
+        let bundled = Font::try_from_bytes(include_bytes!("./fonts/Roboto-Regular.ttf")).unwrap();
+        let mut resolve = |grapheme: &str| {
+            let c = drawn_chars(grapheme).next()?;
+            has_outline(&bundled, c).then(|| bundled.clone())
+        };
+        let mut label = |text: &str| -> String {
+            initials(text, &mut resolve)
+                .into_iter()
+                .map(|(grapheme, _)| grapheme)
+                .collect()
+        };
+        assert_eq!(label("Google Calendar"), "GC");
+        assert_eq!(label("mail"), "M");
+        assert_eq!(label("the-daily (beta)"), "TD");
+        assert_eq!(label("ésprit über alles"), "ÉÜ");
+        assert_eq!(label("straße"), "S");
+        assert_eq!(label("ßtudio"), "ß");
+        assert_eq!(label("(1) Password"), "1P");
+        // Roboto has neither emoji nor CJK outlines.
+        assert_eq!(label("🚀 Launchpad"), "L");
+        assert_eq!(label("微信"), "");
+    }
+
+    #[test]
+    fn font_index_reads_coverage_from_cmap() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/fonts");
+        let index = FontIndex::build(&[dir]);
+        assert_eq!(index.faces.len(), 1);
+        assert!(index.faces[0].maps('A'));
+        assert!(index.faces[0].maps('Ж'));
+        assert!(!index.faces[0].maps('微'));
+        assert!(index.font_for('A', false).is_some());
+        assert!(index.font_for('微', false).is_none());
+    }
+
+    #[test]
+    fn every_palette_color_has_readable_text() {
+        for color in PALETTE {
+            let best = color
+                .contrast_ratio(LIGHT_TEXT)
+                .max(color.contrast_ratio(DARK_TEXT));
+            assert!(best >= 4.5, "{} reaches only {best:.2}:1", color.to_hex());
+        }
+    }
+}
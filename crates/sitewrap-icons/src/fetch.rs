@@ -0,0 +1,476 @@
+//! HTTP access for icon discovery, behind [`Fetcher`] so tests can serve sites locally.
+//!
+//! Pages decide which URLs get fetched, so [`HttpFetcher`] only speaks HTTP(S), refuses
+//! private and loopback addresses (also after redirects and DNS), caps redirects and
+//! body sizes, and [`Budgeted`] bounds the time one icon operation may take.
+
+use std::{
+    error::Error as StdError,
+    io::{self, Read},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use reqwest::{
+    blocking::Client,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect,
+};
+use url::{Host, Url};
+
+use crate::IconError;
+
+static DEFAULT_FETCHER: Lazy<HttpFetcher> = Lazy::new(HttpFetcher::default);
+/// Time one icon operation (fetch, preview, refresh) may spend on the network.
+pub const ICON_FETCH_BUDGET: Duration = Duration::from_secs(30);
+
+/// The fetcher behind the functions that do not take one: the default policy with a
+/// fresh [`ICON_FETCH_BUDGET`].
+pub fn default_fetcher() -> Budgeted<'static> {
+    Budgeted::new(&*DEFAULT_FETCHER, ICON_FETCH_BUDGET)
+}
+
+/// A successful `GET`, with redirects already followed.
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    /// Where the body came from after redirects; relative links resolve against it.
+    pub url: Url,
+    pub content_type: Option<String>,
+    /// Validators for a later conditional request.
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Answer to a conditional `GET`.
+#[derive(Debug, Clone)]
+pub enum Conditional {
+    /// `304 Not Modified`: the copy described by the validators is current.
+    NotModified,
+    Modified(Fetched),
+}
+
+/// Downloads pages, manifests and icons.
+pub trait Fetcher: Send + Sync {
+    /// `GET`s `url`. Error statuses and bodies longer than `max_bytes` are errors.
+    fn get(&self, url: &Url, max_bytes: u64) -> Result<Fetched>;
+
+    /// Like [`Fetcher::get`], but sends `If-None-Match` and `If-Modified-Since` so an
+    /// unchanged resource costs no body. Fetchers without caching support always download.
+    fn get_if_changed(
+        &self,
+        url: &Url,
+        max_bytes: u64,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<Conditional> {
+        let _ = (etag, last_modified);
+        self.get(url, max_bytes).map(Conditional::Modified)
+    }
+}
+
+/// Limits applied by [`HttpFetcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchPolicy {
+    /// Limit for a single request, including reading its body.
+    pub request_timeout: Duration,
+    pub max_redirects: usize,
+    /// Allows loopback, private, link-local and similar addresses; for tests only.
+    pub allow_private_addresses: bool,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(10),
+            max_redirects: 5,
+            allow_private_addresses: false,
+        }
+    }
+}
+
+/// [`Fetcher`] over a blocking reqwest client, enforcing a [`FetchPolicy`].
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    client: Client,
+    policy: FetchPolicy,
+}
+
+impl HttpFetcher {
+    pub fn new(policy: FetchPolicy) -> Result<Self> {
+        let redirects = {
+            let policy = policy.clone();
+            redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() > policy.max_redirects {
+                    let err = IconError::TooManyRedirects(policy.max_redirects);
+                    return attempt.error(err);
+                }
+                match check_url(attempt.url(), &policy) {
+                    Ok(()) => attempt.follow(),
+                    Err(err) => attempt.error(err),
+                }
+            })
+        };
+        let mut builder = Client::builder()
+            .timeout(policy.request_timeout)
+            .redirect(redirects)
+            .user_agent("sitewrap-icon-fetcher/0.1");
+        if !policy.allow_private_addresses {
+            // A proxy would resolve the host itself, past `PublicResolver`.
+            builder = builder.dns_resolver(Arc::new(PublicResolver)).no_proxy();
+        }
+        let client = builder.build().context("build http client")?;
+        Ok(Self { client, policy })
+    }
+}
+
+impl Default for HttpFetcher {
+    fn default() -> Self {
+        Self::new(FetchPolicy::default()).expect("build reqwest client")
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn get(&self, url: &Url, max_bytes: u64) -> Result<Fetched> {
+        match self.get_if_changed(url, max_bytes, None, None)? {
+            Conditional::Modified(fetched) => Ok(fetched),
+            Conditional::NotModified => {
+                bail!(IconError::Download(format!("{url} answered 304 unasked")))
+            }
+        }
+    }
+
+    fn get_if_changed(
+        &self,
+        url: &Url,
+        max_bytes: u64,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<Conditional> {
+        check_url(url, &self.policy)?;
+        let mut request = self.client.get(url.as_str());
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+        let resp = request.send().map_err(|err| classify(url, &err))?;
+        let status = resp.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        if status.is_redirection() {
+            // reqwest hands back redirects to other schemes instead of asking the policy.
+            let target = resp
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|location| resp.url().join(location).ok());
+            if let Some(target) = target {
+                check_url(&target, &self.policy)?;
+            }
+        }
+        if !status.is_success() {
+            bail!(IconError::HttpStatus {
+                url: url.to_string(),
+                status: status.as_u16(),
+            });
+        }
+        if resp.content_length().is_some_and(|len| len > max_bytes) {
+            bail!(IconError::TooLarge { limit: max_bytes });
+        }
+
+        let final_url = resp.url().clone();
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(reqwest::header::CONTENT_TYPE);
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+        // Chunked responses declare no length; stop reading one byte past the limit.
+        let mut body = Vec::new();
+        resp.take(max_bytes + 1)
+            .read_to_end(&mut body)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::TimedOut => IconError::Timeout(url.to_string()),
+                _ => IconError::Download(err.to_string()),
+            })?;
+        if body.len() as u64 > max_bytes {
+            bail!(IconError::TooLarge { limit: max_bytes });
+        }
+        Ok(Conditional::Modified(Fetched {
+            url: final_url,
+            content_type,
+            etag,
+            last_modified,
+            body,
+        }))
+    }
+}
+
+/// Refuses requests once `budget` has passed since it was created. A request already
+/// running when the budget ends still has its own timeout.
+pub struct Budgeted<'a> {
+    inner: &'a dyn Fetcher,
+    deadline: Instant,
+}
+
+impl<'a> Budgeted<'a> {
+    pub fn new(inner: &'a dyn Fetcher, budget: Duration) -> Self {
+        Self {
+            inner,
+            deadline: Instant::now() + budget,
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        if Instant::now() >= self.deadline {
+            bail!(IconError::BudgetExceeded);
+        }
+        Ok(())
+    }
+}
+
+impl Fetcher for Budgeted<'_> {
+    fn get(&self, url: &Url, max_bytes: u64) -> Result<Fetched> {
+        self.check()?;
+        self.inner.get(url, max_bytes)
+    }
+
+    fn get_if_changed(
+        &self,
+        url: &Url,
+        max_bytes: u64,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<Conditional> {
+        self.check()?;
+        self.inner
+            .get_if_changed(url, max_bytes, etag, last_modified)
+    }
+}
+
+/// Refuses requests once `cancelled` is set, so work nobody waits for stops at the next
+/// download. A request already running finishes.
+pub struct Cancellable<'a> {
+    inner: &'a dyn Fetcher,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<'a> Cancellable<'a> {
+    pub fn new(inner: &'a dyn Fetcher, cancelled: Arc<AtomicBool>) -> Self {
+        Self { inner, cancelled }
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            bail!(IconError::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+impl Fetcher for Cancellable<'_> {
+    fn get(&self, url: &Url, max_bytes: u64) -> Result<Fetched> {
+        self.check()?;
+        self.inner.get(url, max_bytes)
+    }
+
+    fn get_if_changed(
+        &self,
+        url: &Url,
+        max_bytes: u64,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<Conditional> {
+        self.check()?;
+        self.inner
+            .get_if_changed(url, max_bytes, etag, last_modified)
+    }
+}
+
+/// Scheme and literal-address checks for a request or redirect target. Host names are
+/// checked after resolution by [`PublicResolver`].
+fn check_url(url: &Url, policy: &FetchPolicy) -> Result<(), IconError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(IconError::UnsupportedScheme(url.scheme().to_string()));
+    }
+    let ip = match url.host() {
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        Some(Host::Domain(_)) => return Ok(()),
+        None => return Err(IconError::UnsupportedScheme(url.scheme().to_string())),
+    };
+    if !policy.allow_private_addresses && !is_public(ip) {
+        return Err(IconError::PrivateAddress(ip.to_string()));
+    }
+    Ok(())
+}
+
+/// Whether `ip` is a globally routable unicast address.
+pub(crate) fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // Carrier-grade NAT, benchmarking and reserved ranges.
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (18..20).contains(&b))
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = embedded_ipv4(ip) {
+                return is_public(IpAddr::V4(v4));
+            }
+            let [first, second, ..] = ip.segments();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unique local, link-local, site-local and documentation ranges.
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first & 0xffc0) == 0xfec0
+                || (first == 0x2001 && second == 0x0db8)
+                // Local-use NAT64, which may embed any IPv4 address.
+                || (first == 0x0064 && second == 0xff9b))
+        }
+    }
+}
+
+/// The IPv4 address `ip` reaches: IPv4-mapped and IPv4-compatible addresses, NAT64
+/// `64:ff9b::/96` and 6to4 `2002::/16`.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return Some(v4);
+    }
+    let from = |high: u16, low: u16| {
+        let [a, b] = high.to_be_bytes();
+        let [c, d] = low.to_be_bytes();
+        Ipv4Addr::new(a, b, c, d)
+    };
+    match ip.segments() {
+        [0, 0, 0, 0, 0, 0, high, low] => Some(from(high, low)),
+        [0x0064, 0xff9b, 0, 0, 0, 0, high, low] => Some(from(high, low)),
+        [0x2002, high, low, ..] => Some(from(high, low)),
+        _ => None,
+    }
+}
+
+/// System DNS with non-public answers dropped, so a host name cannot point the fetcher
+/// at the local network. Connections use the filtered addresses, which also defeats DNS
+/// rebinding between the check and the connect.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let lookup = host.clone();
+            let addrs: Vec<SocketAddr> =
+                tokio::task::spawn_blocking(move || (lookup.as_str(), 0).to_socket_addrs())
+                    .await??
+                    .filter(|addr| is_public(addr.ip()))
+                    .collect();
+            if addrs.is_empty() {
+                return Err(IconError::PrivateAddress(host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The [`IconError`] behind a reqwest failure; policy violations travel as its source.
+fn classify(url: &Url, err: &reqwest::Error) -> IconError {
+    let mut source: Option<&(dyn StdError + 'static)> = Some(err);
+    while let Some(current) = source {
+        if let Some(icon_err) = current.downcast_ref::<IconError>() {
+            return icon_err.clone();
+        }
+        source = current.source();
+    }
+    if err.is_timeout() {
+        IconError::Timeout(url.to_string())
+    } else {
+        IconError::Download(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_globally_routable_addresses_are_public() {
+        for ip in [
+            "93.184.216.34",
+            "2606:2800:220:1:248:1893:25c8:1946",
+            "1.1.1.1",
+            "64:ff9b::101:101",
+            "2002:5db8:d822::1",
+        ] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "2001:db8::1",
+            "fec0::1",
+            "64:ff9b::7f00:1",
+            "64:ff9b::10.0.0.1",
+            "64:ff9b:1::1",
+            "2002:7f00:1::1",
+            "2002:c0a8:101::1",
+            "::127.0.0.1",
+            "::192.168.1.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn rejects_other_schemes_and_private_literals() {
+        let policy = FetchPolicy::default();
+        let check = |url: &str| check_url(&Url::parse(url).unwrap(), &policy);
+        assert!(check("https://example.com/icon.png").is_ok());
+        assert!(matches!(
+            check("file:///etc/passwd"),
+            Err(IconError::UnsupportedScheme(scheme)) if scheme == "file"
+        ));
+        assert!(matches!(
+            check("http://[::1]:8080/"),
+            Err(IconError::PrivateAddress(_))
+        ));
+        assert!(matches!(
+            check("http://192.168.0.1/"),
+            Err(IconError::PrivateAddress(_))
+        ));
+    }
+}
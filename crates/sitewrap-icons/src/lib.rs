@@ -2,29 +2,59 @@ use std::{
     fs,
     io::Cursor,
     path::{Path, PathBuf},
-    time::Duration,
 };
 
 use anyhow::{bail, Context, Result};
 use image::{imageops::FilterType, DynamicImage, ImageFormat};
-use once_cell::sync::Lazy;
-use rand::{rngs::StdRng, Rng, SeedableRng};
-use reqwest::blocking::Client;
-use scraper::{Html, Selector};
-use sha2::{Digest, Sha256};
 use thiserror::Error;
 use url::Url;
 
-const ICON_SIZES: &[u32] = &[16, 32, 48, 64, 128, 256, 512];
-static CLIENT: Lazy<Client> = Lazy::new(|| {
-    Client::builder()
-        .timeout(Duration::from_secs(10))
-        .user_agent("sitewrap-icon-fetcher/0.1")
-        .build()
-        .expect("build reqwest client")
-});
+pub mod candidates;
+pub mod color;
+mod fallback;
+pub mod fetch;
+pub mod manifest;
+pub mod normalize;
+pub mod refresh;
+mod svg;
 
-#[derive(Debug, Error)]
+pub use candidates::{
+    discover_candidates, manifest_candidates, rank_candidates, IconCandidate, IconPurpose,
+};
+pub use color::{dominant_color, pick_accent, Rgb};
+pub use fallback::FallbackIcon;
+pub use fetch::{
+    default_fetcher, Budgeted, Cancellable, Conditional, FetchPolicy, Fetched, Fetcher,
+    HttpFetcher, ICON_FETCH_BUDGET,
+};
+pub use manifest::{
+    fetch_manifest, fetch_manifest_with, fetch_site_info, fetch_site_info_with, page_name,
+    SiteInfo, WebManifest,
+};
+pub use normalize::Normalization;
+pub use refresh::{
+    needs_refresh, refresh_icon, refresh_icon_with, IconOrigin, IconRecord, RefreshOutcome,
+};
+pub use svg::scalable_icon_path;
+
+/// Square PNG sizes written to the icon cache as `<icon_id>-<size>x<size>.png`.
+pub const ICON_SIZES: &[u32] = &[16, 32, 48, 64, 128, 256, 512];
+/// Successful downloads compared before settling for the best of them.
+const MAX_ICON_DOWNLOADS: usize = 4;
+/// A raster at least this large stops the search; below it, later candidates may be sharper.
+const GOOD_ENOUGH_SIZE: u32 = 256;
+/// Largest icon download or local import accepted.
+pub const MAX_ICON_BYTES: u64 = 5 * 1024 * 1024;
+/// Largest start page read when looking for icon links.
+pub(crate) const MAX_PAGE_BYTES: u64 = 2 * 1024 * 1024;
+/// Largest width or height decoded; checked from the header before allocating pixels.
+pub const MAX_ICON_DIMENSION: u32 = 4096;
+/// Edge of the PNG thumbnails in [`IconPreview`].
+const PREVIEW_SIZE: u32 = 64;
+
+/// Why an icon could not be fetched or decoded. Returned inside [`anyhow::Error`];
+/// downcast it to explain the failure.
+#[derive(Debug, Clone, Error)]
 pub enum IconError {
     #[error("no icons found for {0}")]
     NotFound(String),
@@ -32,101 +62,427 @@ pub enum IconError {
     Download(String),
     #[error("decode failed: {0}")]
     Decode(String),
+    #[error("icons are only fetched over HTTP or HTTPS, not {0}:")]
+    UnsupportedScheme(String),
+    #[error("{0} is a local or private network address")]
+    PrivateAddress(String),
+    #[error("more than {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("{url} returned HTTP {status}")]
+    HttpStatus { url: String, status: u16 },
+    #[error("larger than {} KiB", limit / 1024)]
+    TooLarge { limit: u64 },
+    #[error("image is {width}×{height} pixels, more than {MAX_ICON_DIMENSION} per side")]
+    DimensionsTooLarge { width: u32, height: u32 },
+    #[error("{0} did not answer in time")]
+    Timeout(String),
+    #[error("the site took too long overall")]
+    BudgetExceeded,
+    #[error("cancelled")]
+    Cancelled,
 }
 
 #[derive(Debug, Clone)]
 pub struct IconResult {
     pub icon_id: String,
     pub rendered_paths: Vec<PathBuf>,
+    /// The sanitized original when the site's icon is an SVG.
+    pub scalable_path: Option<PathBuf>,
 }
 
+/// Caches the site's best icon, or generates one from `app_name` when it has none.
 pub fn fetch_and_cache_icon(
     start_url: &Url,
+    app_name: &str,
     icon_id: &str,
     cache_dir: &Path,
+    style: Normalization,
+) -> Result<IconResult> {
+    fetch_and_cache_icon_with(
+        &fetch::default_fetcher(),
+        start_url,
+        app_name,
+        icon_id,
+        cache_dir,
+        style,
+    )
+}
+
+/// [`fetch_and_cache_icon`] downloading through `fetcher`.
+pub fn fetch_and_cache_icon_with(
+    fetcher: &dyn Fetcher,
+    start_url: &Url,
+    app_name: &str,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
 ) -> Result<IconResult> {
     fs::create_dir_all(cache_dir)?;
+    if let Some(best) = best_site_icon(fetcher, site_candidates(fetcher, start_url)) {
+        return cache_site_icon(best, style, cache_dir, icon_id);
+    }
 
-    let html = CLIENT
-        .get(start_url.as_str())
-        .send()
-        .and_then(|r| r.error_for_status())
-        .and_then(|r| r.text())
-        .unwrap_or_default();
+    generate_fallback_icon(start_url, app_name, icon_id, cache_dir)
+}
 
-    let candidates = discover_icon_urls(&html, start_url);
-    for url in candidates {
-        match download_and_render(&url, cache_dir, icon_id) {
-            Ok(paths) => {
-                return Ok(IconResult {
-                    icon_id: icon_id.to_string(),
-                    rendered_paths: paths,
-                })
-            }
-            Err(_) => continue,
-        }
+/// [`fetch_and_cache_icon`] for a site already looked up with [`fetch_site_info`]: its
+/// start page and manifest are read from `site` instead of being downloaded again.
+pub fn cache_icon_for_site(
+    site: &SiteInfo,
+    start_url: &Url,
+    app_name: &str,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<IconResult> {
+    cache_icon_for_site_with(
+        &fetch::default_fetcher(),
+        site,
+        start_url,
+        app_name,
+        icon_id,
+        cache_dir,
+        style,
+    )
+}
+
+/// [`cache_icon_for_site`] downloading through `fetcher`.
+pub fn cache_icon_for_site_with(
+    fetcher: &dyn Fetcher,
+    site: &SiteInfo,
+    start_url: &Url,
+    app_name: &str,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<IconResult> {
+    fs::create_dir_all(cache_dir)?;
+    let candidates = match &site.page {
+        Some((base, html)) => page_candidates(html, base, site.manifest.as_ref()),
+        None => page_candidates("", start_url, None),
+    };
+    if let Some(best) = best_site_icon(fetcher, candidates) {
+        return cache_site_icon(best, style, cache_dir, icon_id);
     }
 
-    // fallback: generate initials icon
-    let fallback = generate_fallback(start_url, cache_dir, icon_id)?;
+    generate_fallback_icon(start_url, app_name, icon_id, cache_dir)
+}
+
+/// Caches the initials icon [`fetch_and_cache_icon`] falls back to, without touching the
+/// network; e.g. to redraw a generated icon after the app was renamed.
+pub fn generate_fallback_icon(
+    start_url: &Url,
+    app_name: &str,
+    icon_id: &str,
+    cache_dir: &Path,
+) -> Result<IconResult> {
+    fs::create_dir_all(cache_dir)?;
+    let fallback = generate_fallback(app_name, start_url, cache_dir, icon_id)?;
+    remove_stale_scalable(cache_dir, icon_id);
+    IconRecord::generated().save(cache_dir, icon_id)?;
     Ok(IconResult {
         icon_id: icon_id.to_string(),
         rendered_paths: fallback,
+        scalable_path: None,
     })
 }
 
-fn discover_icon_urls(html: &str, base: &Url) -> Vec<Url> {
-    let mut urls = Vec::new();
-    if !html.is_empty() {
-        let doc = Html::parse_document(html);
-        let sel_icon = Selector::parse(r#"link[rel~="icon"]"#).unwrap();
-        for el in doc.select(&sel_icon) {
-            if let Some(href) = el.value().attr("href") {
-                if let Ok(url) = base.join(href) {
-                    urls.push(url);
-                }
-            }
+/// The site's best icon: the decoded download, its record, and whether it is maskable.
+type SiteIcon = (DownloadedIcon, IconRecord, bool);
+
+/// Downloads `candidates` in rank order and keeps the sharpest.
+fn best_site_icon(fetcher: &dyn Fetcher, candidates: Vec<IconCandidate>) -> Option<SiteIcon> {
+    // Declarations are often wrong or missing, so measure what actually downloads.
+    let mut best: Option<SiteIcon> = None;
+    let mut downloads = 0;
+    for candidate in candidates {
+        let Ok((icon, record)) = download_icon(fetcher, &candidate.url) else {
+            continue;
+        };
+        let resolution = icon.resolution();
+        if !matches!(&best, Some((best, ..)) if best.resolution() >= resolution) {
+            best = Some((icon, record, candidate.is_maskable_only()));
         }
-        let sel_apple = Selector::parse(r#"link[rel~="apple-touch-icon"]"#).unwrap();
-        for el in doc.select(&sel_apple) {
-            if let Some(href) = el.value().attr("href") {
-                if let Ok(url) = base.join(href) {
-                    urls.push(url);
-                }
-            }
+        downloads += 1;
+        if resolution >= GOOD_ENOUGH_SIZE || downloads == MAX_ICON_DOWNLOADS {
+            break;
         }
     }
-    if let Ok(url) = base.join("/favicon.ico") {
-        urls.push(url);
+    best
+}
+
+fn cache_site_icon(
+    (icon, record, maskable): SiteIcon,
+    style: Normalization,
+    cache_dir: &Path,
+    icon_id: &str,
+) -> Result<IconResult> {
+    let result = render_icon(icon, maskable, style, cache_dir, icon_id)?;
+    record.save(cache_dir, icon_id)?;
+    Ok(result)
+}
+
+/// Ranked icon candidates of the page at `start_url` and its manifest. An unreachable
+/// page still yields the origin's `/favicon.ico`.
+fn site_candidates(fetcher: &dyn Fetcher, start_url: &Url) -> Vec<IconCandidate> {
+    let (html, base) = match fetcher.get(start_url, MAX_PAGE_BYTES) {
+        Ok(page) => (String::from_utf8_lossy(&page.body).into_owned(), page.url),
+        Err(_) => (String::new(), start_url.clone()),
+    };
+
+    let manifest = manifest::manifest_for_page(fetcher, &html, &base)
+        .ok()
+        .flatten();
+    page_candidates(&html, &base, manifest.as_ref())
+}
+
+/// Ranked icon candidates of a page already loaded and of its manifest.
+fn page_candidates(
+    html: &str,
+    base: &Url,
+    manifest: Option<&(Url, WebManifest)>,
+) -> Vec<IconCandidate> {
+    let mut candidates = discover_candidates(html, base);
+    if let Some((manifest_url, manifest)) = manifest {
+        candidates.extend(manifest_candidates(manifest, manifest_url));
     }
-    urls
+    rank_candidates(candidates)
 }
 
-fn download_and_render(url: &Url, cache_dir: &Path, icon_id: &str) -> Result<Vec<PathBuf>> {
-    let resp = CLIENT
-        .get(url.as_str())
-        .send()
-        .and_then(|r| r.error_for_status())
-        .context("fetch icon")?;
+/// A downloadable site icon with a thumbnail for the icon picker.
+#[derive(Debug, Clone)]
+pub struct IconPreview {
+    pub candidate: IconCandidate,
+    /// Measured edge in pixels; `None` for vector icons.
+    pub resolution: Option<u32>,
+    /// PNG thumbnail, [`PREVIEW_SIZE`] pixels square.
+    pub png: Vec<u8>,
+}
 
-    if let Some(len) = resp.content_length() {
-        if len > 5 * 1024 * 1024 {
-            bail!(IconError::Download("icon too large".into()))
+/// Downloads up to `limit` of the site's best icon candidates for the user to choose from.
+pub fn preview_candidates(start_url: &Url, limit: usize) -> Vec<IconPreview> {
+    preview_candidates_with(&fetch::default_fetcher(), start_url, limit)
+}
+
+/// [`preview_candidates`] downloading through `fetcher`.
+pub fn preview_candidates_with(
+    fetcher: &dyn Fetcher,
+    start_url: &Url,
+    limit: usize,
+) -> Vec<IconPreview> {
+    site_candidates(fetcher, start_url)
+        .into_iter()
+        .filter_map(|candidate| {
+            let (icon, _) = download_icon(fetcher, &candidate.url).ok()?;
+            let resolution = icon.resolution();
+            let thumbnail = match &icon {
+                DownloadedIcon::Raster(img) => {
+                    img.resize(PREVIEW_SIZE, PREVIEW_SIZE, FilterType::Lanczos3)
+                }
+                DownloadedIcon::Vector(tree) => svg::render_svg(tree, PREVIEW_SIZE).ok()?,
+            };
+            let mut png = Vec::new();
+            thumbnail
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .ok()?;
+            Some(IconPreview {
+                candidate,
+                resolution: (resolution != u32::MAX).then_some(resolution),
+                png,
+            })
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Caches the icon at `url`, typically a candidate the user picked, instead of the
+/// automatically chosen one.
+pub fn cache_icon_from_url(
+    url: &Url,
+    maskable: bool,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<IconResult> {
+    cache_icon_from_url_with(
+        &fetch::default_fetcher(),
+        url,
+        maskable,
+        icon_id,
+        cache_dir,
+        style,
+    )
+}
+
+/// [`cache_icon_from_url`] downloading through `fetcher`.
+pub fn cache_icon_from_url_with(
+    fetcher: &dyn Fetcher,
+    url: &Url,
+    maskable: bool,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<IconResult> {
+    fs::create_dir_all(cache_dir)?;
+    let (icon, record) = download_icon(fetcher, url)?;
+    let result = render_icon(icon, maskable, style, cache_dir, icon_id)?;
+    record.save(cache_dir, icon_id)?;
+    Ok(result)
+}
+
+/// Copies a local PNG, SVG, ICO, JPEG or WebP file into the cache as the app's custom
+/// icon and renders it. The copy lets the icon be restyled after the original is gone.
+pub fn import_custom_icon(
+    source: &Path,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<IconResult> {
+    fs::create_dir_all(cache_dir)?;
+    let len = fs::metadata(source)
+        .with_context(|| format!("read icon {source:?}"))?
+        .len();
+    if len > MAX_ICON_BYTES {
+        bail!(IconError::TooLarge {
+            limit: MAX_ICON_BYTES
+        });
+    }
+    let data = fs::read(source).with_context(|| format!("read icon {source:?}"))?;
+    let is_svg_file = source
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"));
+    let icon = decode_download(&data, is_svg_file.then_some("image/svg+xml"))?;
+
+    for stale in custom_icon_paths(cache_dir, icon_id) {
+        let _ = fs::remove_file(stale);
+    }
+    let [svg_copy, png_copy] = custom_icon_paths(cache_dir, icon_id);
+    match &icon {
+        DownloadedIcon::Vector(tree) => {
+            fs::write(
+                &svg_copy,
+                tree.to_string(&resvg::usvg::WriteOptions::default()),
+            )
+            .with_context(|| format!("write icon {svg_copy:?}"))?;
+        }
+        DownloadedIcon::Raster(img) => {
+            img.save_with_format(&png_copy, ImageFormat::Png)
+                .with_context(|| format!("write icon {png_copy:?}"))?;
         }
     }
+    // Nothing to re-check on the site for an icon the user supplied.
+    IconRecord::remove(cache_dir, icon_id);
+    render_icon(icon, false, style, cache_dir, icon_id)
+}
+
+/// Re-renders a custom icon stored by [`import_custom_icon`], e.g. after a style change.
+pub fn cache_custom_icon(
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<IconResult> {
+    let source = custom_icon_paths(cache_dir, icon_id)
+        .into_iter()
+        .find(|path| path.exists())
+        .ok_or_else(|| IconError::NotFound(format!("custom icon for {icon_id}")))?;
+    let data = fs::read(&source).with_context(|| format!("read icon {source:?}"))?;
+    let icon = decode_download(&data, None)?;
+    render_icon(icon, false, style, cache_dir, icon_id)
+}
 
-    let bytes = resp.bytes().context("read icon bytes")?;
-    if bytes.len() > 5 * 1024 * 1024 {
-        bail!(IconError::Download("icon too large".into()))
+/// Where an imported icon is kept: `<icon_id>-custom.svg` or `<icon_id>-custom.png`.
+fn custom_icon_paths(cache_dir: &Path, icon_id: &str) -> [PathBuf; 2] {
+    [
+        cache_dir.join(format!("{icon_id}-custom.svg")),
+        cache_dir.join(format!("{icon_id}-custom.png")),
+    ]
+}
+
+/// A decoded icon download.
+enum DownloadedIcon {
+    Raster(DynamicImage),
+    Vector(Box<resvg::usvg::Tree>),
+}
+
+impl DownloadedIcon {
+    /// Edge of the largest square it can fill without upscaling.
+    fn resolution(&self) -> u32 {
+        match self {
+            DownloadedIcon::Raster(img) => img.width().min(img.height()),
+            DownloadedIcon::Vector(_) => u32::MAX,
+        }
     }
-    let data = bytes.to_vec();
-    let img = decode_icon(&data).context("decode icon image")?;
-    resize_and_write(&img, cache_dir, icon_id)
+}
+
+/// Downloads and decodes `url`, with the record to keep if it becomes the app icon.
+fn download_icon(fetcher: &dyn Fetcher, url: &Url) -> Result<(DownloadedIcon, IconRecord)> {
+    let fetched = fetcher.get(url, MAX_ICON_BYTES).context("fetch icon")?;
+    let icon = decode_download(&fetched.body, fetched.content_type.as_deref())?;
+    Ok((icon, IconRecord::for_download(url, &fetched)))
+}
+
+fn decode_download(data: &[u8], content_type: Option<&str>) -> Result<DownloadedIcon> {
+    if svg::is_svg(data, content_type) {
+        let tree = svg::parse_svg(data).context("parse svg icon")?;
+        return Ok(DownloadedIcon::Vector(Box::new(tree)));
+    }
+    let img = decode_icon(data).context("decode icon image")?;
+    Ok(DownloadedIcon::Raster(img))
+}
+
+fn render_icon(
+    icon: DownloadedIcon,
+    maskable: bool,
+    style: Normalization,
+    cache_dir: &Path,
+    icon_id: &str,
+) -> Result<IconResult> {
+    // Vectors are drawn per size unless they need reshaping, which works on a bitmap.
+    let reshape_vector = maskable || style == Normalization::Plate;
+    let icon = match icon {
+        DownloadedIcon::Vector(tree) if reshape_vector => {
+            DownloadedIcon::Raster(svg::render_svg(&tree, ICON_SIZES[ICON_SIZES.len() - 1])?)
+        }
+        icon => icon,
+    };
+    let (rendered_paths, scalable_path) = match icon {
+        DownloadedIcon::Vector(tree) => {
+            let mut rendered_paths = Vec::new();
+            for &size in ICON_SIZES {
+                let img = svg::render_svg(&tree, size)?;
+                rendered_paths.push(write_png(&img, cache_dir, icon_id, size)?);
+            }
+            let scalable_path = svg::write_scalable(&tree, cache_dir, icon_id)?;
+            (rendered_paths, Some(scalable_path))
+        }
+        DownloadedIcon::Raster(img) => {
+            let master = normalize::normalize(&img, style, maskable);
+            let rendered_paths = resize_and_write(&master, cache_dir, icon_id)?;
+            remove_stale_scalable(cache_dir, icon_id);
+            (rendered_paths, None)
+        }
+    };
+    Ok(IconResult {
+        icon_id: icon_id.to_string(),
+        rendered_paths,
+        scalable_path,
+    })
+}
+
+/// A raster icon replaced an earlier SVG; launchers must not keep picking up the old one.
+fn remove_stale_scalable(cache_dir: &Path, icon_id: &str) {
+    let _ = fs::remove_file(scalable_icon_path(cache_dir, icon_id));
 }
 
 fn decode_icon(data: &[u8]) -> Result<DynamicImage> {
     if let Ok(dir) = ico::IconDir::read(Cursor::new(data)) {
         if let Some(entry) = dir.entries().iter().max_by_key(|e| e.width()) {
+            check_dimensions(entry.width(), entry.height())?;
+            // `ico` inflates PNG frames without any limit, so give them the same
+            // treatment as a plain PNG.
+            if entry.is_png() {
+                return decode_image(entry.data());
+            }
             let decoded = entry.decode().context("decode ico frame")?;
             let width = decoded.width();
             let height = decoded.height();
@@ -136,68 +492,60 @@ fn decode_icon(data: &[u8]) -> Result<DynamicImage> {
             return Ok(DynamicImage::ImageRgba8(rgba_image));
         }
     }
-    let reader = image::ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    decode_image(data)
+}
+
+fn decode_image(data: &[u8]) -> Result<DynamicImage> {
+    let reader = || image::ImageReader::new(Cursor::new(data)).with_guessed_format();
+    let (width, height) = reader()?.into_dimensions()?;
+    check_dimensions(width, height)?;
+    // The header may lie about the size; the limits still bound what gets allocated.
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_ICON_DIMENSION);
+    limits.max_image_height = Some(MAX_ICON_DIMENSION);
+    limits.max_alloc = Some(2 * 4 * u64::from(MAX_ICON_DIMENSION).pow(2));
+    let mut reader = reader()?;
+    reader.limits(limits);
     Ok(reader.decode()?)
 }
 
+fn check_dimensions(width: u32, height: u32) -> Result<(), IconError> {
+    if width > MAX_ICON_DIMENSION || height > MAX_ICON_DIMENSION {
+        return Err(IconError::DimensionsTooLarge { width, height });
+    }
+    Ok(())
+}
+
 fn resize_and_write(img: &DynamicImage, cache_dir: &Path, icon_id: &str) -> Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
     for &size in ICON_SIZES {
         let resized = img.resize_exact(size, size, FilterType::Lanczos3);
-        let path = cache_dir.join(format!("{icon_id}-{size}x{size}.png"));
-        resized
-            .save_with_format(&path, ImageFormat::Png)
-            .with_context(|| format!("write icon {path:?}"))?;
-        paths.push(path);
+        paths.push(write_png(&resized, cache_dir, icon_id, size)?);
     }
     Ok(paths)
 }
 
-fn generate_fallback(url: &Url, cache_dir: &Path, icon_id: &str) -> Result<Vec<PathBuf>> {
-    let host = url.host_str().unwrap_or("?");
-    let initial = host
-        .trim_start_matches("www.")
-        .chars()
-        .next()
-        .unwrap_or('S')
-        .to_ascii_uppercase();
-    let seed = hash_seed(host.as_bytes());
-    let mut rng = StdRng::seed_from_u64(seed);
-    let bg = [rng.gen::<u8>(), rng.gen::<u8>(), rng.gen::<u8>()];
-
-    let mut base = DynamicImage::new_rgba8(512, 512).into_rgba8();
-    for pixel in base.pixels_mut() {
-        *pixel = image::Rgba([bg[0], bg[1], bg[2], 255]);
-    }
+fn write_png(img: &DynamicImage, cache_dir: &Path, icon_id: &str, size: u32) -> Result<PathBuf> {
+    let path = cache_dir.join(format!("{icon_id}-{size}x{size}.png"));
+    img.save_with_format(&path, ImageFormat::Png)
+        .with_context(|| format!("write icon {path:?}"))?;
+    Ok(path)
+}
 
-    let font_data = include_bytes!("./fonts/Roboto-Regular.ttf");
-    let font = rusttype::Font::try_from_bytes(font_data).context("load fallback font")?;
-    let scale = rusttype::Scale::uniform(220.0);
-    let v_metrics = font.v_metrics(scale);
-    let glyph = font.glyph(initial).scaled(scale);
-    let bb = glyph.exact_bounding_box().unwrap_or(rusttype::Rect {
-        min: rusttype::point(0.0, 0.0),
-        max: rusttype::point(0.0, 0.0),
-    });
-    let x = (512.0 - (bb.max.x - bb.min.x)) / 2.0 - bb.min.x;
-    let y = (512.0 - (bb.max.y - bb.min.y)) / 2.0 - bb.min.y - v_metrics.descent;
-
-    let outline = glyph.positioned(rusttype::point(x, y));
-    outline.draw(|gx, gy, coverage| {
-        let px = base.get_pixel_mut(gx, gy);
-        let alpha = (coverage * 255.0) as u8;
-        *px = image::Rgba([255, 255, 255, alpha]);
-    });
-
-    let img = DynamicImage::ImageRgba8(base);
-    resize_and_write(&img, cache_dir, icon_id)
-}
-
-fn hash_seed(data: &[u8]) -> u64 {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let out = hasher.finalize();
-    u64::from_le_bytes(out[0..8].try_into().unwrap())
+/// Renders the initials icon for an app without a usable site icon.
+fn generate_fallback(
+    name: &str,
+    start_url: &Url,
+    cache_dir: &Path,
+    icon_id: &str,
+) -> Result<Vec<PathBuf>> {
+    // Punycode hosts would otherwise yield an "X".
+    let host = start_url
+        .host_str()
+        .map(|host| idna::domain_to_unicode(host).0)
+        .unwrap_or_default();
+    let icon = FallbackIcon::new(name, &host);
+    resize_and_write(&DynamicImage::ImageRgba8(icon.render()), cache_dir, icon_id)
 }
 
 pub fn cache_dir_from_base(base: &Path) -> PathBuf {
@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    fetch::{default_fetcher, Fetcher},
+    MAX_PAGE_BYTES,
+};
+
+/// Upper bound for manifest bodies; real manifests are a few KiB.
+const MAX_MANIFEST_BYTES: u64 = 512 * 1024;
+/// Suggested names are cut to this many characters.
+const MAX_NAME_CHARS: usize = 60;
+
+/// Subset of the Web App Manifest that Sitewrap uses.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct WebManifest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub short_name: Option<String>,
+    #[serde(default)]
+    pub theme_color: Option<String>,
+    #[serde(default)]
+    pub shortcuts: Vec<ManifestShortcut>,
+    #[serde(default)]
+    pub icons: Vec<ManifestIcon>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct ManifestIcon {
+    pub src: String,
+    #[serde(default)]
+    pub sizes: Option<String>,
+    #[serde(default, rename = "type")]
+    pub mime: Option<String>,
+    #[serde(default)]
+    pub purpose: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct ManifestShortcut {
+    pub name: String,
+    #[serde(default)]
+    pub short_name: Option<String>,
+    pub url: String,
+}
+
+impl WebManifest {
+    pub fn parse(data: &str) -> Result<Self> {
+        serde_json::from_str(data).context("parse web app manifest")
+    }
+
+    /// Shortcuts as `(label, absolute url)` pairs, resolved against the manifest URL.
+    /// Entries with unparsable or non-http(s) URLs are dropped.
+    pub fn resolved_shortcuts(&self, manifest_url: &Url) -> Vec<(String, Url)> {
+        self.shortcuts
+            .iter()
+            .filter_map(|shortcut| {
+                let url = manifest_url.join(&shortcut.url).ok()?;
+                if !matches!(url.scheme(), "http" | "https") {
+                    return None;
+                }
+                let label = shortcut
+                    .short_name
+                    .as_deref()
+                    .filter(|s| !s.trim().is_empty())
+                    .unwrap_or(&shortcut.name)
+                    .trim()
+                    .to_string();
+                (!label.is_empty()).then_some((label, url))
+            })
+            .collect()
+    }
+}
+
+/// Returns the `<link rel="manifest">` target of a page, if any.
+pub fn discover_manifest_url(html: &str, base: &Url) -> Option<Url> {
+    let doc = Html::parse_document(html);
+    let selector = Selector::parse(r#"link[rel~="manifest"]"#).unwrap();
+    doc.select(&selector)
+        .filter_map(|el| el.value().attr("href"))
+        .find_map(|href| base.join(href).ok())
+}
+
+/// Fetches the page at `start_url` and the manifest it links to.
+/// Returns `Ok(None)` when the page declares no manifest.
+pub fn fetch_manifest(start_url: &Url) -> Result<Option<(Url, WebManifest)>> {
+    fetch_manifest_with(&default_fetcher(), start_url)
+}
+
+/// [`fetch_manifest`] downloading through `fetcher`.
+pub fn fetch_manifest_with(
+    fetcher: &dyn Fetcher,
+    start_url: &Url,
+) -> Result<Option<(Url, WebManifest)>> {
+    let page = fetcher
+        .get(start_url, MAX_PAGE_BYTES)
+        .context("fetch start page")?;
+    manifest_for_page(fetcher, &String::from_utf8_lossy(&page.body), &page.url)
+}
+
+/// What the create flow learns from a site before the app exists.
+#[derive(Debug, Clone, Default)]
+pub struct SiteInfo {
+    /// A name for the app, from the manifest or the page; see [`page_name`].
+    pub name: Option<String>,
+    /// The linked manifest and its URL. A manifest that fails to load is left out.
+    pub manifest: Option<(Url, WebManifest)>,
+    /// The start page as loaded: its URL after redirects and its HTML. `None` only in a
+    /// default value, for a site that could not be reached.
+    pub page: Option<(Url, String)>,
+}
+
+/// Fetches the page at `start_url` and its manifest. Fails only when the page itself
+/// cannot be loaded, which usually means the URL is wrong.
+pub fn fetch_site_info(start_url: &Url) -> Result<SiteInfo> {
+    fetch_site_info_with(&default_fetcher(), start_url)
+}
+
+/// [`fetch_site_info`] downloading through `fetcher`.
+pub fn fetch_site_info_with(fetcher: &dyn Fetcher, start_url: &Url) -> Result<SiteInfo> {
+    let page = fetcher
+        .get(start_url, MAX_PAGE_BYTES)
+        .context("fetch start page")?;
+    let html = String::from_utf8_lossy(&page.body).into_owned();
+    let manifest = manifest_for_page(fetcher, &html, &page.url).ok().flatten();
+    let manifest_name = manifest.as_ref().and_then(|(_, manifest)| {
+        [&manifest.name, &manifest.short_name]
+            .into_iter()
+            .find_map(|name| name.as_deref().and_then(clean_name))
+    });
+    Ok(SiteInfo {
+        name: manifest_name.or_else(|| page_name(&html)),
+        manifest,
+        page: Some((page.url, html)),
+    })
+}
+
+/// The name a page gives itself: `application-name` or `og:site_name` metadata, or else
+/// its `<title>`. Whitespace is collapsed and long names are cut.
+pub fn page_name(html: &str) -> Option<String> {
+    let doc = Html::parse_document(html);
+    let meta = Selector::parse(
+        r#"meta[name="application-name"], meta[property="og:site_name"], meta[name="apple-mobile-web-app-title"]"#,
+    )
+    .unwrap();
+    let title = Selector::parse("title").unwrap();
+    doc.select(&meta)
+        .filter_map(|el| el.value().attr("content"))
+        .find_map(clean_name)
+        .or_else(|| {
+            doc.select(&title)
+                .next()
+                .and_then(|el| clean_name(&el.text().collect::<String>()))
+        })
+}
+
+fn clean_name(name: &str) -> Option<String> {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    (!words.is_empty()).then(|| words.join(" ").chars().take(MAX_NAME_CHARS).collect())
+}
+
+/// Fetches the manifest linked from an already downloaded page.
+pub(crate) fn manifest_for_page(
+    fetcher: &dyn Fetcher,
+    html: &str,
+    page_url: &Url,
+) -> Result<Option<(Url, WebManifest)>> {
+    let Some(manifest_url) = discover_manifest_url(html, page_url) else {
+        return Ok(None);
+    };
+    let fetched = fetcher
+        .get(&manifest_url, MAX_MANIFEST_BYTES)
+        .context("fetch manifest")?;
+    let manifest = WebManifest::parse(&String::from_utf8_lossy(&fetched.body))?;
+    Ok(Some((fetched.url, manifest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_manifest_link() {
+        let base = Url::parse("https://mail.example/app/").unwrap();
+        let html =
+            r#"<html><head><link rel="manifest" href="/manifest.webmanifest"></head></html>"#;
+        assert_eq!(
+            discover_manifest_url(html, &base).unwrap().as_str(),
+            "https://mail.example/manifest.webmanifest"
+        );
+        assert!(discover_manifest_url("<html></html>", &base).is_none());
+    }
+
+    #[test]
+    fn page_names_prefer_metadata_over_the_title() {
+        assert_eq!(
+            page_name("<title>\n  Inbox  (3) —\tExample Mail </title>").as_deref(),
+            Some("Inbox (3) — Example Mail")
+        );
+        assert_eq!(
+            page_name(
+                r#"<head><title>Welcome | Notes</title>
+                <meta property="og:site_name" content=" Notes "></head>"#
+            )
+            .as_deref(),
+            Some("Notes")
+        );
+        assert_eq!(page_name("<title>   </title>"), None);
+        assert_eq!(
+            page_name(&format!("<title>{}</title>", "a".repeat(100))).map(|n| n.len()),
+            Some(60)
+        );
+    }
+
+    #[test]
+    fn resolves_shortcuts_relative_to_manifest() {
+        let manifest = WebManifest::parse(
+            r#"{
+                "name": "Mail",
+                "shortcuts": [
+                    {"name": "Compose new message", "short_name": "Compose", "url": "compose"},
+                    {"name": "Inbox", "url": "/inbox?src=shortcut"},
+                    {"name": "Bad", "url": "javascript:alert(1)"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let manifest_url = Url::parse("https://mail.example/app/manifest.json").unwrap();
+        let shortcuts = manifest.resolved_shortcuts(&manifest_url);
+        assert_eq!(shortcuts.len(), 2);
+        assert_eq!(shortcuts[0].0, "Compose");
+        assert_eq!(shortcuts[0].1.as_str(), "https://mail.example/app/compose");
+        assert_eq!(shortcuts[1].0, "Inbox");
+        assert_eq!(
+            shortcuts[1].1.as_str(),
+            "https://mail.example/inbox?src=shortcut"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Reshapes downloaded icons to sit well next to GNOME app icons.
+//!
+//! Proportions follow the GNOME icon grid on a 128 px canvas: square icons are 96 px
+//! with rounded corners, and free-form artwork fits a 112 px circle.
+
+use image::{imageops::FilterType, DynamicImage, Rgba, RgbaImage};
+
+use crate::color::{dominant_color, Rgb};
+
+/// Edge of the master image every cached size is scaled down from.
+pub(crate) const MASTER: u32 = 512;
+/// GNOME square keyline: 96 of 128.
+pub(crate) const SQUARE_FRACTION: f32 = 96.0 / 128.0;
+/// Corner radius of the square keyline, relative to its edge.
+const CORNER_FRACTION: f32 = 16.0 / 96.0;
+/// Free-form artwork keyline: 112 of 128.
+const ARTWORK_FRACTION: f32 = 112.0 / 128.0;
+/// Glyph size on a plate, relative to the plate.
+const GLYPH_FRACTION: f32 = 0.6;
+/// Maskable icons keep their important content inside the central 80%.
+const MASKABLE_SAFE_ZONE: f32 = 0.8;
+/// Favicons smaller than this look blurry on their own and go on a plate.
+const SMALL_SOURCE: u32 = 64;
+/// Channel difference under which a pixel counts as the background color.
+const BACKGROUND_TOLERANCE: u8 = 12;
+
+/// How a downloaded icon is reshaped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Trim padding, round full-bleed art, and put small or boxed favicons on a plate.
+    #[default]
+    Auto,
+    /// Only scale; maskable icons are still cropped to their safe zone.
+    Original,
+    /// Always draw the trimmed glyph on a rounded, tinted plate.
+    Plate,
+}
+
+/// What the pixels say about how an icon was drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconAnalysis {
+    /// Uniform color shared by all four corners; transparent for cut-out icons.
+    pub background: Option<Rgba<u8>>,
+    /// Bounding box `(x, y, width, height)` of everything that is not background.
+    pub content: (u32, u32, u32, u32),
+}
+
+impl IconAnalysis {
+    pub fn has_transparent_background(&self) -> bool {
+        self.background.is_some_and(|color| color.0[3] < 16)
+    }
+
+    pub fn has_opaque_background(&self) -> bool {
+        self.background.is_some_and(|color| color.0[3] >= 240)
+    }
+}
+
+pub fn analyze(image: &RgbaImage) -> IconAnalysis {
+    let (width, height) = image.dimensions();
+    let corners = [
+        *image.get_pixel(0, 0),
+        *image.get_pixel(width - 1, 0),
+        *image.get_pixel(0, height - 1),
+        *image.get_pixel(width - 1, height - 1),
+    ];
+    let background = corners
+        .iter()
+        .all(|corner| similar(*corner, corners[0]))
+        .then_some(corners[0]);
+
+    let is_content = |pixel: Rgba<u8>| match background {
+        Some(bg) if bg.0[3] < 16 => pixel.0[3] >= 16,
+        Some(bg) => !similar(pixel, bg),
+        None => true,
+    };
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if is_content(*pixel) {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+    let content = if min_x > max_x {
+        (0, 0, width, height)
+    } else {
+        (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
+    };
+    IconAnalysis {
+        background,
+        content,
+    }
+}
+
+fn similar(a: Rgba<u8>, b: Rgba<u8>) -> bool {
+    if a.0[3] < 16 && b.0[3] < 16 {
+        return true;
+    }
+    a.0.iter()
+        .zip(b.0)
+        .all(|(x, y)| x.abs_diff(y) <= BACKGROUND_TOLERANCE)
+}
+
+/// Turns a decoded icon into the square master the cached sizes are made from.
+pub fn normalize(image: &DynamicImage, style: Normalization, maskable: bool) -> DynamicImage {
+    let source = image.to_rgba8();
+    if maskable {
+        // The platform is expected to crop these; do it ourselves with GNOME's shape.
+        let safe = crop_centered(&source, MASKABLE_SAFE_ZONE);
+        return DynamicImage::ImageRgba8(rounded_square(&safe));
+    }
+    if style == Normalization::Original {
+        return DynamicImage::ImageRgba8(source);
+    }
+
+    let analysis = analyze(&source);
+    let (x, y, width, height) = analysis.content;
+    let glyph = image::imageops::crop_imm(&source, x, y, width, height).to_image();
+    let plate_color = analysis.has_opaque_background().then(|| {
+        let [r, g, b, _] = analysis.background.unwrap().0;
+        Rgb(r, g, b)
+    });
+    let small = source.width().min(source.height()) < SMALL_SOURCE;
+
+    let master = match style {
+        Normalization::Plate => on_plate(&glyph, plate_color),
+        _ if analysis.background.is_none() => rounded_square(&source),
+        _ if analysis.has_transparent_background() && !small => fit_artwork(&glyph),
+        _ => on_plate(&glyph, plate_color),
+    };
+    DynamicImage::ImageRgba8(master)
+}
+
+/// The central `fraction` of a square-ish image.
+fn crop_centered(image: &RgbaImage, fraction: f32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let crop_w = ((width as f32 * fraction).round() as u32).max(1);
+    let crop_h = ((height as f32 * fraction).round() as u32).max(1);
+    image::imageops::crop_imm(
+        image,
+        (width - crop_w) / 2,
+        (height - crop_h) / 2,
+        crop_w,
+        crop_h,
+    )
+    .to_image()
+}
+
+/// Full-bleed art scaled to the square keyline with rounded corners.
+pub(crate) fn rounded_square(image: &RgbaImage) -> RgbaImage {
+    let edge = keyline(SQUARE_FRACTION);
+    let art = image::imageops::resize(image, edge, edge, FilterType::Lanczos3);
+    let mut canvas = RgbaImage::new(MASTER, MASTER);
+    let offset = (MASTER - edge) / 2;
+    let radius = edge as f32 * CORNER_FRACTION;
+    for (x, y, pixel) in art.enumerate_pixels() {
+        let coverage = rounded_rect_coverage(x as f32 + 0.5, y as f32 + 0.5, edge as f32, radius);
+        let mut pixel = *pixel;
+        pixel.0[3] = (f32::from(pixel.0[3]) * coverage).round() as u8;
+        canvas.put_pixel(x + offset, y + offset, pixel);
+    }
+    canvas
+}
+
+/// Cut-out art scaled, without distortion, to fit the artwork keyline.
+fn fit_artwork(glyph: &RgbaImage) -> RgbaImage {
+    let mut canvas = RgbaImage::new(MASTER, MASTER);
+    overlay_fitted(&mut canvas, glyph, keyline(ARTWORK_FRACTION));
+    canvas
+}
+
+/// The glyph centered on a rounded plate in `color`, or in a tint of the glyph's own color.
+fn on_plate(glyph: &RgbaImage, color: Option<Rgb>) -> RgbaImage {
+    let color = color.unwrap_or_else(|| plate_tint(glyph));
+    let edge = keyline(SQUARE_FRACTION);
+    let plate = RgbaImage::from_pixel(edge, edge, Rgba([color.0, color.1, color.2, 255]));
+    let mut canvas = rounded_square(&plate);
+    overlay_fitted(
+        &mut canvas,
+        glyph,
+        (edge as f32 * GLYPH_FRACTION).round() as u32,
+    );
+    canvas
+}
+
+/// A pale version of the glyph's color, or a dark one for light glyphs so they stay visible.
+fn plate_tint(glyph: &RgbaImage) -> Rgb {
+    let glyph_color = dominant_color(&DynamicImage::ImageRgba8(glyph.clone()));
+    match glyph_color {
+        Some(color) if color.relative_luminance() > 0.6 => mix(color, Rgb(0x3d, 0x38, 0x46), 0.85),
+        Some(color) => mix(color, Rgb(0xff, 0xff, 0xff), 0.85),
+        None => Rgb(0xf6, 0xf5, 0xf4),
+    }
+}
+
+fn mix(from: Rgb, to: Rgb, amount: f32) -> Rgb {
+    let channel =
+        |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * amount).round() as u8;
+    Rgb(
+        channel(from.0, to.0),
+        channel(from.1, to.1),
+        channel(from.2, to.2),
+    )
+}
+
+pub(crate) fn keyline(fraction: f32) -> u32 {
+    (MASTER as f32 * fraction).round() as u32
+}
+
+/// Scales `glyph` to fit a centered `box_edge` square and draws it over `canvas`.
+fn overlay_fitted(canvas: &mut RgbaImage, glyph: &RgbaImage, box_edge: u32) {
+    let (width, height) = glyph.dimensions();
+    let scale = box_edge as f32 / width.max(height) as f32;
+    let fitted_w = ((width as f32 * scale).round() as u32).max(1);
+    let fitted_h = ((height as f32 * scale).round() as u32).max(1);
+    let fitted = image::imageops::resize(glyph, fitted_w, fitted_h, FilterType::Lanczos3);
+    image::imageops::overlay(
+        canvas,
+        &fitted,
+        i64::from((MASTER - fitted_w) / 2),
+        i64::from((MASTER - fitted_h) / 2),
+    );
+}
+
+/// Anti-aliased coverage of the point `(x, y)` by a rounded square of `edge` at the origin.
+fn rounded_rect_coverage(x: f32, y: f32, edge: f32, radius: f32) -> f32 {
+    let half = edge / 2.0;
+    let dx = ((x - half).abs() - (half - radius)).max(0.0);
+    let dy = ((y - half).abs() - (half - radius)).max(0.0);
+    let distance = (dx * dx + dy * dy).sqrt() - radius;
+    (0.5 - distance).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alpha(image: &DynamicImage, x: u32, y: u32) -> u8 {
+        image.to_rgba8().get_pixel(x, y).0[3]
+    }
+
+    #[test]
+    fn detects_padding_and_background() {
+        let mut icon = RgbaImage::new(64, 64);
+        for x in 20..30 {
+            for y in 10..50 {
+                icon.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            }
+        }
+        let analysis = analyze(&icon);
+        assert!(analysis.has_transparent_background());
+        assert_eq!(analysis.content, (20, 10, 10, 40));
+
+        let boxed = RgbaImage::from_pixel(8, 8, Rgba([250, 250, 250, 255]));
+        let analysis = analyze(&boxed);
+        assert!(analysis.has_opaque_background());
+        assert_eq!(analysis.content, (0, 0, 8, 8));
+    }
+
+    #[test]
+    fn maskable_art_is_cropped_to_a_rounded_square() {
+        // Red safe zone inside a blue bleed area.
+        let mut icon = RgbaImage::from_pixel(100, 100, Rgba([0, 0, 255, 255]));
+        for x in 10..90 {
+            for y in 10..90 {
+                icon.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+        let master = normalize(&DynamicImage::ImageRgba8(icon), Normalization::Auto, true);
+        let pixels = master.to_rgba8();
+        let inset = (MASTER - keyline(SQUARE_FRACTION)) / 2;
+        // Outside the keyline and at the rounded corner: transparent.
+        assert_eq!(alpha(&master, 0, 0), 0);
+        assert_eq!(alpha(&master, inset, inset), 0);
+        // The bleed area is gone; the edge of the square is the safe zone's red.
+        assert_eq!(pixels.get_pixel(MASTER / 2, inset + 2).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn small_favicons_go_on_a_tinted_plate() {
+        let mut icon = RgbaImage::new(16, 16);
+        for x in 4..12 {
+            for y in 4..12 {
+                icon.put_pixel(x, y, Rgba([200, 30, 40, 255]));
+            }
+        }
+        let master = normalize(&DynamicImage::ImageRgba8(icon), Normalization::Auto, false);
+        let pixels = master.to_rgba8();
+        let inset = (MASTER - keyline(SQUARE_FRACTION)) / 2;
+        // The plate is a pale red, and the glyph sits in its middle.
+        let [r, g, b, a] = pixels.get_pixel(MASTER / 2, inset + 4).0;
+        assert_eq!(a, 255);
+        assert!(r > 240 && g > 200 && b > 200, "{:?}", (r, g, b));
+        assert_eq!(
+            pixels.get_pixel(MASTER / 2, MASTER / 2).0,
+            [200, 30, 40, 255]
+        );
+    }
+
+    #[test]
+    fn large_cut_out_art_is_trimmed_to_the_keyline() {
+        let mut icon = RgbaImage::new(256, 256);
+        for x in 64..192 {
+            for y in 64..192 {
+                icon.put_pixel(x, y, Rgba([0, 120, 0, 255]));
+            }
+        }
+        let master = normalize(
+            &DynamicImage::ImageRgba8(icon.clone()),
+            Normalization::Auto,
+            false,
+        );
+        let inset = (MASTER - keyline(ARTWORK_FRACTION)) / 2;
+        assert_eq!(alpha(&master, inset + 2, inset + 2), 255);
+        assert_eq!(alpha(&master, inset - 2, inset - 2), 0);
+
+        let original = normalize(
+            &DynamicImage::ImageRgba8(icon),
+            Normalization::Original,
+            false,
+        );
+        assert_eq!(original.width(), 256);
+        assert_eq!(alpha(&original, 70, 70), 255);
+        assert_eq!(alpha(&original, 10, 10), 0);
+    }
+}
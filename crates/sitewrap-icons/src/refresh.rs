@@ -0,0 +1,250 @@
+//! Where a cached icon came from, so it can be re-checked with a conditional request
+//! instead of a full re-download.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    best_site_icon, cache_icon_from_url_with, cache_site_icon, decode_download,
+    fetch::{default_fetcher, Conditional, Fetched, Fetcher},
+    fetch_and_cache_icon_with, render_icon, site_candidates, IconResult, Normalization,
+    MAX_ICON_BYTES,
+};
+
+/// Kept as `<icon_id>-source.json` next to the PNG renditions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IconRecord {
+    /// The downloaded icon; `None` when it was generated because the site had none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Hex SHA-256 of the downloaded bytes, to spot servers that resend the same icon.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Seconds since the Unix epoch of the last download or check.
+    #[serde(default)]
+    pub checked_at: u64,
+}
+
+impl IconRecord {
+    /// A record for the download `fetched` from `url`, checked now.
+    pub(crate) fn for_download(url: &Url, fetched: &Fetched) -> Self {
+        Self {
+            url: Some(url.to_string()),
+            etag: fetched.etag.clone(),
+            last_modified: fetched.last_modified.clone(),
+            sha256: Some(sha256_hex(&fetched.body)),
+            checked_at: now(),
+        }
+    }
+
+    /// A record for a generated icon, so it is rechecked on the same schedule.
+    pub(crate) fn generated() -> Self {
+        Self {
+            checked_at: now(),
+            ..Self::default()
+        }
+    }
+
+    pub fn load(cache_dir: &Path, icon_id: &str) -> Option<Self> {
+        let data = fs::read(record_path(cache_dir, icon_id)).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    pub(crate) fn save(&self, cache_dir: &Path, icon_id: &str) -> Result<()> {
+        let path = record_path(cache_dir, icon_id);
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write icon record {path:?}"))
+    }
+
+    pub(crate) fn remove(cache_dir: &Path, icon_id: &str) {
+        let _ = fs::remove_file(record_path(cache_dir, icon_id));
+    }
+
+    pub fn checked_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.checked_at)
+    }
+}
+
+pub fn record_path(cache_dir: &Path, icon_id: &str) -> PathBuf {
+    cache_dir.join(format!("{icon_id}-source.json"))
+}
+
+/// Whether `icon_id` was last checked longer than `max_age` ago, or never.
+pub fn needs_refresh(cache_dir: &Path, icon_id: &str, max_age: Duration) -> bool {
+    IconRecord::load(cache_dir, icon_id).is_none_or(|record| {
+        SystemTime::now()
+            .duration_since(record.checked_at())
+            .is_ok_and(|age| age > max_age)
+    })
+}
+
+/// What a cached icon is refreshed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IconOrigin {
+    /// Whatever icon the site at `start_url` declares best; initials of `app_name` when
+    /// it declares none.
+    Site { start_url: Url, app_name: String },
+    /// One icon URL the user picked.
+    Pinned { url: Url, maskable: bool },
+}
+
+#[derive(Debug, Clone)]
+pub enum RefreshOutcome {
+    Unchanged,
+    /// The renditions were rewritten with a different image.
+    Updated(IconResult),
+}
+
+pub fn refresh_icon(
+    origin: &IconOrigin,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<RefreshOutcome> {
+    refresh_icon_with(&default_fetcher(), origin, icon_id, cache_dir, style)
+}
+
+/// Re-checks a cached icon. The recorded download is revalidated with its ETag and
+/// Last-Modified; only when it changed or disappeared is the site searched again. The
+/// outcome compares the rendered image, so a new download of the same artwork, or a
+/// site still without icons, is `Unchanged`. A downloaded icon is never swapped for a
+/// generated one: when nothing downloads, the site is more likely down than iconless,
+/// and the failed download is returned so the icon is checked again next time.
+pub fn refresh_icon_with(
+    fetcher: &dyn Fetcher,
+    origin: &IconOrigin,
+    icon_id: &str,
+    cache_dir: &Path,
+    style: Normalization,
+) -> Result<RefreshOutcome> {
+    let before = rendered_digest(cache_dir, icon_id);
+    let record = IconRecord::load(cache_dir, icon_id);
+    let recorded_url = record
+        .as_ref()
+        .and_then(|record| record.url.as_deref())
+        .and_then(|url| Url::parse(url).ok())
+        // A pin to a different URL than the last download starts over.
+        .filter(|url| !matches!(origin, IconOrigin::Pinned { url: pinned, .. } if pinned != url));
+
+    let had_download = recorded_url.is_some();
+    let mut download_err = None;
+
+    if let (Some(mut record), Some(url)) = (record, recorded_url) {
+        let response = fetcher.get_if_changed(
+            &url,
+            MAX_ICON_BYTES,
+            record.etag.as_deref(),
+            record.last_modified.as_deref(),
+        );
+        match (response, origin) {
+            (Ok(Conditional::NotModified), _) => {
+                record.checked_at = now();
+                record.save(cache_dir, icon_id)?;
+                return Ok(RefreshOutcome::Unchanged);
+            }
+            (Ok(Conditional::Modified(fetched)), _)
+                if record.sha256.as_deref() == Some(sha256_hex(&fetched.body).as_str()) =>
+            {
+                record = IconRecord::for_download(&url, &fetched);
+                record.save(cache_dir, icon_id)?;
+                return Ok(RefreshOutcome::Unchanged);
+            }
+            (Ok(Conditional::Modified(fetched)), IconOrigin::Pinned { maskable, .. }) => {
+                let icon = decode_download(&fetched.body, fetched.content_type.as_deref())?;
+                let result = render_icon(icon, *maskable, style, cache_dir, icon_id)?;
+                IconRecord::for_download(&url, &fetched).save(cache_dir, icon_id)?;
+                return Ok(outcome(before, cache_dir, result));
+            }
+            (Err(err), IconOrigin::Pinned { .. }) => return Err(err),
+            // The site changed or moved its icon; look at what it declares now.
+            (Ok(Conditional::Modified(_)), IconOrigin::Site { .. }) => {}
+            (Err(err), IconOrigin::Site { .. }) => download_err = Some(err),
+        }
+    }
+
+    let result = match origin {
+        IconOrigin::Site { start_url, .. } if had_download => {
+            match best_site_icon(fetcher, site_candidates(fetcher, start_url)) {
+                Some(best) => cache_site_icon(best, style, cache_dir, icon_id)?,
+                None => match download_err {
+                    Some(err) => return Err(err.context("the site is unreachable")),
+                    None => return Ok(RefreshOutcome::Unchanged),
+                },
+            }
+        }
+        IconOrigin::Site {
+            start_url,
+            app_name,
+        } => fetch_and_cache_icon_with(fetcher, start_url, app_name, icon_id, cache_dir, style)?,
+        IconOrigin::Pinned { url, maskable } => {
+            cache_icon_from_url_with(fetcher, url, *maskable, icon_id, cache_dir, style)?
+        }
+    };
+    Ok(outcome(before, cache_dir, result))
+}
+
+fn outcome(before: Option<String>, cache_dir: &Path, result: IconResult) -> RefreshOutcome {
+    if before.is_some() && before == rendered_digest(cache_dir, &result.icon_id) {
+        RefreshOutcome::Unchanged
+    } else {
+        RefreshOutcome::Updated(result)
+    }
+}
+
+/// Digest of the largest rendition, which changes whenever the artwork does.
+fn rendered_digest(cache_dir: &Path, icon_id: &str) -> Option<String> {
+    let largest = crate::ICON_SIZES[crate::ICON_SIZES.len() - 1];
+    fs::read(cache_dir.join(format!("{icon_id}-{largest}x{largest}.png")))
+        .ok()
+        .map(|data| sha256_hex(&data))
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_without_a_recent_check_need_refreshing() {
+        let dir = std::env::temp_dir().join(format!("sitewrap-record-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let week = Duration::from_secs(7 * 24 * 60 * 60);
+        assert!(needs_refresh(&dir, "demo", week), "never checked");
+
+        IconRecord::generated().save(&dir, "demo").unwrap();
+        assert!(!needs_refresh(&dir, "demo", week));
+
+        let stale = IconRecord {
+            checked_at: now() - 8 * 24 * 60 * 60,
+            ..IconRecord::default()
+        };
+        stale.save(&dir, "demo").unwrap();
+        assert!(needs_refresh(&dir, "demo", week));
+        assert_eq!(IconRecord::load(&dir, "demo"), Some(stale));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
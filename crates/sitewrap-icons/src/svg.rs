@@ -0,0 +1,167 @@
+//! SVG icons, rasterized at each cached size instead of being scaled from one bitmap.
+
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use image::{DynamicImage, ImageFormat, ImageReader, RgbaImage};
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{self, ImageHrefResolver, ImageKind, Tree},
+};
+
+use crate::{IconError, MAX_ICON_BYTES, MAX_ICON_DIMENSION};
+
+/// Whether a download is an SVG document, by content type or by sniffing the markup.
+pub(crate) fn is_svg(data: &[u8], content_type: Option<&str>) -> bool {
+    if content_type.is_some_and(|value| value.trim_start().starts_with("image/svg+xml")) {
+        return true;
+    }
+    let head = &data[..data.len().min(1024)];
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    text.starts_with('<') && text.contains("<svg")
+}
+
+/// Parses an SVG icon. External references are never loaded, so a hostile icon cannot
+/// pull local files into the rendering. Embedded `data:` images are kept only when they
+/// are rasters within the icon size limits, and compressed (svgz) documents may not
+/// inflate past the download limit. Text is skipped because no fonts are loaded.
+pub(crate) fn parse_svg(data: &[u8]) -> Result<Tree> {
+    let options = usvg::Options {
+        image_href_resolver: ImageHrefResolver {
+            resolve_data: Box::new(|_, data, _| embedded_raster(data)),
+            resolve_string: Box::new(|_, _| None),
+        },
+        ..usvg::Options::default()
+    };
+    // usvg would inflate svgz without any bound.
+    let inflated;
+    let data = if data.starts_with(&[0x1f, 0x8b]) {
+        inflated = inflate_svgz(data)?;
+        inflated.as_slice()
+    } else {
+        data
+    };
+    Tree::from_data(data, &options).map_err(|err| IconError::Decode(err.to_string()).into())
+}
+
+fn inflate_svgz(data: &[u8]) -> Result<Vec<u8>> {
+    let mut inflated = Vec::new();
+    GzDecoder::new(data)
+        .take(MAX_ICON_BYTES + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|err| IconError::Decode(err.to_string()))?;
+    if inflated.len() as u64 > MAX_ICON_BYTES {
+        return Err(IconError::TooLarge {
+            limit: MAX_ICON_BYTES,
+        }
+        .into());
+    }
+    Ok(inflated)
+}
+
+/// Keeps an embedded PNG, JPEG or WebP whose header is within [`MAX_ICON_DIMENSION`];
+/// nested SVG documents and anything else are dropped.
+fn embedded_raster(data: Arc<Vec<u8>>) -> Option<ImageKind> {
+    let reader = ImageReader::new(Cursor::new(data.as_slice()))
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    let (width, height) = reader.into_dimensions().ok()?;
+    if width > MAX_ICON_DIMENSION || height > MAX_ICON_DIMENSION {
+        return None;
+    }
+    match format {
+        ImageFormat::Png => Some(ImageKind::PNG(data)),
+        ImageFormat::Jpeg => Some(ImageKind::JPEG(data)),
+        ImageFormat::WebP => Some(ImageKind::WEBP(data)),
+        _ => None,
+    }
+}
+
+/// Renders `tree` centered in a transparent `size`×`size` square, keeping its aspect ratio.
+pub(crate) fn render_svg(tree: &Tree, size: u32) -> Result<DynamicImage> {
+    let mut pixmap = Pixmap::new(size, size).context("allocate icon pixmap")?;
+    let view = tree.size();
+    let scale = size as f32 / view.width().max(view.height());
+    let dx = (size as f32 - view.width() * scale) / 2.0;
+    let dy = (size as f32 - view.height() * scale) / 2.0;
+    resvg::render(
+        tree,
+        Transform::from_row(scale, 0.0, 0.0, scale, dx, dy),
+        &mut pixmap.as_mut(),
+    );
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let image = RgbaImage::from_raw(size, size, data).context("create rgba image from svg")?;
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+/// Where the scalable original of `icon_id` is kept next to its PNG renditions.
+pub fn scalable_icon_path(cache_dir: &Path, icon_id: &str) -> PathBuf {
+    cache_dir.join(format!("{icon_id}.svg"))
+}
+
+/// Writes the normalized document (scripts, external references and unknown elements
+/// dropped) as the scalable icon for launchers.
+pub(crate) fn write_scalable(tree: &Tree, cache_dir: &Path, icon_id: &str) -> Result<PathBuf> {
+    let path = scalable_icon_path(cache_dir, icon_id);
+    fs::write(&path, tree.to_string(&usvg::WriteOptions::default()))
+        .with_context(|| format!("write icon {path:?}"))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDE_SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 10">
+        <script>alert(1)</script>
+        <rect width="20" height="10" fill="#ff0000"/>
+    </svg>"##;
+
+    #[test]
+    fn sniffs_svg_documents() {
+        assert!(is_svg(WIDE_SVG.as_bytes(), None));
+        assert!(is_svg(b"\xef\xbb\xbf<?xml version=\"1.0\"?>\n<svg/>", None));
+        assert!(is_svg(b"", Some("image/svg+xml; charset=utf-8")));
+        assert!(!is_svg(b"\x89PNG\r\n\x1a\n", Some("image/png")));
+        assert!(!is_svg(b"<html><body>not found</body></html>", None));
+    }
+
+    #[test]
+    fn renders_crisply_at_each_size_and_keeps_aspect_ratio() {
+        let tree = parse_svg(WIDE_SVG.as_bytes()).unwrap();
+        for size in [16, 512] {
+            let image = render_svg(&tree, size).unwrap().into_rgba8();
+            assert_eq!(image.dimensions(), (size, size));
+            // Letterboxed: transparent above, solid red through the middle.
+            assert_eq!(image.get_pixel(size / 2, 0).0[3], 0);
+            assert_eq!(image.get_pixel(size / 2, size / 2).0, [255, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn scalable_copy_drops_scripts() {
+        let dir = std::env::temp_dir().join(format!("sitewrap-svg-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let tree = parse_svg(WIDE_SVG.as_bytes()).unwrap();
+        let path = write_scalable(&tree, &dir, "demo").unwrap();
+        assert_eq!(path, scalable_icon_path(&dir, "demo"));
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("<svg") && !written.contains("script"));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
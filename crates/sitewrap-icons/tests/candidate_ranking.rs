@@ -0,0 +1,93 @@
+//! Icon discovery and ranking over page snippets saved from real sites.
+
+use sitewrap_icons::{
+    candidates::CandidateSource, discover_candidates, manifest_candidates, rank_candidates,
+    IconCandidate, WebManifest,
+};
+use url::Url;
+
+fn ranked_urls(candidates: Vec<IconCandidate>) -> Vec<String> {
+    rank_candidates(candidates)
+        .into_iter()
+        .map(|candidate| candidate.url.to_string())
+        .collect()
+}
+
+#[test]
+fn vector_favicon_beats_png_and_mask_icon_is_ignored() {
+    let base = Url::parse("https://github.com/").unwrap();
+    let candidates = discover_candidates(include_str!("fixtures/github.html"), &base);
+    assert_eq!(
+        ranked_urls(candidates),
+        [
+            "https://github.githubassets.com/favicons/favicon.svg",
+            "https://github.githubassets.com/favicons/favicon.png",
+            "https://github.com/favicon.ico",
+        ]
+    );
+}
+
+#[test]
+fn apple_touch_icon_beats_undeclared_favicons() {
+    let base = Url::parse("https://en.wikipedia.org/wiki/Main_Page").unwrap();
+    let candidates = discover_candidates(include_str!("fixtures/wikipedia.html"), &base);
+    assert_eq!(
+        ranked_urls(candidates),
+        [
+            "https://en.wikipedia.org/static/apple-touch/wikipedia.png",
+            "https://en.wikipedia.org/static/favicon/wikipedia.ico",
+            "https://en.wikipedia.org/favicon.ico",
+        ]
+    );
+}
+
+#[test]
+fn declared_sizes_rank_largest_first_and_prefer_lossless() {
+    let base = Url::parse("https://recipes.example/").unwrap();
+    let candidates = discover_candidates(include_str!("fixtures/realfavicongenerator.html"), &base);
+    assert_eq!(
+        ranked_urls(candidates),
+        [
+            "https://recipes.example/android-chrome-192x192.png",
+            "https://recipes.example/static/logo-192.jpg",
+            "https://recipes.example/apple-touch-icon.png",
+            "https://recipes.example/favicon-32x32.png",
+            // `shortcut icon` has no sizes; guessed at 32, it follows the declared 32px icon.
+            "https://recipes.example/favicon.ico",
+            "https://recipes.example/favicon-16x16.png",
+        ]
+    );
+}
+
+#[test]
+fn manifest_icons_join_the_page_icons() {
+    let base = Url::parse("https://recipes.example/").unwrap();
+    let manifest_url = base.join("/site.webmanifest").unwrap();
+    let manifest = WebManifest::parse(include_str!("fixtures/site.webmanifest")).unwrap();
+
+    let mut candidates =
+        discover_candidates(include_str!("fixtures/realfavicongenerator.html"), &base);
+    candidates.extend(manifest_candidates(&manifest, &manifest_url));
+    let ranked = rank_candidates(candidates);
+
+    let top: Vec<_> = ranked
+        .iter()
+        .take(3)
+        .map(|candidate| (candidate.url.path(), candidate.source))
+        .collect();
+    assert_eq!(
+        top,
+        [
+            ("/icons/icon-512.png", CandidateSource::Manifest),
+            ("/icons/maskable-512.png", CandidateSource::Manifest),
+            // Ties with the page's 192px PNG; manifest icons are what installers use.
+            ("/icons/icon-192.png", CandidateSource::Manifest),
+        ]
+    );
+    assert!(
+        ranked
+            .iter()
+            .all(|candidate| candidate.url.path() != "/icons/mono-1024.png"),
+        "monochrome-only icons are not app icons"
+    );
+}
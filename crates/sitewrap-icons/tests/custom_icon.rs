@@ -0,0 +1,61 @@
+//! Importing a local icon file and re-rendering it later.
+
+use std::{fs, path::PathBuf};
+
+use sitewrap_icons::{cache_custom_icon, import_custom_icon, Normalization, ICON_SIZES};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sitewrap-icons-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn imports_png_and_svg_and_restyles_from_the_stored_copy() {
+    let dir = temp_dir("custom");
+    let cache = dir.join("cache");
+
+    let png = dir.join("logo.png");
+    image::RgbaImage::from_pixel(40, 40, image::Rgba([20, 90, 200, 255]))
+        .save(&png)
+        .unwrap();
+    let result = import_custom_icon(&png, "demo", &cache, Normalization::Original).unwrap();
+    assert_eq!(result.rendered_paths.len(), ICON_SIZES.len());
+    assert!(result.scalable_path.is_none());
+    let largest = image::open(cache.join("demo-512x512.png")).unwrap();
+    assert_eq!(largest.width(), 512);
+
+    let svg = dir.join("logo.svg");
+    fs::write(
+        &svg,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><circle cx="5" cy="5" r="5" fill="#e01b24"/></svg>"##,
+    )
+    .unwrap();
+    let result = import_custom_icon(&svg, "demo", &cache, Normalization::Auto).unwrap();
+    assert_eq!(
+        result.scalable_path,
+        Some(cache.join("demo.svg")),
+        "an imported SVG is exported as a scalable icon"
+    );
+
+    // The originals can go away; restyling works from the cached copy.
+    fs::remove_file(&png).unwrap();
+    fs::remove_file(&svg).unwrap();
+    let result = cache_custom_icon("demo", &cache, Normalization::Plate).unwrap();
+    assert_eq!(result.rendered_paths.len(), ICON_SIZES.len());
+    assert!(result.scalable_path.is_none());
+    assert!(!cache.join("demo.svg").exists());
+
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rejects_files_that_are_not_images() {
+    let dir = temp_dir("custom-invalid");
+    let text = dir.join("notes.png");
+    fs::write(&text, "not an image").unwrap();
+    assert!(import_custom_icon(&text, "demo", &dir.join("cache"), Normalization::Auto).is_err());
+    assert!(cache_custom_icon("demo", &dir.join("cache"), Normalization::Auto).is_err());
+    let _ = fs::remove_dir_all(dir);
+}
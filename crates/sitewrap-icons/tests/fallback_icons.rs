@@ -0,0 +1,126 @@
+//! Generated fallback icons compared with golden images in `tests/golden`.
+//!
+//! Run with `SITEWRAP_BLESS=1` to rewrite the golden images after an intended change.
+
+use std::path::{Path, PathBuf};
+
+use image::{imageops::FilterType, RgbaImage};
+use sitewrap_icons::FallbackIcon;
+
+/// Edge of the golden images; the 512 px masters are scaled down to keep them small.
+const GOLDEN_SIZE: u32 = 128;
+/// Per-channel difference allowed for rounding differences between platforms.
+const TOLERANCE: u8 = 4;
+
+/// Only the bundled font, so results do not depend on what is installed.
+fn icon(name: &str, host: &str) -> FallbackIcon {
+    FallbackIcon::with_font_dirs(name, host, &[])
+}
+
+fn assert_golden(icon: &FallbackIcon, golden: &str) {
+    let actual = image::imageops::resize(
+        &icon.render(),
+        GOLDEN_SIZE,
+        GOLDEN_SIZE,
+        FilterType::Triangle,
+    );
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{golden}.png"));
+    if std::env::var_os("SITEWRAP_BLESS").is_some() {
+        actual.save(&path).unwrap();
+        return;
+    }
+    let expected = image::open(&path)
+        .unwrap_or_else(|err| panic!("{path:?}: {err}; run with SITEWRAP_BLESS=1"))
+        .into_rgba8();
+    assert_eq!(actual.dimensions(), expected.dimensions());
+    let worst = worst_difference(&actual, &expected);
+    if worst > TOLERANCE {
+        let failed = std::env::temp_dir().join(format!("{golden}-actual.png"));
+        actual.save(&failed).unwrap();
+        panic!("{golden} differs by up to {worst}; rendered {failed:?}");
+    }
+}
+
+fn worst_difference(a: &RgbaImage, b: &RgbaImage) -> u8 {
+    a.pixels()
+        .zip(b.pixels())
+        .flat_map(|(pa, pb)| pa.0.into_iter().zip(pb.0).map(|(x, y)| x.abs_diff(y)))
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn a_single_word_gets_one_initial() {
+    let icon = icon("mail", "mail.example.com");
+    assert_eq!(icon.label(), "M");
+    assert_golden(&icon, "fallback-mail");
+}
+
+#[test]
+fn two_words_get_two_initials() {
+    let icon = icon("Google Calendar", "calendar.google.com");
+    assert_eq!(icon.label(), "GC");
+    assert_golden(&icon, "fallback-google-calendar");
+}
+
+#[test]
+fn accented_and_cyrillic_initials_are_upper_cased() {
+    let accented = icon("élan vital", "elan.example");
+    assert_eq!(accented.label(), "ÉV");
+    assert_golden(&accented, "fallback-elan-vital");
+
+    let cyrillic = icon("почта", "mail.ru");
+    assert_eq!(cyrillic.label(), "П");
+    assert_golden(&cyrillic, "fallback-pochta");
+}
+
+#[test]
+fn the_host_is_used_when_the_name_cannot_be_drawn() {
+    let unnamed = icon("", "www.wikipedia.org");
+    assert_eq!(unnamed.label(), "W");
+    assert_golden(&unnamed, "fallback-wikipedia");
+
+    // Without an installed CJK or emoji font, nothing in these names can be drawn.
+    assert_eq!(icon("微信", "weixin.qq.com").label(), "W");
+    assert_eq!(icon("🚀", "launch.example").label(), "L");
+    assert_eq!(icon("🚀 Rocket Chat", "chat.example").label(), "RC");
+    assert_eq!(icon("", "").label(), "?");
+}
+
+#[test]
+fn emoji_are_drawn_with_an_installed_font() {
+    // DejaVu Sans has outlines for a few emoji, such as the hot beverage.
+    let dejavu = PathBuf::from("/usr/share/fonts/truetype/dejavu");
+    if !dejavu.join("DejaVuSans.ttf").exists() {
+        eprintln!("DejaVu Sans is not installed; skipping");
+        return;
+    }
+    let coffee = FallbackIcon::with_font_dirs("☕ Coffee Club", "coffee.example", &[dejavu]);
+    assert_eq!(coffee.label(), "☕");
+    let ink = coffee.foreground();
+    let drawn = coffee
+        .render()
+        .pixels()
+        .filter(|pixel| pixel.0[..3] == [ink.0, ink.1, ink.2])
+        .count();
+    assert!(
+        drawn > 1_000,
+        "the emoji is drawn over the plate ({drawn} px)"
+    );
+}
+
+#[test]
+fn colors_are_stable_and_readable() {
+    let first = icon("Notes", "notes.example");
+    let again = icon("Notes", "elsewhere.example");
+    assert_eq!(first.background(), again.background());
+    assert!(first.background().contrast_ratio(first.foreground()) >= 4.5);
+
+    let backgrounds: std::collections::HashSet<_> = ["Notes", "Mail", "Maps", "Music", "Chat"]
+        .into_iter()
+        .map(|name| icon(name, "").background())
+        .collect();
+    assert!(backgrounds.len() > 1, "names spread over the palette");
+}
@@ -0,0 +1,260 @@
+//! The limits [`HttpFetcher`] and [`Budgeted`] put on what a page can make us fetch,
+//! and the ones decoding puts on what a download may expand to.
+
+mod http_server;
+
+use std::{
+    fs,
+    io::{Cursor, Write},
+    time::Duration,
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+use http_server::{Route, TestServer};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use sitewrap_icons::{
+    cache_icon_from_url_with, Budgeted, FetchPolicy, Fetcher, HttpFetcher, IconError,
+    Normalization, MAX_ICON_BYTES, MAX_ICON_DIMENSION,
+};
+use url::Url;
+
+fn icon_error(err: anyhow::Error) -> IconError {
+    err.downcast_ref::<IconError>()
+        .cloned()
+        .unwrap_or_else(|| panic!("not an IconError: {err:#}"))
+}
+
+#[test]
+fn the_default_policy_refuses_local_addresses() {
+    let server = TestServer::start([("/icon.png", Route::Status(200))]);
+    let fetcher = HttpFetcher::default();
+
+    let err = fetcher
+        .get(&server.url("/icon.png"), MAX_ICON_BYTES)
+        .unwrap_err();
+    assert!(matches!(icon_error(err), IconError::PrivateAddress(_)));
+
+    let mut by_name = server.url("/icon.png");
+    by_name.set_host(Some("localhost")).unwrap();
+    let err = fetcher.get(&by_name, MAX_ICON_BYTES).unwrap_err();
+    assert!(matches!(icon_error(err), IconError::PrivateAddress(host) if host == "localhost"));
+    assert!(server.requests().is_empty(), "nothing reached the server");
+}
+
+#[test]
+fn only_http_and_https_are_fetched() {
+    let fetcher = http_server::fetcher();
+    for url in [
+        "file:///etc/passwd",
+        "ftp://example.com/icon.png",
+        "data:image/png,x",
+    ] {
+        let err = fetcher
+            .get(&Url::parse(url).unwrap(), MAX_ICON_BYTES)
+            .unwrap_err();
+        assert!(
+            matches!(icon_error(err), IconError::UnsupportedScheme(_)),
+            "{url}"
+        );
+    }
+
+    let server = TestServer::start([("/icon.png", Route::Redirect("file:///etc/passwd"))]);
+    let err = fetcher
+        .get(&server.url("/icon.png"), MAX_ICON_BYTES)
+        .unwrap_err();
+    assert!(matches!(icon_error(err), IconError::UnsupportedScheme(scheme) if scheme == "file"));
+}
+
+#[test]
+fn redirect_chains_are_capped() {
+    let server = TestServer::start([
+        ("/1", Route::Redirect("/2")),
+        ("/2", Route::Redirect("/3")),
+        ("/3", Route::Redirect("/4")),
+        ("/4", Route::Redirect("/5")),
+        ("/5", Route::Redirect("/6")),
+        ("/6", Route::Redirect("/7")),
+        ("/7", Route::html("too far")),
+    ]);
+    let fetcher = http_server::fetcher();
+    assert_eq!(
+        fetcher.get(&server.url("/2"), MAX_ICON_BYTES).unwrap().body,
+        b"too far",
+        "five redirects are followed"
+    );
+    let err = fetcher.get(&server.url("/1"), MAX_ICON_BYTES).unwrap_err();
+    assert!(matches!(icon_error(err), IconError::TooManyRedirects(5)));
+}
+
+#[test]
+fn oversized_images_are_refused_before_decoding() {
+    let wide = DynamicImage::ImageLuma8(image::GrayImage::new(MAX_ICON_DIMENSION + 1, 1));
+    let mut data = Vec::new();
+    wide.write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+        .unwrap();
+    let server = TestServer::start([("/wide.png", Route::png(data))]);
+    let dir = std::env::temp_dir().join(format!("sitewrap-policy-{}", std::process::id()));
+
+    let err = cache_icon_from_url_with(
+        &http_server::fetcher(),
+        &server.url("/wide.png"),
+        false,
+        "demo",
+        &dir,
+        Normalization::Original,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        icon_error(err),
+        IconError::DimensionsTooLarge { width, height: 1 } if width == MAX_ICON_DIMENSION + 1
+    ));
+    let _ = fs::remove_dir_all(dir);
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    DynamicImage::ImageLuma8(image::GrayImage::new(width, height))
+        .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+        .unwrap();
+    data
+}
+
+#[test]
+fn ico_frames_larger_than_their_directory_entry_are_refused() {
+    // A 32×32 directory entry wrapping a PNG frame that inflates far beyond it.
+    let png = png(MAX_ICON_DIMENSION + 1, MAX_ICON_DIMENSION + 1);
+    let mut ico = vec![0, 0, 1, 0, 1, 0, 32, 32, 0, 0, 1, 0, 32, 0];
+    ico.extend_from_slice(&(png.len() as u32).to_le_bytes());
+    ico.extend_from_slice(&22u32.to_le_bytes());
+    ico.extend_from_slice(&png);
+    let server = TestServer::start([(
+        "/favicon.ico",
+        Route::Ok {
+            content_type: "image/x-icon",
+            body: ico,
+        },
+    )]);
+    let dir = std::env::temp_dir().join(format!("sitewrap-policy-ico-{}", std::process::id()));
+
+    let err = cache_icon_from_url_with(
+        &http_server::fetcher(),
+        &server.url("/favicon.ico"),
+        false,
+        "demo",
+        &dir,
+        Normalization::Original,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        icon_error(err),
+        IconError::DimensionsTooLarge { width, .. } if width == MAX_ICON_DIMENSION + 1
+    ));
+    let _ = fs::remove_dir_all(dir);
+}
+
+fn svg_route(body: Vec<u8>) -> Route {
+    Route::Ok {
+        content_type: "image/svg+xml",
+        body,
+    }
+}
+
+#[test]
+fn compressed_svgs_may_not_inflate_past_the_download_limit() {
+    let gzip = |svg: &[u8]| {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(svg).unwrap();
+        encoder.finish().unwrap()
+    };
+    let small = gzip(br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1 1"><rect width="1" height="1"/></svg>"#);
+    let mut bomb = br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1 1"><!--"#.to_vec();
+    bomb.resize(bomb.len() + MAX_ICON_BYTES as usize, b' ');
+    bomb.extend_from_slice(b"--></svg>");
+    let bomb = gzip(&bomb);
+    assert!(bomb.len() < 64 * 1024);
+    let server = TestServer::start([
+        ("/small.svgz", svg_route(small)),
+        ("/bomb.svgz", svg_route(bomb)),
+    ]);
+    let dir = std::env::temp_dir().join(format!("sitewrap-policy-svgz-{}", std::process::id()));
+    let cache = |path| {
+        cache_icon_from_url_with(
+            &http_server::fetcher(),
+            &server.url(path),
+            false,
+            "demo",
+            &dir,
+            Normalization::Original,
+        )
+    };
+
+    assert!(cache("/small.svgz").unwrap().scalable_path.is_some());
+    let err = cache("/bomb.svgz").unwrap_err();
+    assert!(matches!(icon_error(err), IconError::TooLarge { limit } if limit == MAX_ICON_BYTES));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn svgs_keep_only_embedded_rasters_within_the_size_limit() {
+    let data_url = |png: Vec<u8>| {
+        let encoded: String = png.iter().map(|byte| format!("%{byte:02X}")).collect();
+        format!("data:image/png,{encoded}")
+    };
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 8 8">
+            <image width="8" height="8" href="{small}"/>
+            <image width="8" height="8" href="{wide}"/>
+        </svg>"#,
+        small = data_url(png(8, 8)),
+        wide = data_url(png(MAX_ICON_DIMENSION + 1, 1)),
+    );
+    let server = TestServer::start([("/icon.svg", svg_route(svg.into_bytes()))]);
+    let dir = std::env::temp_dir().join(format!("sitewrap-policy-svg-{}", std::process::id()));
+
+    let result = cache_icon_from_url_with(
+        &http_server::fetcher(),
+        &server.url("/icon.svg"),
+        false,
+        "demo",
+        &dir,
+        Normalization::Original,
+    )
+    .unwrap();
+    let written = fs::read_to_string(result.scalable_path.unwrap()).unwrap();
+    assert_eq!(written.matches("<image").count(), 1, "{written}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn slow_sites_run_out_of_time() {
+    let slow_png = {
+        let mut data = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::new(16, 16))
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        Route::Slow(Duration::from_millis(600), Box::new(Route::png(data)))
+    };
+    let server = TestServer::start([("/slow.png", slow_png)]);
+
+    let impatient = HttpFetcher::new(FetchPolicy {
+        request_timeout: Duration::from_millis(200),
+        allow_private_addresses: true,
+        ..FetchPolicy::default()
+    })
+    .unwrap();
+    let err = impatient
+        .get(&server.url("/slow.png"), MAX_ICON_BYTES)
+        .unwrap_err();
+    assert!(matches!(icon_error(err), IconError::Timeout(_)));
+
+    let fetcher = http_server::fetcher();
+    let budgeted = Budgeted::new(&fetcher, Duration::from_millis(300));
+    budgeted
+        .get(&server.url("/slow.png"), MAX_ICON_BYTES)
+        .expect("started within the budget");
+    let err = budgeted
+        .get(&server.url("/slow.png"), MAX_ICON_BYTES)
+        .unwrap_err();
+    assert!(matches!(icon_error(err), IconError::BudgetExceeded));
+}
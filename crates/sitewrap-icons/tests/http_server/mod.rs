@@ -0,0 +1,190 @@
+//! A local HTTP server for the fetching tests, serving fixed routes on a free port.
+
+// Each test binary uses a different part of the server.
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use sitewrap_icons::{FetchPolicy, HttpFetcher};
+use tiny_http::{Header, Response, Server, StatusCode};
+use url::Url;
+
+/// The default policy, except that it may reach the server on 127.0.0.1.
+pub fn fetcher() -> HttpFetcher {
+    HttpFetcher::new(FetchPolicy {
+        allow_private_addresses: true,
+        ..FetchPolicy::default()
+    })
+    .unwrap()
+}
+
+/// What the server answers for one path.
+#[derive(Clone)]
+pub enum Route {
+    Ok {
+        content_type: &'static str,
+        body: Vec<u8>,
+    },
+    /// Sent without `Content-Length`, so only the bytes read reveal the size.
+    Chunked {
+        content_type: &'static str,
+        body: Vec<u8>,
+    },
+    /// Answers `304 Not Modified` when `If-None-Match` carries `etag`.
+    Tagged {
+        content_type: &'static str,
+        body: Vec<u8>,
+        etag: String,
+    },
+    Redirect(&'static str),
+    Status(u16),
+    /// Answers like `route` after `delay`.
+    Slow(Duration, Box<Route>),
+}
+
+impl Route {
+    pub fn html(body: &str) -> Self {
+        Route::Ok {
+            content_type: "text/html; charset=utf-8",
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn png(body: Vec<u8>) -> Self {
+        Route::Ok {
+            content_type: "image/png",
+            body,
+        }
+    }
+}
+
+type Routes = Arc<Mutex<HashMap<String, Route>>>;
+
+pub struct TestServer {
+    server: Arc<Server>,
+    base: Url,
+    routes: Routes,
+    requests: Arc<Mutex<Vec<String>>>,
+    not_modified: Arc<Mutex<Vec<String>>>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl TestServer {
+    /// Serves `routes` until dropped; unknown paths get 404.
+    pub fn start(routes: impl IntoIterator<Item = (&'static str, Route)>) -> Self {
+        let routes: Routes = Arc::new(Mutex::new(
+            routes
+                .into_iter()
+                .map(|(path, route)| (path.to_string(), route))
+                .collect(),
+        ));
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("bind test server"));
+        let port = server.server_addr().to_ip().expect("tcp listener").port();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let not_modified = Arc::new(Mutex::new(Vec::new()));
+        let worker = {
+            let server = Arc::clone(&server);
+            let routes = Arc::clone(&routes);
+            let requests = Arc::clone(&requests);
+            let not_modified = Arc::clone(&not_modified);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    let path = request.url().to_string();
+                    requests.lock().unwrap().push(path.clone());
+                    let if_none_match = request
+                        .headers()
+                        .iter()
+                        .find(|header| header.field.equiv("If-None-Match"))
+                        .map(|header| header.value.to_string());
+                    let mut route = routes.lock().unwrap().get(&path).cloned();
+                    if let Some(Route::Slow(delay, inner)) = route {
+                        thread::sleep(delay);
+                        route = Some(*inner);
+                    }
+                    let _ = match route {
+                        Some(Route::Ok { content_type, body }) => {
+                            request.respond(Response::from_data(body).with_header(
+                                Header::from_bytes("Content-Type", content_type).unwrap(),
+                            ))
+                        }
+                        Some(Route::Chunked { content_type, body }) => {
+                            request.respond(Response::new(
+                                StatusCode(200),
+                                vec![Header::from_bytes("Content-Type", content_type).unwrap()],
+                                Cursor::new(body),
+                                None,
+                                None,
+                            ))
+                        }
+                        Some(Route::Tagged { etag, .. })
+                            if if_none_match.as_ref() == Some(&etag) =>
+                        {
+                            not_modified.lock().unwrap().push(path);
+                            request.respond(Response::empty(304))
+                        }
+                        Some(Route::Tagged {
+                            content_type,
+                            body,
+                            etag,
+                        }) => request.respond(
+                            Response::from_data(body)
+                                .with_header(
+                                    Header::from_bytes("Content-Type", content_type).unwrap(),
+                                )
+                                .with_header(Header::from_bytes("ETag", etag).unwrap()),
+                        ),
+                        Some(Route::Redirect(location)) => request.respond(
+                            Response::empty(301)
+                                .with_header(Header::from_bytes("Location", location).unwrap()),
+                        ),
+                        Some(Route::Status(code)) => request.respond(Response::empty(code)),
+                        Some(Route::Slow(..)) => unreachable!("unwrapped above"),
+                        None => request.respond(Response::empty(404)),
+                    };
+                }
+            })
+        };
+        Self {
+            server,
+            base: Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap(),
+            routes,
+            requests,
+            not_modified,
+            worker: Some(worker),
+        }
+    }
+
+    pub fn url(&self, path: &str) -> Url {
+        self.base.join(path).unwrap()
+    }
+
+    /// Replaces what `path` answers from now on.
+    pub fn set_route(&self, path: &str, route: Route) {
+        self.routes.lock().unwrap().insert(path.to_string(), route);
+    }
+
+    /// Paths requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Paths answered with `304 Not Modified`, in order.
+    pub fn not_modified(&self) -> Vec<String> {
+        self.not_modified.lock().unwrap().clone()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
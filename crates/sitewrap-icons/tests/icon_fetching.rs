@@ -0,0 +1,380 @@
+//! Icon discovery and download against a local HTTP server.
+
+mod http_server;
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use http_server::{Route, TestServer};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use sitewrap_icons::{
+    cache_icon_for_site_with, fetch_and_cache_icon_with, fetch_manifest_with, fetch_site_info_with,
+    preview_candidates_with, Cancellable, Fetcher, IconError, IconResult, Normalization,
+    ICON_SIZES, MAX_ICON_BYTES,
+};
+
+const BLUE: [u8; 4] = [20, 90, 200, 255];
+const RED: [u8; 4] = [200, 30, 40, 255];
+const GREEN: [u8; 4] = [40, 160, 70, 255];
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sitewrap-fetch-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn solid(size: u32, color: [u8; 4], format: ImageFormat) -> Vec<u8> {
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(size, size, Rgba(color)));
+    let mut data = Vec::new();
+    image.write_to(&mut Cursor::new(&mut data), format).unwrap();
+    data
+}
+
+fn fetch(server: &TestServer, dir: &Path) -> IconResult {
+    fetch_and_cache_icon_with(
+        &http_server::fetcher(),
+        &server.url("/"),
+        "Demo",
+        "demo",
+        dir,
+        Normalization::Original,
+    )
+    .unwrap()
+}
+
+/// Center pixel of the largest rendition.
+fn rendered_center(dir: &Path) -> [u8; 4] {
+    let image = image::open(dir.join("demo-512x512.png"))
+        .unwrap()
+        .into_rgba8();
+    image.get_pixel(256, 256).0
+}
+
+#[test]
+fn uses_a_declared_icon_and_stops_once_it_is_large_enough() {
+    let server = TestServer::start([
+        (
+            "/",
+            Route::html(r#"<link rel="icon" href="/icons/app.png" sizes="256x256">"#),
+        ),
+        (
+            "/icons/app.png",
+            Route::png(solid(256, BLUE, ImageFormat::Png)),
+        ),
+    ]);
+    let dir = temp_dir("declared");
+
+    let result = fetch(&server, &dir);
+    assert_eq!(result.rendered_paths.len(), ICON_SIZES.len());
+    assert!(result.scalable_path.is_none());
+    assert_eq!(rendered_center(&dir), BLUE);
+    assert_eq!(
+        server.requests(),
+        ["/", "/icons/app.png"],
+        "a 256 px icon is good enough; /favicon.ico is never fetched"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn falls_back_to_the_origin_favicon_ico() {
+    let server = TestServer::start([
+        ("/", Route::html("<title>No icons here</title>")),
+        (
+            "/favicon.ico",
+            Route::Ok {
+                content_type: "image/x-icon",
+                body: solid(48, RED, ImageFormat::Ico),
+            },
+        ),
+    ]);
+    let dir = temp_dir("ico");
+
+    fetch(&server, &dir);
+    assert_eq!(rendered_center(&dir), RED);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn follows_redirects_and_resolves_links_against_the_final_page() {
+    let server = TestServer::start([
+        ("/", Route::Redirect("/home/")),
+        (
+            "/home/",
+            Route::html(r#"<link rel="icon" href="icon.png">"#),
+        ),
+        ("/home/icon.png", Route::Redirect("/static/icon.png")),
+        (
+            "/static/icon.png",
+            Route::png(solid(64, GREEN, ImageFormat::Png)),
+        ),
+    ]);
+    let dir = temp_dir("redirect");
+
+    fetch(&server, &dir);
+    assert_eq!(rendered_center(&dir), GREEN);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn skips_oversized_missing_and_undecodable_icons() {
+    let oversized = vec![0; MAX_ICON_BYTES as usize + 1];
+    let server = TestServer::start([
+        (
+            "/",
+            Route::html(
+                r#"<link rel="icon" href="/declared-large.png" sizes="512x512">
+                <link rel="icon" href="/chunked-large.png" sizes="384x384">
+                <link rel="icon" href="/missing.png" sizes="256x256">
+                <link rel="icon" href="/broken.png" sizes="192x192">
+                <link rel="icon" href="/small.png" sizes="64x64">"#,
+            ),
+        ),
+        ("/declared-large.png", Route::png(oversized.clone())),
+        (
+            "/chunked-large.png",
+            Route::Chunked {
+                content_type: "image/png",
+                body: oversized,
+            },
+        ),
+        (
+            "/broken.png",
+            Route::png(b"<html>not an image</html>".to_vec()),
+        ),
+        ("/small.png", Route::png(solid(64, GREEN, ImageFormat::Png))),
+    ]);
+    let dir = temp_dir("skips");
+
+    fetch(&server, &dir);
+    assert_eq!(rendered_center(&dir), GREEN);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn fetcher_enforces_the_size_limit_without_content_length() {
+    let server = TestServer::start([
+        (
+            "/chunked",
+            Route::Chunked {
+                content_type: "application/octet-stream",
+                body: vec![7; 2048],
+            },
+        ),
+        ("/error", Route::Status(503)),
+    ]);
+    let fetcher = http_server::fetcher();
+
+    let err = fetcher.get(&server.url("/chunked"), 1024).unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<IconError>(),
+            Some(IconError::TooLarge { limit: 1024 })
+        ),
+        "{err}"
+    );
+    assert_eq!(
+        fetcher
+            .get(&server.url("/chunked"), 2048)
+            .unwrap()
+            .body
+            .len(),
+        2048
+    );
+    let err = fetcher.get(&server.url("/error"), 1024).unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<IconError>(),
+            Some(IconError::HttpStatus { status: 503, .. })
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn generates_an_initials_icon_when_the_site_has_none() {
+    let server = TestServer::start([("/", Route::Status(500))]);
+    let dir = temp_dir("fallback");
+
+    let result = fetch(&server, &dir);
+    assert_eq!(result.rendered_paths.len(), ICON_SIZES.len());
+    assert!(result.scalable_path.is_none());
+    for path in &result.rendered_paths {
+        assert!(path.exists(), "{path:?}");
+    }
+    let image = image::open(dir.join("demo-512x512.png"))
+        .unwrap()
+        .into_rgba8();
+    assert_eq!(
+        image.get_pixel(0, 0).0[3],
+        0,
+        "the plate has rounded corners"
+    );
+    assert_eq!(
+        image.get_pixel(256, 100).0[3],
+        255,
+        "the initials plate is opaque"
+    );
+    assert_eq!(server.requests(), ["/", "/favicon.ico"]);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn manifest_icons_are_previewed_and_vectors_kept_scalable() {
+    let server = TestServer::start([
+        (
+            "/",
+            Route::html(
+                r#"<link rel="manifest" href="/app.webmanifest">
+                <link rel="icon" href="/favicon-32.png" sizes="32x32">"#,
+            ),
+        ),
+        (
+            "/app.webmanifest",
+            Route::Ok {
+                content_type: "application/manifest+json",
+                body: br#"{
+                    "name": "Demo",
+                    "icons": [
+                        { "src": "m/192.png", "sizes": "192x192", "type": "image/png" },
+                        { "src": "m/logo.svg", "sizes": "any", "type": "image/svg+xml" }
+                    ]
+                }"#
+                .to_vec(),
+            },
+        ),
+        ("/m/192.png", Route::png(solid(192, BLUE, ImageFormat::Png))),
+        (
+            "/m/logo.svg",
+            Route::Ok {
+                content_type: "image/svg+xml",
+                body: br##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><rect width="10" height="10" fill="#e01b24"/></svg>"##
+                    .to_vec(),
+            },
+        ),
+        ("/favicon-32.png", Route::png(solid(32, RED, ImageFormat::Png))),
+    ]);
+    let fetcher = http_server::fetcher();
+
+    let (manifest_url, manifest) = fetch_manifest_with(&fetcher, &server.url("/"))
+        .unwrap()
+        .expect("manifest is linked");
+    assert_eq!(manifest_url, server.url("/app.webmanifest"));
+    assert_eq!(manifest.icons.len(), 2);
+
+    let previews = preview_candidates_with(&fetcher, &server.url("/"), 8);
+    let summary: Vec<_> = previews
+        .iter()
+        .map(|preview| (preview.candidate.url.path(), preview.resolution))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("/m/logo.svg", None),
+            ("/m/192.png", Some(192)),
+            ("/favicon-32.png", Some(32)),
+        ]
+    );
+    assert!(previews.iter().all(|preview| !preview.png.is_empty()));
+
+    let dir = temp_dir("manifest");
+    let result = fetch(&server, &dir);
+    assert_eq!(result.scalable_path, Some(dir.join("demo.svg")));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn site_info_names_the_app_from_its_manifest_or_page() {
+    let server = TestServer::start([
+        (
+            "/",
+            Route::html(
+                r#"<title>Inbox - Example Mail</title><link rel="manifest" href="/app.webmanifest">"#,
+            ),
+        ),
+        (
+            "/app.webmanifest",
+            Route::Ok {
+                content_type: "application/manifest+json",
+                body: br##"{"name": "Example Mail", "theme_color": "#1c71d8"}"##.to_vec(),
+            },
+        ),
+        ("/plain", Route::html("<title>  Plain   Page </title>")),
+        ("/broken", Route::Redirect("/gone")),
+    ]);
+    let fetcher = http_server::fetcher();
+
+    let info = fetch_site_info_with(&fetcher, &server.url("/")).unwrap();
+    assert_eq!(info.name.as_deref(), Some("Example Mail"));
+    let (_, manifest) = info.manifest.expect("manifest is linked");
+    assert_eq!(manifest.theme_color.as_deref(), Some("#1c71d8"));
+
+    let info = fetch_site_info_with(&fetcher, &server.url("/plain")).unwrap();
+    assert_eq!(info.name.as_deref(), Some("Plain Page"));
+    assert!(info.manifest.is_none());
+
+    let err = fetch_site_info_with(&fetcher, &server.url("/broken")).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<IconError>(),
+        Some(IconError::HttpStatus { status: 404, .. })
+    ));
+}
+
+#[test]
+fn icon_for_a_looked_up_site_reuses_its_page_and_manifest() {
+    let server = TestServer::start([
+        (
+            "/",
+            Route::html(r#"<link rel="manifest" href="/app.webmanifest">"#),
+        ),
+        (
+            "/app.webmanifest",
+            Route::Ok {
+                content_type: "application/manifest+json",
+                body: br#"{"icons": [{"src": "/icon.png", "sizes": "256x256"}]}"#.to_vec(),
+            },
+        ),
+        ("/icon.png", Route::png(solid(256, GREEN, ImageFormat::Png))),
+    ]);
+    let fetcher = http_server::fetcher();
+    let dir = temp_dir("site-info");
+
+    let info = fetch_site_info_with(&fetcher, &server.url("/")).unwrap();
+    cache_icon_for_site_with(
+        &fetcher,
+        &info,
+        &server.url("/"),
+        "Demo",
+        "demo",
+        &dir,
+        Normalization::Original,
+    )
+    .unwrap();
+    assert_eq!(rendered_center(&dir), GREEN);
+    assert_eq!(server.requests(), ["/", "/app.webmanifest", "/icon.png"]);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn cancelled_fetcher_stops_before_the_next_request() {
+    let server = TestServer::start([("/", Route::html("<title>Demo</title>"))]);
+    let inner = http_server::fetcher();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let fetcher = Cancellable::new(&inner, Arc::clone(&cancelled));
+
+    assert!(fetch_site_info_with(&fetcher, &server.url("/")).is_ok());
+    cancelled.store(true, Ordering::Relaxed);
+    let err = fetch_site_info_with(&fetcher, &server.url("/")).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<IconError>(),
+        Some(IconError::Cancelled)
+    ));
+    assert_eq!(server.requests(), ["/"]);
+}
@@ -0,0 +1,229 @@
+//! Re-checking cached icons against a local HTTP server.
+
+mod http_server;
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use http_server::{Route, TestServer};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use sitewrap_icons::{
+    cache_icon_from_url_with, fetch_and_cache_icon_with, refresh_icon_with, IconOrigin, IconRecord,
+    Normalization, RefreshOutcome,
+};
+
+const BLUE: [u8; 4] = [20, 90, 200, 255];
+const ORANGE: [u8; 4] = [230, 120, 20, 255];
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sitewrap-refresh-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn png(color: [u8; 4]) -> Vec<u8> {
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(256, 256, Rgba(color)));
+    let mut data = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+        .unwrap();
+    data
+}
+
+fn tagged(color: [u8; 4], etag: &str) -> Route {
+    Route::Tagged {
+        content_type: "image/png",
+        body: png(color),
+        etag: etag.to_string(),
+    }
+}
+
+fn site(icon: Route) -> TestServer {
+    TestServer::start([
+        (
+            "/",
+            Route::html(r#"<link rel="icon" href="/icon.png" sizes="256x256">"#),
+        ),
+        ("/icon.png", icon),
+    ])
+}
+
+fn cache(server: &TestServer, dir: &Path) {
+    fetch_and_cache_icon_with(
+        &http_server::fetcher(),
+        &server.url("/"),
+        "Demo",
+        "demo",
+        dir,
+        Normalization::Original,
+    )
+    .unwrap();
+}
+
+fn site_origin(server: &TestServer) -> IconOrigin {
+    IconOrigin::Site {
+        start_url: server.url("/"),
+        app_name: "Demo".to_string(),
+    }
+}
+
+fn refresh(origin: &IconOrigin, dir: &Path) -> RefreshOutcome {
+    refresh_icon_with(
+        &http_server::fetcher(),
+        origin,
+        "demo",
+        dir,
+        Normalization::Original,
+    )
+    .unwrap()
+}
+
+fn rendered_center(dir: &Path) -> [u8; 4] {
+    let image = image::open(dir.join("demo-512x512.png"))
+        .unwrap()
+        .into_rgba8();
+    image.get_pixel(256, 256).0
+}
+
+#[test]
+fn records_the_download_and_revalidates_it_with_its_etag() {
+    let server = site(tagged(BLUE, "\"v1\""));
+    let dir = temp_dir("etag");
+    cache(&server, &dir);
+
+    let record = IconRecord::load(&dir, "demo").expect("record written");
+    assert_eq!(
+        record.url.as_deref(),
+        Some(server.url("/icon.png").as_str())
+    );
+    assert_eq!(record.etag.as_deref(), Some("\"v1\""));
+    assert!(record.sha256.is_some());
+
+    let origin = site_origin(&server);
+    assert!(matches!(refresh(&origin, &dir), RefreshOutcome::Unchanged));
+    assert_eq!(server.not_modified(), ["/icon.png"]);
+    assert_eq!(
+        server.requests().iter().filter(|path| *path == "/").count(),
+        1,
+        "an unchanged icon does not re-read the page"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn a_rebranded_icon_is_downloaded_and_rendered_again() {
+    let server = site(tagged(BLUE, "\"v1\""));
+    let dir = temp_dir("rebrand");
+    cache(&server, &dir);
+
+    server.set_route("/icon.png", tagged(ORANGE, "\"v2\""));
+    let origin = site_origin(&server);
+    assert!(matches!(refresh(&origin, &dir), RefreshOutcome::Updated(_)));
+    assert_eq!(rendered_center(&dir), ORANGE);
+    assert_eq!(
+        IconRecord::load(&dir, "demo").unwrap().etag.as_deref(),
+        Some("\"v2\"")
+    );
+
+    assert!(matches!(refresh(&origin, &dir), RefreshOutcome::Unchanged));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn identical_bytes_without_validators_are_unchanged() {
+    let server = site(Route::png(png(BLUE)));
+    let dir = temp_dir("same-bytes");
+    cache(&server, &dir);
+
+    let origin = site_origin(&server);
+    assert!(matches!(refresh(&origin, &dir), RefreshOutcome::Unchanged));
+    assert!(server.not_modified().is_empty());
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn a_moved_icon_is_found_again_through_the_page() {
+    let server = site(Route::png(png(BLUE)));
+    let dir = temp_dir("moved");
+    cache(&server, &dir);
+
+    server.set_route("/icon.png", Route::Status(404));
+    server.set_route(
+        "/",
+        Route::html(r#"<link rel="icon" href="/brand/new.png" sizes="256x256">"#),
+    );
+    server.set_route("/brand/new.png", Route::png(png(ORANGE)));
+    let origin = site_origin(&server);
+    assert!(matches!(refresh(&origin, &dir), RefreshOutcome::Updated(_)));
+    assert_eq!(rendered_center(&dir), ORANGE);
+    assert_eq!(
+        IconRecord::load(&dir, "demo").unwrap().url.as_deref(),
+        Some(server.url("/brand/new.png").as_str())
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn an_unreachable_site_keeps_the_downloaded_icon() {
+    let server = site(tagged(BLUE, "\"v1\""));
+    let dir = temp_dir("outage");
+    cache(&server, &dir);
+
+    for path in ["/", "/icon.png"] {
+        server.set_route(path, Route::Status(503));
+    }
+    let err = refresh_icon_with(
+        &http_server::fetcher(),
+        &site_origin(&server),
+        "demo",
+        &dir,
+        Normalization::Original,
+    )
+    .expect_err("an outage is not reported as an unchanged icon");
+    assert!(format!("{err:#}").contains("unreachable"), "{err:#}");
+    assert_eq!(rendered_center(&dir), BLUE, "no initials icon replaces it");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn pinned_icons_refresh_from_their_url_only() {
+    let server = site(tagged(BLUE, "\"v1\""));
+    server.set_route("/picked.png", tagged(BLUE, "\"p1\""));
+    let dir = temp_dir("pinned");
+    let picked = server.url("/picked.png");
+    cache_icon_from_url_with(
+        &http_server::fetcher(),
+        &picked,
+        false,
+        "demo",
+        &dir,
+        Normalization::Original,
+    )
+    .unwrap();
+
+    server.set_route("/picked.png", tagged(ORANGE, "\"p2\""));
+    let origin = IconOrigin::Pinned {
+        url: picked,
+        maskable: false,
+    };
+    assert!(matches!(refresh(&origin, &dir), RefreshOutcome::Updated(_)));
+    assert_eq!(rendered_center(&dir), ORANGE);
+
+    server.set_route("/picked.png", Route::Status(410));
+    assert!(refresh_icon_with(
+        &http_server::fetcher(),
+        &origin,
+        "demo",
+        &dir,
+        Normalization::Original,
+    )
+    .is_err());
+    assert!(
+        !server.requests().contains(&"/".to_string()),
+        "a pinned icon never falls back to the site's choice"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
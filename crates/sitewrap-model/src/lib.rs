@@ -1,4 +1,9 @@
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
 use directories::BaseDirs;
@@ -40,10 +45,19 @@ impl AppPaths {
         self.config_dir.join("apps")
     }
 
+    /// How the manager window lists apps; see [`ManagerSettings`].
+    pub fn manager_settings_path(&self) -> PathBuf {
+        self.config_dir.join("manager.toml")
+    }
+
     pub fn permissions_dir(&self) -> PathBuf {
         self.config_dir.join("permissions")
     }
 
+    pub fn audit_dir(&self) -> PathBuf {
+        self.data_dir.join("audit")
+    }
+
     pub fn icons_cache_dir(&self) -> PathBuf {
         self.cache_dir.join("icons")
     }
@@ -56,6 +70,16 @@ impl AppPaths {
         self.profiles_dir().join(id.to_string())
     }
 
+    /// Bytes the app's profile (cookies, storage, cache) takes up; 0 before first launch.
+    pub fn profile_size(&self, id: WebAppId) -> u64 {
+        dir_size(&self.profile_dir(id))
+    }
+
+    /// Removed apps waiting to be restored or purged; see [`Trash`].
+    pub fn trash_dir(&self) -> PathBuf {
+        self.data_dir.join("trash")
+    }
+
     pub fn delete_profile_dir(&self, id: WebAppId) -> Result<()> {
         let dir = self.profile_dir(id);
         if dir.exists() {
@@ -80,6 +104,31 @@ impl AppPaths {
         }
         Ok(())
     }
+
+    /// Clears the cached icons of `app` when its data is reset. An imported icon stays:
+    /// its file is the only copy and nothing would fetch it again.
+    pub fn reset_icons_for(&self, app: &WebAppDefinition) -> Result<()> {
+        if matches!(app.icon_source, IconSource::Custom) {
+            return Ok(());
+        }
+        self.delete_icons_for(&app.icon_id)
+    }
+}
+
+/// Total size of the files under `dir`. Symlinks are not followed and unreadable
+/// entries count as empty.
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(kind) if kind.is_file() => entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +137,226 @@ pub struct BehaviorConfig {
     pub open_external_links: bool,
     #[serde(default = "default_show_navigation")]
     pub show_navigation: bool,
+    /// Hide the window on close instead of quitting, so notifications keep arriving.
+    #[serde(default)]
+    pub run_in_background: bool,
+    /// Start the app at login through the Background portal's autostart entry.
+    #[serde(default)]
+    pub start_at_login: bool,
+    /// Light/dark mode for the window and the pages it shows.
+    #[serde(default)]
+    pub color_scheme: ColorSchemePreference,
+}
+
+/// Per-app override of the desktop's light/dark preference.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSchemePreference {
+    #[default]
+    FollowSystem,
+    ForceLight,
+    ForceDark,
+}
+
+impl ColorSchemePreference {
+    pub const ALL: [ColorSchemePreference; 3] = [
+        ColorSchemePreference::FollowSystem,
+        ColorSchemePreference::ForceLight,
+        ColorSchemePreference::ForceDark,
+    ];
+
+    /// Whether pages should see `prefers-color-scheme: dark`.
+    pub fn is_dark(self, system_prefers_dark: bool) -> bool {
+        match self {
+            ColorSchemePreference::FollowSystem => system_prefers_dark,
+            ColorSchemePreference::ForceLight => false,
+            ColorSchemePreference::ForceDark => true,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorSchemePreference::FollowSystem => "Follow System",
+            ColorSchemePreference::ForceLight => "Light",
+            ColorSchemePreference::ForceDark => "Dark",
+        }
+    }
+}
+
+/// How the fetched site icon is shaped into the app icon.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IconStyle {
+    /// Crop maskable art, trim padding, and put small favicons on a plate.
+    #[default]
+    Automatic,
+    /// The site's icon as drawn, only scaled.
+    Original,
+    /// The icon's glyph on a rounded, tinted plate.
+    Plate,
+}
+
+impl IconStyle {
+    pub const ALL: [IconStyle; 3] = [IconStyle::Automatic, IconStyle::Original, IconStyle::Plate];
+
+    pub fn is_automatic(&self) -> bool {
+        *self == IconStyle::Automatic
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IconStyle::Automatic => "Automatic",
+            IconStyle::Original => "Original",
+            IconStyle::Plate => "On a Plate",
+        }
+    }
+}
+
+/// Order of the apps in the manager.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Name,
+    /// Most recently launched first; apps never launched go last.
+    LastLaunched,
+    /// Newest first.
+    Created,
+}
+
+impl SortMode {
+    pub const ALL: [SortMode; 3] = [SortMode::Name, SortMode::LastLaunched, SortMode::Created];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "Name",
+            SortMode::LastLaunched => "Last Launched",
+            SortMode::Created => "Date Added",
+        }
+    }
+
+    /// Stable identifier, as stored in [`ManagerSettings`].
+    pub fn key(self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::LastLaunched => "last_launched",
+            SortMode::Created => "created",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.key() == key)
+    }
+
+    /// Sorts `apps` in this order; ties are broken by name.
+    pub fn sort(self, apps: &mut [WebAppDefinition]) {
+        apps.sort_by(|a, b| {
+            let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+            match self {
+                SortMode::Name => by_name(),
+                // `None` sorts before `Some`, so comparing b to a puts it last.
+                SortMode::LastLaunched => b.last_launched_at.cmp(&a.last_launched_at),
+                SortMode::Created => b.created_at.cmp(&a.created_at),
+            }
+            .then_with(by_name)
+        });
+    }
+}
+
+/// How the manager window lists apps; remembered between sessions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManagerSettings {
+    #[serde(default)]
+    pub sort: SortMode,
+    /// Show apps as a grid of icons instead of a list.
+    #[serde(default)]
+    pub grid: bool,
+    /// Only apps with this tag are listed; `None` lists all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Days a removed app stays in the trash before it is purged; 0 keeps it until the
+    /// user deletes it.
+    #[serde(default = "default_purge_after_days")]
+    pub purge_after_days: u32,
+}
+
+impl Default for ManagerSettings {
+    fn default() -> Self {
+        Self {
+            sort: SortMode::default(),
+            grid: false,
+            tag: None,
+            purge_after_days: default_purge_after_days(),
+        }
+    }
+}
+
+fn default_purge_after_days() -> u32 {
+    30
+}
+
+impl ManagerSettings {
+    /// The saved settings, or the defaults when there are none or they cannot be read.
+    pub fn load(paths: &AppPaths) -> Self {
+        fs::read_to_string(paths.manager_settings_path())
+            .ok()
+            .and_then(|data| toml::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, paths: &AppPaths) -> Result<()> {
+        let path = paths.manager_settings_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("create config dir {parent:?}"))?;
+        }
+        let toml = toml::to_string_pretty(self).context("serialize manager settings")?;
+        fs::write(&path, toml).with_context(|| format!("write manager settings {path:?}"))
+    }
+
+    /// The apps to list: those matching `query` (see [`WebAppDefinition::matches_query`])
+    /// and the selected tag, in the selected order.
+    pub fn visible_apps(&self, apps: &[WebAppDefinition], query: &str) -> Vec<WebAppDefinition> {
+        let mut visible: Vec<_> = apps
+            .iter()
+            .filter(|app| app.matches_query(query))
+            .filter(|app| self.tag.as_deref().is_none_or(|tag| app.has_tag(tag)))
+            .cloned()
+            .collect();
+        self.sort.sort(&mut visible);
+        visible
+    }
+}
+
+/// Splits comma-separated tags, trimming them and dropping empty and repeated ones.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+    {
+        if !tags
+            .iter()
+            .any(|known| known.to_lowercase() == tag.to_lowercase())
+        {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Every tag used by `apps`, once, sorted case-insensitively.
+pub fn all_tags(apps: &[WebAppDefinition]) -> Vec<String> {
+    let mut tags = parse_tags(
+        &apps
+            .iter()
+            .flat_map(|app| &app.tags)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    tags
 }
 
 impl Default for BehaviorConfig {
@@ -95,10 +364,20 @@ impl Default for BehaviorConfig {
         Self {
             open_external_links: default_open_external_links(),
             show_navigation: default_show_navigation(),
+            run_in_background: false,
+            start_at_login: false,
+            color_scheme: ColorSchemePreference::default(),
         }
     }
 }
 
+impl BehaviorConfig {
+    /// Whether the Background portal needs to be asked for anything.
+    pub fn needs_background(&self) -> bool {
+        self.run_in_background || self.start_at_login
+    }
+}
+
 fn default_open_external_links() -> bool {
     true
 }
@@ -107,6 +386,105 @@ fn default_show_navigation() -> bool {
     false
 }
 
+/// A user-defined launcher action that opens the app at a deep link.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AppShortcut {
+    pub label: String,
+    pub url: String,
+}
+
+/// A non-http URI scheme the app handles, e.g. `mailto` for a webmail client.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SchemeHandler {
+    pub scheme: String,
+    /// Page that receives the URI; `%s` is replaced with the percent-encoded URI,
+    /// as with `navigator.registerProtocolHandler`.
+    pub url_template: String,
+}
+
+/// Which links from other applications should open in this web app.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LinkHandlers {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schemes: Vec<SchemeHandler>,
+    /// http(s) origins whose links open here instead of the default browser.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub origins: Vec<String>,
+    /// Registers the launcher for all http(s) links, which makes it a browser choice on the
+    /// desktop. Off unless the user opts in; links outside `origins` are passed on anyway.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub claim_web_links: bool,
+}
+
+impl LinkHandlers {
+    pub fn is_empty(&self) -> bool {
+        self.schemes.is_empty() && self.origins.is_empty() && !self.claim_web_links
+    }
+
+    /// `MimeType` values to export in the launcher's desktop entry.
+    pub fn mime_types(&self) -> Vec<String> {
+        let mut types: Vec<String> = self
+            .schemes
+            .iter()
+            .map(|handler| format!("x-scheme-handler/{}", handler.scheme.to_ascii_lowercase()))
+            .collect();
+        if self.claim_web_links && !self.origins.is_empty() {
+            types.push("x-scheme-handler/http".to_string());
+            types.push("x-scheme-handler/https".to_string());
+        }
+        types.sort();
+        types.dedup();
+        types
+    }
+}
+
+/// Outcome of the last attempt to put the app's launcher on the desktop.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LauncherStatus {
+    /// Not attempted yet, or created before install results were tracked.
+    #[default]
+    Unknown,
+    /// Installed through the DynamicLauncher portal.
+    Installed,
+    /// Written manually; `path` is the exported `.desktop` file.
+    Exported {
+        path: PathBuf,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl LauncherStatus {
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown)
+    }
+}
+
+/// Where the app icon comes from; anything but `Site` was picked by the user.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IconSource {
+    /// The best icon the site declares, chosen again on every refetch.
+    #[default]
+    Site,
+    /// A specific icon URL from the site's candidates.
+    Url {
+        url: String,
+        #[serde(default)]
+        maskable: bool,
+    },
+    /// A local file imported into the icon cache.
+    Custom,
+}
+
+impl IconSource {
+    pub fn is_site(&self) -> bool {
+        matches!(self, Self::Site)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebAppDefinition {
     pub id: WebAppId,
@@ -120,6 +498,23 @@ pub struct WebAppDefinition {
     pub last_launched_at: Option<OffsetDateTime>,
     #[serde(default)]
     pub behavior: BehaviorConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shortcuts: Vec<AppShortcut>,
+    #[serde(default, skip_serializing_if = "LinkHandlers::is_empty")]
+    pub link_handlers: LinkHandlers,
+    #[serde(default, skip_serializing_if = "LauncherStatus::is_unknown")]
+    pub launcher: LauncherStatus,
+    /// Header bar color as `#rrggbb`; seeded from the manifest `theme_color` or the icon,
+    /// then editable. `None` keeps the default chrome.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<String>,
+    #[serde(default, skip_serializing_if = "IconStyle::is_automatic")]
+    pub icon_style: IconStyle,
+    #[serde(default, skip_serializing_if = "IconSource::is_site")]
+    pub icon_source: IconSource,
+    /// Labels for grouping apps in the manager, e.g. a team or project.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl WebAppDefinition {
@@ -136,10 +531,93 @@ impl WebAppDefinition {
             created_at: now,
             last_launched_at: None,
             behavior: BehaviorConfig::default(),
+            shortcuts: Vec::new(),
+            link_handlers: LinkHandlers::default(),
+            launcher: LauncherStatus::Unknown,
+            accent_color: None,
+            icon_style: IconStyle::Automatic,
+            icon_source: IconSource::Site,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags
+            .iter()
+            .any(|own| own.to_lowercase() == tag.to_lowercase())
+    }
+
+    /// Whether `query` occurs in the name, start URL, origin or a tag, ignoring case.
+    /// An empty query matches every app.
+    pub fn matches_query(&self, query: &str) -> bool {
+        let needle = query.trim().to_lowercase();
+        needle.is_empty()
+            || [&self.name, &self.start_url, &self.primary_origin]
+                .into_iter()
+                .chain(&self.tags)
+                .any(|field| field.to_lowercase().contains(&needle))
+    }
+
+    /// Maps a URI from another application to the page this app should open, if it handles it.
+    pub fn resolve_handled_uri(&self, uri: &Url) -> Option<Url> {
+        match uri.scheme() {
+            "http" | "https" => {
+                let origin = origin_for(uri);
+                self.link_handlers
+                    .origins
+                    .iter()
+                    .any(|o| o.trim_end_matches('/') == origin)
+                    .then(|| uri.clone())
+            }
+            scheme => {
+                let handler = self
+                    .link_handlers
+                    .schemes
+                    .iter()
+                    .find(|h| h.scheme.eq_ignore_ascii_case(scheme))?;
+                let encoded: String =
+                    url::form_urlencoded::byte_serialize(uri.as_str().as_bytes()).collect();
+                let target = Url::parse(&handler.url_template.replace("%s", &encoded)).ok()?;
+                matches!(target.scheme(), "http" | "https").then_some(target)
+            }
+        }
+    }
+
+    /// Length of the start URL path this app is scoped to when it also matches `uri`.
+    fn scope_match_len(&self, uri: &Url) -> usize {
+        let Ok(start) = Url::parse(&self.start_url) else {
+            return 0;
+        };
+        if origin_for(&start) != origin_for(uri) {
+            return 0;
+        }
+        let scope = match start.path().rfind('/') {
+            Some(index) => &start.path()[..=index],
+            None => "/",
+        };
+        if uri.path().starts_with(scope) {
+            scope.len()
+        } else {
+            0
         }
     }
 }
 
+/// Picks the app that should open `uri` and the page to load. When several apps handle it,
+/// the one whose start URL scope matches most specifically wins, then the most recently launched.
+pub fn find_app_for_uri<'a>(
+    apps: &'a [WebAppDefinition],
+    uri: &Url,
+) -> Option<(&'a WebAppDefinition, Url)> {
+    apps.iter()
+        .filter_map(|app| app.resolve_handled_uri(uri).map(|target| (app, target)))
+        .max_by(|(a, _), (b, _)| {
+            a.scope_match_len(uri)
+                .cmp(&b.scope_match_len(uri))
+                .then_with(|| a.last_launched_at.cmp(&b.last_launched_at))
+        })
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum PermissionState {
@@ -149,6 +627,80 @@ pub enum PermissionState {
     Block,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionKind {
+    Notifications,
+    Camera,
+    Microphone,
+    Location,
+}
+
+impl PermissionKind {
+    pub const ALL: [PermissionKind; 4] = [
+        PermissionKind::Notifications,
+        PermissionKind::Camera,
+        PermissionKind::Microphone,
+        PermissionKind::Location,
+    ];
+}
+
+/// Expiry timestamps for time-bounded grants; a missing entry means the state never expires.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PermissionExpiry {
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub notifications: Option<OffsetDateTime>,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub camera: Option<OffsetDateTime>,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub microphone: Option<OffsetDateTime>,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub location: Option<OffsetDateTime>,
+}
+
+impl PermissionExpiry {
+    pub fn is_empty(&self) -> bool {
+        self.notifications.is_none()
+            && self.camera.is_none()
+            && self.microphone.is_none()
+            && self.location.is_none()
+    }
+
+    pub fn get(&self, kind: PermissionKind) -> Option<OffsetDateTime> {
+        match kind {
+            PermissionKind::Notifications => self.notifications,
+            PermissionKind::Camera => self.camera,
+            PermissionKind::Microphone => self.microphone,
+            PermissionKind::Location => self.location,
+        }
+    }
+
+    fn slot_mut(&mut self, kind: PermissionKind) -> &mut Option<OffsetDateTime> {
+        match kind {
+            PermissionKind::Notifications => &mut self.notifications,
+            PermissionKind::Camera => &mut self.camera,
+            PermissionKind::Microphone => &mut self.microphone,
+            PermissionKind::Location => &mut self.location,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerOriginPermissions {
     #[serde(default)]
@@ -159,6 +711,8 @@ pub struct PerOriginPermissions {
     pub microphone: PermissionState,
     #[serde(default)]
     pub location: PermissionState,
+    #[serde(default, skip_serializing_if = "PermissionExpiry::is_empty")]
+    pub expires: PermissionExpiry,
 }
 
 impl Default for PerOriginPermissions {
@@ -168,6 +722,64 @@ impl Default for PerOriginPermissions {
             camera: PermissionState::Ask,
             microphone: PermissionState::Ask,
             location: PermissionState::Ask,
+            expires: PermissionExpiry::default(),
+        }
+    }
+}
+
+impl PerOriginPermissions {
+    pub fn get(&self, kind: PermissionKind) -> PermissionState {
+        match kind {
+            PermissionKind::Notifications => self.notifications.clone(),
+            PermissionKind::Camera => self.camera.clone(),
+            PermissionKind::Microphone => self.microphone.clone(),
+            PermissionKind::Location => self.location.clone(),
+        }
+    }
+
+    /// Sets a permanent state, dropping any pending expiry for that permission.
+    pub fn set(&mut self, kind: PermissionKind, state: PermissionState) {
+        *self.state_mut(kind) = state;
+        *self.expires.slot_mut(kind) = None;
+    }
+
+    /// Sets a state that reverts to `Ask` once `expires_at` has passed.
+    pub fn set_until(
+        &mut self,
+        kind: PermissionKind,
+        state: PermissionState,
+        expires_at: OffsetDateTime,
+    ) {
+        *self.state_mut(kind) = state;
+        *self.expires.slot_mut(kind) = Some(expires_at);
+    }
+
+    /// Effective state at `now`, treating expired grants as `Ask`.
+    pub fn resolve(&self, kind: PermissionKind, now: OffsetDateTime) -> PermissionState {
+        match self.expires.get(kind) {
+            Some(expires_at) if expires_at <= now => PermissionState::Ask,
+            _ => self.get(kind),
+        }
+    }
+
+    /// Resets expired grants back to `Ask`; returns true if anything changed.
+    pub fn prune_expired(&mut self, now: OffsetDateTime) -> bool {
+        let mut changed = false;
+        for kind in PermissionKind::ALL {
+            if matches!(self.expires.get(kind), Some(expires_at) if expires_at <= now) {
+                self.set(kind, PermissionState::Ask);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn state_mut(&mut self, kind: PermissionKind) -> &mut PermissionState {
+        match kind {
+            PermissionKind::Notifications => &mut self.notifications,
+            PermissionKind::Camera => &mut self.camera,
+            PermissionKind::Microphone => &mut self.microphone,
+            PermissionKind::Location => &mut self.location,
         }
     }
 }
@@ -182,6 +794,123 @@ impl PermissionStore {
     pub fn get_or_default_mut(&mut self, origin: &str) -> &mut PerOriginPermissions {
         self.origins.entry(origin.to_string()).or_default()
     }
+
+    /// Effective persisted state for `origin` at `now`; unknown origins resolve to `Ask`.
+    pub fn resolve(
+        &self,
+        origin: &str,
+        kind: PermissionKind,
+        now: OffsetDateTime,
+    ) -> PermissionState {
+        self.origins
+            .get(origin)
+            .map(|entry| entry.resolve(kind, now))
+            .unwrap_or_default()
+    }
+
+    /// Resets expired grants across all origins; returns true if the store needs saving.
+    pub fn prune_expired(&mut self, now: OffsetDateTime) -> bool {
+        let mut changed = false;
+        for entry in self.origins.values_mut() {
+            changed |= entry.prune_expired(now);
+        }
+        changed
+    }
+}
+
+/// Permission settings the manager applies to several apps at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionPreset {
+    /// Forget all decisions, so every site asks again.
+    AskForAll,
+    AllowNotifications,
+    BlockNotifications,
+    BlockAll,
+}
+
+impl PermissionPreset {
+    pub const ALL: [PermissionPreset; 4] = [
+        PermissionPreset::AskForAll,
+        PermissionPreset::AllowNotifications,
+        PermissionPreset::BlockNotifications,
+        PermissionPreset::BlockAll,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PermissionPreset::AskForAll => "Ask for Everything",
+            PermissionPreset::AllowNotifications => "Allow Notifications",
+            PermissionPreset::BlockNotifications => "Block Notifications",
+            PermissionPreset::BlockAll => "Block Everything",
+        }
+    }
+
+    /// Stable identifier, used as the manager's action target.
+    pub fn key(self) -> &'static str {
+        match self {
+            PermissionPreset::AskForAll => "ask_all",
+            PermissionPreset::AllowNotifications => "allow_notifications",
+            PermissionPreset::BlockNotifications => "block_notifications",
+            PermissionPreset::BlockAll => "block_all",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.key() == key)
+    }
+
+    /// Sets the preset's permissions, without expiry, for every origin in `store` and
+    /// for `primary_origin`; permissions the preset does not cover are left alone.
+    pub fn apply(self, store: &mut PermissionStore, primary_origin: &str) {
+        let (kinds, state): (&[PermissionKind], _) = match self {
+            PermissionPreset::AskForAll => (&PermissionKind::ALL, PermissionState::Ask),
+            PermissionPreset::AllowNotifications => {
+                (&[PermissionKind::Notifications], PermissionState::Allow)
+            }
+            PermissionPreset::BlockNotifications => {
+                (&[PermissionKind::Notifications], PermissionState::Block)
+            }
+            PermissionPreset::BlockAll => (&PermissionKind::ALL, PermissionState::Block),
+        };
+        store.get_or_default_mut(primary_origin);
+        for entry in store.origins.values_mut() {
+            for &kind in kinds {
+                entry.set(kind, state.clone());
+            }
+        }
+    }
+}
+
+/// In-memory grants that last until the shell process exits; never written to disk.
+#[derive(Debug, Clone, Default)]
+pub struct SessionGrants {
+    grants: HashMap<(String, PermissionKind), PermissionState>,
+}
+
+impl SessionGrants {
+    pub fn set(&mut self, origin: &str, kind: PermissionKind, state: PermissionState) {
+        self.grants.insert((origin.to_string(), kind), state);
+    }
+
+    pub fn get(&self, origin: &str, kind: PermissionKind) -> Option<PermissionState> {
+        self.grants.get(&(origin.to_string(), kind)).cloned()
+    }
+
+    pub fn clear(&mut self) {
+        self.grants.clear();
+    }
+
+    /// Session grants take precedence over the persisted store.
+    pub fn resolve(
+        &self,
+        store: &PermissionStore,
+        origin: &str,
+        kind: PermissionKind,
+        now: OffsetDateTime,
+    ) -> PermissionState {
+        self.get(origin, kind)
+            .unwrap_or_else(|| store.resolve(origin, kind, now))
+    }
 }
 
 #[derive(Clone)]
@@ -293,6 +1022,376 @@ impl PermissionRepository {
     }
 }
 
+/// What happened to a permission request, as recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// The site asked for a permission.
+    Requested,
+    /// The user was shown a prompt.
+    Prompted,
+    /// The user answered a prompt.
+    Decided,
+    /// A stored or session `allow` granted the request without prompting.
+    AutoAllowed,
+    /// A stored or session `block` denied the request without prompting.
+    AutoDenied,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditEntry {
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    pub origin: String,
+    pub permission: PermissionKind,
+    pub action: AuditAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision: Option<PermissionState>,
+    /// Free-form detail such as the grant lifetime chosen in a prompt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(origin: &str, permission: PermissionKind, action: AuditAction) -> Self {
+        Self {
+            at: OffsetDateTime::now_utc(),
+            origin: origin.to_string(),
+            permission,
+            action,
+            decision: None,
+            note: None,
+        }
+    }
+
+    pub fn with_decision(mut self, decision: PermissionState) -> Self {
+        self.decision = Some(decision);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+}
+
+/// Per-origin rollup of audit entries for the privacy dashboard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OriginActivity {
+    pub origin: String,
+    pub requests: usize,
+    pub prompts: usize,
+    pub allowed: usize,
+    pub denied: usize,
+    pub last_activity: Option<OffsetDateTime>,
+}
+
+/// Rotate once the active log grows past this size; one rotated file is kept.
+pub const AUDIT_LOG_MAX_BYTES: u64 = 256 * 1024;
+
+/// Append-only JSON-lines log of permission activity, one file per web app.
+#[derive(Clone)]
+pub struct AuditLog {
+    paths: AppPaths,
+    max_bytes: u64,
+}
+
+impl AuditLog {
+    pub fn new(paths: AppPaths) -> Self {
+        Self {
+            paths,
+            max_bytes: AUDIT_LOG_MAX_BYTES,
+        }
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn append(&self, id: WebAppId, entry: &AuditEntry) -> Result<()> {
+        let path = self.log_path(id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("create audit dir {parent:?}"))?;
+        }
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size >= self.max_bytes {
+            let rotated = self.rotated_path(id);
+            fs::rename(&path, &rotated)
+                .with_context(|| format!("rotate audit log {path:?} -> {rotated:?}"))?;
+        }
+        let mut line = serde_json::to_string(entry).context("serialize audit entry")?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("open audit log {path:?}"))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("append audit log {path:?}"))?;
+        Ok(())
+    }
+
+    /// Entries from the rotated and active files, oldest first. Corrupt lines are skipped.
+    pub fn read(&self, id: WebAppId) -> Result<Vec<AuditEntry>> {
+        let mut entries = Vec::new();
+        for path in [self.rotated_path(id), self.log_path(id)] {
+            if !path.exists() {
+                continue;
+            }
+            let data =
+                fs::read_to_string(&path).with_context(|| format!("read audit log {path:?}"))?;
+            entries.extend(
+                data.lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok()),
+            );
+        }
+        Ok(entries)
+    }
+
+    pub fn delete(&self, id: WebAppId) -> Result<()> {
+        for path in [self.log_path(id), self.rotated_path(id)] {
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("remove audit log {path:?}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Summarizes entries newer than `since` per origin, most recently active first.
+    pub fn summarize(entries: &[AuditEntry], since: OffsetDateTime) -> Vec<OriginActivity> {
+        let mut by_origin: HashMap<&str, OriginActivity> = HashMap::new();
+        for entry in entries.iter().filter(|e| e.at >= since) {
+            let activity =
+                by_origin
+                    .entry(entry.origin.as_str())
+                    .or_insert_with(|| OriginActivity {
+                        origin: entry.origin.clone(),
+                        ..OriginActivity::default()
+                    });
+            match entry.action {
+                AuditAction::Requested => activity.requests += 1,
+                AuditAction::Prompted => activity.prompts += 1,
+                AuditAction::AutoAllowed => activity.allowed += 1,
+                AuditAction::AutoDenied => activity.denied += 1,
+                AuditAction::Decided => match entry.decision {
+                    Some(PermissionState::Allow) => activity.allowed += 1,
+                    Some(PermissionState::Block) => activity.denied += 1,
+                    _ => {}
+                },
+            }
+            if activity.last_activity.is_none_or(|last| entry.at > last) {
+                activity.last_activity = Some(entry.at);
+            }
+        }
+        let mut summary: Vec<OriginActivity> = by_origin.into_values().collect();
+        summary.sort_by(|a, b| {
+            b.last_activity
+                .cmp(&a.last_activity)
+                .then_with(|| a.origin.cmp(&b.origin))
+        });
+        summary
+    }
+
+    fn log_path(&self, id: WebAppId) -> PathBuf {
+        self.paths.audit_dir().join(format!("{id}.log"))
+    }
+
+    fn rotated_path(&self, id: WebAppId) -> PathBuf {
+        self.paths.audit_dir().join(format!("{id}.log.1"))
+    }
+}
+
+/// An app in the trash, as recorded in its `entry.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedApp {
+    #[serde(with = "time::serde::rfc3339")]
+    pub removed_at: OffsetDateTime,
+    pub app: WebAppDefinition,
+}
+
+/// Removed apps, kept so removal can be undone. Each app's definition, permissions, audit
+/// log and profile move to `trash/<id>/`; its icons stay cached until it is purged.
+#[derive(Debug, Clone)]
+pub struct Trash {
+    paths: AppPaths,
+}
+
+impl Trash {
+    pub fn new(paths: AppPaths) -> Self {
+        Self { paths }
+    }
+
+    /// Moves `app` and its data into the trash. `entry.toml` is written once the data is
+    /// in place and the definition leaves the registry last; if any step fails, the data
+    /// already moved goes back, so the app stays as it was and never shows in [`Self::list`].
+    pub fn trash(&self, app: &WebAppDefinition) -> Result<TrashedApp> {
+        let entry = TrashedApp {
+            removed_at: OffsetDateTime::now_utc(),
+            app: app.clone(),
+        };
+        let dir = self.entry_dir(app.id);
+        let result = fs::create_dir_all(&dir)
+            .with_context(|| format!("create trash dir {dir:?}"))
+            .and_then(|()| {
+                move_all_or_none(self.locations(app.id), || {
+                    let toml = toml::to_string_pretty(&entry).context("serialize trash entry")?;
+                    let entry_path = dir.join("entry.toml");
+                    fs::write(&entry_path, toml)
+                        .with_context(|| format!("write {entry_path:?}"))?;
+                    AppRegistry::new(self.paths.clone()).delete(app.id)
+                })
+            });
+        if let Err(err) = result {
+            if dir.exists() {
+                let _ = fs::remove_dir_all(&dir);
+            }
+            return Err(err);
+        }
+        Ok(entry)
+    }
+
+    /// Apps in the trash, most recently removed first. Unreadable entries are skipped.
+    pub fn list(&self) -> Result<Vec<TrashedApp>> {
+        let dir = self.paths.trash_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries: Vec<TrashedApp> = Vec::new();
+        for entry in fs::read_dir(&dir).with_context(|| format!("read trash dir {dir:?}"))? {
+            let path = entry?.path().join("entry.toml");
+            if let Some(trashed) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|data| toml::from_str(&data).ok())
+            {
+                entries.push(trashed);
+            }
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.removed_at));
+        Ok(entries)
+    }
+
+    /// Moves the app and its data back and returns its definition. If any of it cannot
+    /// be moved, what already was goes back into the trash.
+    pub fn restore(&self, id: WebAppId) -> Result<WebAppDefinition> {
+        let entry = self.load(id)?;
+        let back = self.locations(id).map(|(live, trashed)| (trashed, live));
+        move_all_or_none(back, || {
+            AppRegistry::new(self.paths.clone()).save(&entry.app)
+        })?;
+        let dir = self.entry_dir(id);
+        fs::remove_dir_all(&dir).with_context(|| format!("remove trash dir {dir:?}"))?;
+        Ok(entry.app)
+    }
+
+    /// Deletes a trashed app and its icons for good.
+    pub fn purge(&self, id: WebAppId) -> Result<()> {
+        if let Ok(entry) = self.load(id) {
+            self.paths.delete_icons_for(&entry.app.icon_id)?;
+        }
+        let dir = self.entry_dir(id);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).with_context(|| format!("remove trash dir {dir:?}"))?;
+        }
+        Ok(())
+    }
+
+    /// Purges apps removed at least `max_age` before `now`, returning them. An entry that
+    /// cannot be purged is logged and left for the next time.
+    pub fn purge_older_than(
+        &self,
+        max_age: time::Duration,
+        now: OffsetDateTime,
+    ) -> Result<Vec<TrashedApp>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|entry| entry.removed_at + max_age <= now)
+            .filter(|entry| match self.purge(entry.app.id) {
+                Ok(()) => true,
+                Err(err) => {
+                    tracing::warn!(target: "model", app = %entry.app.id, "purge failed: {err:?}");
+                    false
+                }
+            })
+            .collect())
+    }
+
+    /// Bytes the trashed app's profile takes up.
+    pub fn profile_size(&self, id: WebAppId) -> u64 {
+        dir_size(&self.entry_dir(id).join("profile"))
+    }
+
+    fn load(&self, id: WebAppId) -> Result<TrashedApp> {
+        let path = self.entry_dir(id).join("entry.toml");
+        let data = fs::read_to_string(&path).with_context(|| format!("read {path:?}"))?;
+        toml::from_str(&data).with_context(|| format!("parse {path:?}"))
+    }
+
+    fn entry_dir(&self, id: WebAppId) -> PathBuf {
+        self.paths.trash_dir().join(id.to_string())
+    }
+
+    /// Where each piece of the app's data lives, paired with its place in the trash.
+    fn locations(&self, id: WebAppId) -> [(PathBuf, PathBuf); 4] {
+        let dir = self.entry_dir(id);
+        let audit = AuditLog::new(self.paths.clone());
+        [
+            (
+                PermissionRepository::new(self.paths.clone()).permission_path(id),
+                dir.join("permissions.toml"),
+            ),
+            (audit.log_path(id), dir.join("audit.log")),
+            (audit.rotated_path(id), dir.join("audit.log.1")),
+            (self.paths.profile_dir(id), dir.join("profile")),
+        ]
+    }
+}
+
+/// Moves each `(from, to)` pair, then runs `finish`. If a move or `finish` fails, the
+/// pairs already moved go back in reverse order and the error is returned.
+fn move_all_or_none(
+    pairs: impl IntoIterator<Item = (PathBuf, PathBuf)>,
+    finish: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    let mut moved = Vec::new();
+    let result = (|| {
+        for (from, to) in pairs {
+            move_path(&from, &to)?;
+            moved.push((from, to));
+        }
+        finish()
+    })();
+    if result.is_err() {
+        for (from, to) in moved.iter().rev() {
+            if let Err(undo_err) = move_path(to, from) {
+                tracing::warn!(target: "model", "move {to:?} back failed: {undo_err:?}");
+            }
+        }
+    }
+    result
+}
+
+/// Renames `from` to `to`, copying files that cannot be renamed across file systems.
+/// A missing `from` is not an error.
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if !from.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("create dir {parent:?}"))?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_dir() {
+        anyhow::bail!("cannot move {from:?} to {to:?}");
+    }
+    fs::copy(from, to).with_context(|| format!("copy {from:?} to {to:?}"))?;
+    fs::remove_file(from).with_context(|| format!("remove {from:?}"))
+}
+
 /// Convenience helper to validate and normalize URLs.
 pub fn normalize_url(input: &str) -> Result<Url> {
     let trimmed = input.trim();
@@ -347,16 +1446,296 @@ mod tests {
             WebAppDefinition::new("Example".into(), Url::parse("https://example.com").unwrap());
         app.behavior.open_external_links = false;
         app.behavior.show_navigation = true;
+        app.behavior.run_in_background = true;
+        app.behavior.start_at_login = true;
+        app.behavior.color_scheme = ColorSchemePreference::ForceDark;
         registry.save(&app).unwrap();
 
         let loaded = registry.load(app.id).unwrap();
         assert!(!loaded.behavior.open_external_links);
         assert!(loaded.behavior.show_navigation);
+        assert!(loaded.behavior.run_in_background);
+        assert!(loaded.behavior.start_at_login);
+        assert_eq!(
+            loaded.behavior.color_scheme,
+            ColorSchemePreference::ForceDark
+        );
+        assert!(loaded.behavior.color_scheme.is_dark(false));
+
+        // Files written before the background settings existed keep the old behavior.
+        let legacy: BehaviorConfig =
+            toml::from_str("open_external_links = true\nshow_navigation = false\n").unwrap();
+        assert!(!legacy.needs_background());
+        assert_eq!(legacy.color_scheme, ColorSchemePreference::FollowSystem);
+        assert!(legacy.color_scheme.is_dark(true));
 
         registry.delete(app.id).unwrap();
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn profile_size_counts_nested_files() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-size-{}", Uuid::new_v4()));
+        let paths = AppPaths::for_test(root.clone());
+        let id = Uuid::new_v4();
+        assert_eq!(paths.profile_size(id), 0);
+
+        let profile = paths.profile_dir(id);
+        fs::create_dir_all(profile.join("Cache/data")).unwrap();
+        fs::write(profile.join("Cookies"), [0; 100]).unwrap();
+        fs::write(profile.join("Cache/data/1"), [0; 28]).unwrap();
+        assert_eq!(paths.profile_size(id), 128);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn tags_are_parsed_matched_and_collected() {
+        assert_eq!(
+            parse_tags(" Work, dev,,work , Team A "),
+            ["Work", "dev", "Team A"]
+        );
+
+        let mut mail =
+            WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        mail.tags = parse_tags("Work, Comms");
+        let mut wiki =
+            WebAppDefinition::new("Wiki".into(), Url::parse("https://wiki.example").unwrap());
+        wiki.tags = parse_tags("work, Docs");
+        assert!(mail.matches_query("comm"));
+        assert!(mail.matches_query(" MAIL.example "));
+        assert!(!wiki.matches_query("comms"));
+        assert!(wiki.has_tag("WORK"));
+        assert_eq!(all_tags(&[mail, wiki]), ["Comms", "Docs", "Work"]);
+    }
+
+    #[test]
+    fn sort_modes_and_tag_filter() {
+        let at = |hour: i64| OffsetDateTime::UNIX_EPOCH + time::Duration::hours(hour);
+        let app = |name: &str, created: i64, launched: Option<i64>, tags: &str| {
+            let mut app =
+                WebAppDefinition::new(name.into(), Url::parse("https://example.com").unwrap());
+            app.created_at = at(created);
+            app.last_launched_at = launched.map(at);
+            app.tags = parse_tags(tags);
+            app
+        };
+        let apps = [
+            app("beta", 1, None, "ops"),
+            app("Alpha", 3, Some(5), ""),
+            app("gamma", 2, Some(9), "ops"),
+            app("delta", 0, None, ""),
+        ];
+        let names = |settings: &ManagerSettings, query: &str| -> Vec<String> {
+            settings
+                .visible_apps(&apps, query)
+                .into_iter()
+                .map(|app| app.name)
+                .collect()
+        };
+
+        let mut settings = ManagerSettings::default();
+        assert_eq!(names(&settings, ""), ["Alpha", "beta", "delta", "gamma"]);
+        settings.sort = SortMode::LastLaunched;
+        assert_eq!(names(&settings, ""), ["gamma", "Alpha", "beta", "delta"]);
+        settings.sort = SortMode::Created;
+        assert_eq!(names(&settings, ""), ["Alpha", "gamma", "beta", "delta"]);
+        settings.tag = Some("OPS".into());
+        assert_eq!(names(&settings, ""), ["gamma", "beta"]);
+        assert_eq!(names(&settings, "gam"), ["gamma"]);
+    }
+
+    #[test]
+    fn manager_settings_roundtrip() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-manager-{}", Uuid::new_v4()));
+        let paths = AppPaths::for_test(root.clone());
+        assert_eq!(ManagerSettings::load(&paths), ManagerSettings::default());
+
+        let settings = ManagerSettings {
+            sort: SortMode::LastLaunched,
+            grid: true,
+            tag: Some("Work".into()),
+            purge_after_days: 7,
+        };
+        settings.save(&paths).unwrap();
+        assert_eq!(ManagerSettings::load(&paths), settings);
+        assert_eq!(SortMode::from_key(settings.sort.key()), Some(settings.sort));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn permission_presets_cover_every_origin() {
+        let now = OffsetDateTime::now_utc();
+        let mut store = PermissionStore::default();
+        store.get_or_default_mut("https://chat.example").set_until(
+            PermissionKind::Notifications,
+            PermissionState::Allow,
+            now + time::Duration::hours(1),
+        );
+        store
+            .get_or_default_mut("https://chat.example")
+            .set(PermissionKind::Camera, PermissionState::Allow);
+
+        PermissionPreset::BlockNotifications.apply(&mut store, "https://example.com");
+        for origin in ["https://chat.example", "https://example.com"] {
+            assert_eq!(
+                store.resolve(origin, PermissionKind::Notifications, now),
+                PermissionState::Block
+            );
+        }
+        let chat = &store.origins["https://chat.example"];
+        assert!(chat.expires.is_empty());
+        assert_eq!(chat.camera, PermissionState::Allow);
+
+        PermissionPreset::AskForAll.apply(&mut store, "https://example.com");
+        assert!(store.origins.values().all(|entry| PermissionKind::ALL
+            .into_iter()
+            .all(|kind| entry.get(kind) == PermissionState::Ask)));
+        for preset in PermissionPreset::ALL {
+            assert_eq!(PermissionPreset::from_key(preset.key()), Some(preset));
+        }
+    }
+
+    #[test]
+    fn definition_fields_roundtrip() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-fields-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let registry = AppRegistry::new(AppPaths::for_test(root.clone()));
+
+        let mut app =
+            WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        registry.save(&app).unwrap();
+        let loaded = registry.load(app.id).unwrap();
+        assert!(loaded.shortcuts.is_empty());
+        assert!(loaded.tags.is_empty());
+        assert_eq!(loaded.launcher, LauncherStatus::Unknown);
+        assert_eq!(loaded.icon_source, IconSource::Site);
+        assert_eq!(loaded.accent_color, None);
+        assert_eq!(loaded.icon_style, IconStyle::Automatic);
+
+        app.shortcuts.push(AppShortcut {
+            label: "Compose".into(),
+            url: "https://mail.example/compose".into(),
+        });
+        app.tags = parse_tags("Work, Comms");
+        app.accent_color = Some("#3f51b5".into());
+        app.icon_style = IconStyle::Plate;
+        let launchers = [
+            LauncherStatus::Installed,
+            LauncherStatus::Exported {
+                path: root.join("applications/mail.desktop"),
+            },
+            LauncherStatus::Failed {
+                error: "portal missing".into(),
+            },
+        ];
+        let sources = [
+            IconSource::Url {
+                url: "https://mail.example/icon-512.png".into(),
+                maskable: true,
+            },
+            IconSource::Custom,
+            IconSource::Site,
+        ];
+        for (launcher, source) in launchers.into_iter().zip(sources) {
+            app.launcher = launcher;
+            app.icon_source = source;
+            registry.save(&app).unwrap();
+            let loaded = registry.load(app.id).unwrap();
+            assert_eq!(loaded.shortcuts, app.shortcuts);
+            assert_eq!(loaded.tags, app.tags);
+            assert_eq!(loaded.launcher, app.launcher);
+            assert_eq!(loaded.icon_source, app.icon_source);
+            assert_eq!(loaded.accent_color.as_deref(), Some("#3f51b5"));
+            assert_eq!(loaded.icon_style, IconStyle::Plate);
+        }
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn link_handlers_export_mime_types_and_roundtrip() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-links-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let registry = AppRegistry::new(AppPaths::for_test(root.clone()));
+
+        let mut app =
+            WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        app.link_handlers.schemes.push(SchemeHandler {
+            scheme: "mailto".into(),
+            url_template: "https://mail.example/compose?uri=%s".into(),
+        });
+        app.link_handlers
+            .origins
+            .push("https://mail.example".into());
+        assert_eq!(
+            app.link_handlers.mime_types(),
+            vec!["x-scheme-handler/mailto"]
+        );
+
+        app.link_handlers.claim_web_links = true;
+        app.link_handlers.schemes.push(SchemeHandler {
+            scheme: "HTTP".into(),
+            url_template: "https://mail.example/open?uri=%s".into(),
+        });
+        assert_eq!(
+            app.link_handlers.mime_types(),
+            vec![
+                "x-scheme-handler/http",
+                "x-scheme-handler/https",
+                "x-scheme-handler/mailto"
+            ]
+        );
+
+        registry.save(&app).unwrap();
+        let loaded = registry.load(app.id).unwrap();
+        assert_eq!(loaded.link_handlers, app.link_handlers);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn find_app_for_uri_prefers_specific_scope() {
+        let mut mail = WebAppDefinition::new(
+            "Mail".into(),
+            Url::parse("https://example.com/mail/").unwrap(),
+        );
+        mail.link_handlers
+            .origins
+            .push("https://example.com".into());
+        mail.link_handlers.schemes.push(SchemeHandler {
+            scheme: "mailto".into(),
+            url_template: "https://example.com/mail/compose?uri=%s".into(),
+        });
+        let mut portal =
+            WebAppDefinition::new("Portal".into(), Url::parse("https://example.com/").unwrap());
+        portal
+            .link_handlers
+            .origins
+            .push("https://example.com".into());
+        let apps = vec![portal, mail];
+
+        let uri = Url::parse("https://example.com/mail/inbox").unwrap();
+        let (app, target) = find_app_for_uri(&apps, &uri).unwrap();
+        assert_eq!(app.name, "Mail");
+        assert_eq!(target, uri);
+
+        let uri = Url::parse("https://example.com/news").unwrap();
+        assert_eq!(find_app_for_uri(&apps, &uri).unwrap().0.name, "Portal");
+
+        let uri = Url::parse("mailto:someone@example.com?subject=Hi there").unwrap();
+        let (app, target) = find_app_for_uri(&apps, &uri).unwrap();
+        assert_eq!(app.name, "Mail");
+        assert_eq!(
+            target.as_str(),
+            "https://example.com/mail/compose?uri=mailto%3Asomeone%40example.com%3Fsubject%3DHi%2520there"
+        );
+
+        let uri = Url::parse("https://elsewhere.example/").unwrap();
+        assert!(find_app_for_uri(&apps, &uri).is_none());
+    }
+
     #[test]
     fn registry_save_load_delete() {
         let root = std::env::temp_dir().join(format!("sitewrap-test-{}", Uuid::new_v4()));
@@ -379,6 +1758,183 @@ mod tests {
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn trash_restores_and_purges_apps() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-trash-{}", Uuid::new_v4()));
+        let paths = AppPaths::for_test(root.clone());
+        let registry = AppRegistry::new(paths.clone());
+        let permissions = PermissionRepository::new(paths.clone());
+        let audit = AuditLog::new(paths.clone());
+        let trash = Trash::new(paths.clone());
+
+        let app = WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        registry.save(&app).unwrap();
+        let mut store = PermissionStore::default();
+        store
+            .get_or_default_mut("https://mail.example")
+            .set(PermissionKind::Camera, PermissionState::Allow);
+        permissions.save(app.id, &store).unwrap();
+        audit
+            .append(
+                app.id,
+                &AuditEntry::new(
+                    "https://mail.example",
+                    PermissionKind::Camera,
+                    AuditAction::Requested,
+                ),
+            )
+            .unwrap();
+        fs::create_dir_all(paths.profile_dir(app.id)).unwrap();
+        fs::write(paths.profile_dir(app.id).join("cookies"), b"session").unwrap();
+        fs::create_dir_all(paths.icons_cache_dir()).unwrap();
+        let icon = paths
+            .icons_cache_dir()
+            .join(format!("{}-128x128.png", app.icon_id));
+        fs::write(&icon, b"png").unwrap();
+
+        trash.trash(&app).unwrap();
+        assert!(registry.list().unwrap().is_empty());
+        assert!(!paths.profile_dir(app.id).exists());
+        assert!(permissions.load(app.id).unwrap().origins.is_empty());
+        assert!(audit.read(app.id).unwrap().is_empty());
+        assert_eq!(trash.profile_size(app.id), 7);
+        let listed = trash.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].app.name, "Mail");
+
+        let restored = trash.restore(app.id).unwrap();
+        assert_eq!(restored.id, app.id);
+        assert_eq!(registry.load(app.id).unwrap().name, "Mail");
+        assert_eq!(
+            fs::read(paths.profile_dir(app.id).join("cookies")).unwrap(),
+            b"session"
+        );
+        assert_eq!(permissions.load(app.id).unwrap().origins.len(), 1);
+        assert_eq!(audit.read(app.id).unwrap().len(), 1);
+        assert!(trash.list().unwrap().is_empty());
+
+        let removed = trash.trash(&app).unwrap();
+        let now = removed.removed_at + time::Duration::days(2);
+        assert!(trash
+            .purge_older_than(time::Duration::days(7), now)
+            .unwrap()
+            .is_empty());
+        let purged = trash
+            .purge_older_than(time::Duration::days(1), now)
+            .unwrap();
+        assert_eq!(purged.len(), 1);
+        assert!(trash.list().unwrap().is_empty());
+        assert!(!icon.exists());
+        assert!(!paths.trash_dir().join(app.id.to_string()).exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn reset_keeps_imported_icons() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-reset-{}", Uuid::new_v4()));
+        let paths = AppPaths::for_test(root.clone());
+        fs::create_dir_all(paths.icons_cache_dir()).unwrap();
+        let mut app =
+            WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        let icon = |name: &str| {
+            paths
+                .icons_cache_dir()
+                .join(format!("{}{name}", app.icon_id))
+        };
+        for name in ["-custom.png", "-64x64.png"] {
+            fs::write(icon(name), b"png").unwrap();
+        }
+
+        app.icon_source = IconSource::Custom;
+        paths.reset_icons_for(&app).unwrap();
+        assert!(icon("-custom.png").exists() && icon("-64x64.png").exists());
+
+        app.icon_source = IconSource::Site;
+        paths.reset_icons_for(&app).unwrap();
+        assert!(!icon("-custom.png").exists() && !icon("-64x64.png").exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn trash_rolls_back_when_a_move_fails() {
+        let root =
+            std::env::temp_dir().join(format!("sitewrap-test-trash-fail-{}", Uuid::new_v4()));
+        let paths = AppPaths::for_test(root.clone());
+        let registry = AppRegistry::new(paths.clone());
+        let permissions = PermissionRepository::new(paths.clone());
+        let audit = AuditLog::new(paths.clone());
+        let trash = Trash::new(paths.clone());
+
+        let app = WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        registry.save(&app).unwrap();
+        let mut store = PermissionStore::default();
+        store
+            .get_or_default_mut("https://mail.example")
+            .set(PermissionKind::Camera, PermissionState::Allow);
+        permissions.save(app.id, &store).unwrap();
+        audit
+            .append(
+                app.id,
+                &AuditEntry::new(
+                    "https://mail.example",
+                    PermissionKind::Camera,
+                    AuditAction::Requested,
+                ),
+            )
+            .unwrap();
+        // A non-empty directory where the audit log should go makes its move fail.
+        let blocker = paths.trash_dir().join(app.id.to_string()).join("audit.log");
+        fs::create_dir_all(&blocker).unwrap();
+        fs::write(blocker.join("file"), b"x").unwrap();
+
+        assert!(trash.trash(&app).is_err());
+        assert_eq!(registry.load(app.id).unwrap().name, "Mail");
+        assert_eq!(permissions.load(app.id).unwrap().origins.len(), 1);
+        assert_eq!(audit.read(app.id).unwrap().len(), 1);
+        assert!(trash.list().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn restore_rolls_back_when_a_move_fails() {
+        let root =
+            std::env::temp_dir().join(format!("sitewrap-test-restore-fail-{}", Uuid::new_v4()));
+        let paths = AppPaths::for_test(root.clone());
+        let registry = AppRegistry::new(paths.clone());
+        let permissions = PermissionRepository::new(paths.clone());
+        let trash = Trash::new(paths.clone());
+
+        let app = WebAppDefinition::new("Mail".into(), Url::parse("https://mail.example").unwrap());
+        registry.save(&app).unwrap();
+        let mut store = PermissionStore::default();
+        store
+            .get_or_default_mut("https://mail.example")
+            .set(PermissionKind::Camera, PermissionState::Allow);
+        permissions.save(app.id, &store).unwrap();
+        fs::create_dir_all(paths.profile_dir(app.id)).unwrap();
+        fs::write(paths.profile_dir(app.id).join("cookies"), b"c").unwrap();
+        trash.trash(&app).unwrap();
+        // Something new in the live profile directory makes the profile's move fail
+        // after the permissions have already gone back.
+        fs::create_dir_all(paths.profile_dir(app.id)).unwrap();
+        fs::write(paths.profile_dir(app.id).join("new"), b"x").unwrap();
+
+        assert!(trash.restore(app.id).is_err());
+        assert!(registry.load(app.id).is_err());
+        assert!(permissions.load(app.id).unwrap().origins.is_empty());
+        assert_eq!(trash.list().unwrap().len(), 1);
+
+        fs::remove_dir_all(paths.profile_dir(app.id)).unwrap();
+        trash.restore(app.id).unwrap();
+        assert_eq!(permissions.load(app.id).unwrap().origins.len(), 1);
+        assert!(paths.profile_dir(app.id).join("cookies").exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn permission_repo_roundtrip() {
         let root = std::env::temp_dir().join(format!("sitewrap-test-perm-{}", Uuid::new_v4()));
@@ -418,6 +1974,165 @@ mod tests {
         assert!(!app.behavior.show_navigation);
     }
 
+    #[test]
+    fn expired_grant_resolves_to_ask() {
+        let now = OffsetDateTime::now_utc();
+        let mut store = PermissionStore::default();
+        let origin = "https://example.com";
+        store.get_or_default_mut(origin).set_until(
+            PermissionKind::Notifications,
+            PermissionState::Allow,
+            now + time::Duration::hours(1),
+        );
+
+        assert_eq!(
+            store.resolve(origin, PermissionKind::Notifications, now),
+            PermissionState::Allow
+        );
+        let later = now + time::Duration::hours(2);
+        assert_eq!(
+            store.resolve(origin, PermissionKind::Notifications, later),
+            PermissionState::Ask
+        );
+
+        assert!(store.prune_expired(later));
+        let entry = store.origins.get(origin).unwrap();
+        assert_eq!(entry.notifications, PermissionState::Ask);
+        assert!(entry.expires.is_empty());
+        assert!(!store.prune_expired(later));
+    }
+
+    #[test]
+    fn expiry_roundtrips_and_permanent_set_clears_it() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-expiry-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let repo = PermissionRepository::new(AppPaths::for_test(root.clone()));
+        let app_id = Uuid::new_v4();
+        let expires_at = time::macros::datetime!(2030-01-01 12:00 UTC);
+
+        let mut store = PermissionStore::default();
+        store.get_or_default_mut("https://example.com").set_until(
+            PermissionKind::Camera,
+            PermissionState::Allow,
+            expires_at,
+        );
+        repo.save(app_id, &store).unwrap();
+
+        let mut loaded = repo.load(app_id).unwrap();
+        let entry = loaded.get_or_default_mut("https://example.com");
+        assert_eq!(entry.camera, PermissionState::Allow);
+        assert_eq!(entry.expires.camera, Some(expires_at));
+
+        entry.set(PermissionKind::Camera, PermissionState::Block);
+        assert!(entry.expires.is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn session_grants_override_store() {
+        let now = OffsetDateTime::now_utc();
+        let mut store = PermissionStore::default();
+        store
+            .get_or_default_mut("https://example.com")
+            .notifications = PermissionState::Block;
+        let mut session = SessionGrants::default();
+        assert_eq!(
+            session.resolve(
+                &store,
+                "https://example.com",
+                PermissionKind::Notifications,
+                now
+            ),
+            PermissionState::Block
+        );
+
+        session.set(
+            "https://example.com",
+            PermissionKind::Notifications,
+            PermissionState::Allow,
+        );
+        assert_eq!(
+            session.resolve(
+                &store,
+                "https://example.com",
+                PermissionKind::Notifications,
+                now
+            ),
+            PermissionState::Allow
+        );
+
+        session.clear();
+        assert_eq!(
+            session.resolve(&store, "https://other.example", PermissionKind::Camera, now),
+            PermissionState::Ask
+        );
+    }
+
+    #[test]
+    fn audit_log_appends_rotates_and_summarizes() {
+        let root = std::env::temp_dir().join(format!("sitewrap-test-audit-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let paths = AppPaths::for_test(root.clone());
+        let log = AuditLog::new(paths.clone()).with_max_bytes(200);
+        let app_id = Uuid::new_v4();
+        let origin = "https://example.com";
+
+        log.append(
+            app_id,
+            &AuditEntry::new(
+                origin,
+                PermissionKind::Notifications,
+                AuditAction::Requested,
+            ),
+        )
+        .unwrap();
+        log.append(
+            app_id,
+            &AuditEntry::new(origin, PermissionKind::Notifications, AuditAction::Prompted),
+        )
+        .unwrap();
+        log.append(
+            app_id,
+            &AuditEntry::new(origin, PermissionKind::Notifications, AuditAction::Decided)
+                .with_decision(PermissionState::Allow)
+                .with_note("permanent"),
+        )
+        .unwrap();
+        log.append(
+            app_id,
+            &AuditEntry::new(
+                "https://other.example",
+                PermissionKind::Camera,
+                AuditAction::AutoDenied,
+            ),
+        )
+        .unwrap();
+
+        let rotated = paths.audit_dir().join(format!("{app_id}.log.1"));
+        assert!(rotated.exists(), "small size limit should force a rotation");
+
+        let entries = log.read(app_id).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[2].decision, Some(PermissionState::Allow));
+
+        let summary = AuditLog::summarize(&entries, OffsetDateTime::UNIX_EPOCH);
+        let example = summary.iter().find(|s| s.origin == origin).unwrap();
+        assert_eq!(example.requests, 1);
+        assert_eq!(example.prompts, 1);
+        assert_eq!(example.allowed, 1);
+        let other = summary
+            .iter()
+            .find(|s| s.origin == "https://other.example")
+            .unwrap();
+        assert_eq!(other.denied, 1);
+
+        log.delete(app_id).unwrap();
+        assert!(log.read(app_id).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn origin_for_matches_normalize() {
         let url = Url::parse("https://Example.com/path").unwrap();
@@ -0,0 +1,160 @@
+//! System appearance (`org.freedesktop.appearance`) from the Settings portal.
+
+use anyhow::{Context, Result};
+use ashpd::desktop::settings::{self, Settings};
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+use crate::RUNTIME;
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const APPEARANCE_KEYS: [&str; 2] = ["color-scheme", "accent-color"];
+
+/// The desktop-wide light/dark preference.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    NoPreference,
+    PreferDark,
+    PreferLight,
+}
+
+impl ColorScheme {
+    pub fn prefers_dark(self) -> bool {
+        self == ColorScheme::PreferDark
+    }
+}
+
+/// An sRGB color with channels in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccentColor {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+}
+
+impl AccentColor {
+    /// The portal reports out-of-range channels when the user has not picked an accent.
+    fn from_portal(red: f64, green: f64, blue: f64) -> Option<Self> {
+        [red, green, blue]
+            .iter()
+            .all(|channel| (0.0..=1.0).contains(channel))
+            .then_some(Self { red, green, blue })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Appearance {
+    pub color_scheme: ColorScheme,
+    pub accent_color: Option<AccentColor>,
+}
+
+/// Current appearance followed by every change, until the receiver is dropped. Ends
+/// without yielding anything when the Settings portal is unavailable.
+#[derive(Debug)]
+pub struct AppearanceWatch {
+    receiver: mpsc::UnboundedReceiver<Appearance>,
+}
+
+impl AppearanceWatch {
+    /// A watch fed by `sender`, for [`Portals`](crate::Portals) implementations.
+    pub fn channel() -> (mpsc::UnboundedSender<Appearance>, Self) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (sender, Self { receiver })
+    }
+
+    /// Waits for the next appearance; usable from any executor.
+    pub async fn next(&mut self) -> Option<Appearance> {
+        self.receiver.recv().await
+    }
+}
+
+/// Reads the current appearance once.
+pub fn appearance() -> Result<Appearance> {
+    RUNTIME.block_on(async {
+        let settings = Settings::new().await.context("connect Settings portal")?;
+        Ok(read_appearance(&settings).await)
+    })
+}
+
+/// Starts following the appearance on the portal runtime.
+pub(crate) fn watch_appearance() -> AppearanceWatch {
+    let (sender, watch) = AppearanceWatch::channel();
+    RUNTIME.spawn(async move {
+        if let Err(err) = forward_appearance(&sender).await {
+            debug!(target: "portal", error = %err, "appearance watch ended");
+        }
+    });
+    watch
+}
+
+async fn forward_appearance(sender: &mpsc::UnboundedSender<Appearance>) -> Result<()> {
+    let settings = Settings::new().await.context("connect Settings portal")?;
+    // Subscribe before the first read so no change slips in between.
+    let mut changes = settings
+        .receive_setting_changed()
+        .await
+        .context("subscribe to SettingChanged")?;
+    let mut current = read_appearance(&settings).await;
+    if sender.send(current).is_err() {
+        return Ok(());
+    }
+    loop {
+        let setting = tokio::select! {
+            _ = sender.closed() => return Ok(()),
+            setting = changes.next() => match setting {
+                Some(setting) => setting,
+                None => return Ok(()),
+            },
+        };
+        if setting.namespace() != APPEARANCE_NAMESPACE || !APPEARANCE_KEYS.contains(&setting.key())
+        {
+            continue;
+        }
+        let appearance = read_appearance(&settings).await;
+        if appearance != current {
+            info!(target: "portal", ?appearance, "system appearance changed");
+            current = appearance;
+            if sender.send(current).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn read_appearance(settings: &Settings<'_>) -> Appearance {
+    // Older portals lack these keys; treat that as "no preference" rather than an error.
+    let color_scheme = match settings.color_scheme().await {
+        Ok(settings::ColorScheme::PreferDark) => ColorScheme::PreferDark,
+        Ok(settings::ColorScheme::PreferLight) => ColorScheme::PreferLight,
+        Ok(settings::ColorScheme::NoPreference) | Err(_) => ColorScheme::NoPreference,
+    };
+    let accent_color = settings
+        .accent_color()
+        .await
+        .ok()
+        .and_then(|color| AccentColor::from_portal(color.red(), color.green(), color.blue()));
+    Appearance {
+        color_scheme,
+        accent_color,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_accent_means_unset() {
+        assert_eq!(
+            AccentColor::from_portal(0.2, 0.4, 1.0),
+            Some(AccentColor {
+                red: 0.2,
+                green: 0.4,
+                blue: 1.0
+            })
+        );
+        assert_eq!(AccentColor::from_portal(-1.0, -1.0, -1.0), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Per-portal availability, so callers can degrade one feature at a time instead of
+//! treating "portals" as a single on/off switch.
+
+use ashpd::zbus::{Connection, Proxy};
+use tracing::debug;
+
+const DESKTOP_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const DESKTOP_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// A portal interface Sitewrap uses or plans to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PortalKind {
+    DynamicLauncher,
+    Notification,
+    OpenUri,
+    FileChooser,
+    Camera,
+    Location,
+    Settings,
+    Background,
+}
+
+impl PortalKind {
+    pub const ALL: [PortalKind; 8] = [
+        PortalKind::DynamicLauncher,
+        PortalKind::Notification,
+        PortalKind::OpenUri,
+        PortalKind::FileChooser,
+        PortalKind::Camera,
+        PortalKind::Location,
+        PortalKind::Settings,
+        PortalKind::Background,
+    ];
+
+    /// D-Bus interface name under `org.freedesktop.portal.Desktop`.
+    pub fn interface(self) -> &'static str {
+        match self {
+            PortalKind::DynamicLauncher => "org.freedesktop.portal.DynamicLauncher",
+            PortalKind::Notification => "org.freedesktop.portal.Notification",
+            PortalKind::OpenUri => "org.freedesktop.portal.OpenURI",
+            PortalKind::FileChooser => "org.freedesktop.portal.FileChooser",
+            PortalKind::Camera => "org.freedesktop.portal.Camera",
+            PortalKind::Location => "org.freedesktop.portal.Location",
+            PortalKind::Settings => "org.freedesktop.portal.Settings",
+            PortalKind::Background => "org.freedesktop.portal.Background",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PortalKind::DynamicLauncher => "Dynamic Launcher",
+            PortalKind::Notification => "Notifications",
+            PortalKind::OpenUri => "Open URI",
+            PortalKind::FileChooser => "File Chooser",
+            PortalKind::Camera => "Camera",
+            PortalKind::Location => "Location",
+            PortalKind::Settings => "Settings",
+            PortalKind::Background => "Background",
+        }
+    }
+
+    /// What stops working without this portal, for diagnostics.
+    pub fn affects(self) -> &'static str {
+        match self {
+            PortalKind::DynamicLauncher => "Installing launchers (manual export still works)",
+            PortalKind::Notification => "Web notifications",
+            PortalKind::OpenUri => "Opening links in the default browser",
+            PortalKind::FileChooser => "Saving files and picking export folders",
+            PortalKind::Camera => "Camera access for web apps",
+            PortalKind::Location => "Location access for web apps",
+            PortalKind::Settings => "Following the system color scheme",
+            PortalKind::Background => "Running in the background",
+        }
+    }
+}
+
+/// Interface version of each portal; `None` when the portal is not reachable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PortalCapabilities {
+    pub dynamic_launcher: Option<u32>,
+    pub notification: Option<u32>,
+    pub open_uri: Option<u32>,
+    pub file_chooser: Option<u32>,
+    pub camera: Option<u32>,
+    pub location: Option<u32>,
+    pub settings: Option<u32>,
+    pub background: Option<u32>,
+}
+
+impl PortalCapabilities {
+    /// Every portal reported at `version`; handy for tests and mocks.
+    pub fn all(version: u32) -> Self {
+        let mut capabilities = Self::default();
+        for kind in PortalKind::ALL {
+            capabilities.set(kind, Some(version));
+        }
+        capabilities
+    }
+
+    pub fn version(&self, kind: PortalKind) -> Option<u32> {
+        match kind {
+            PortalKind::DynamicLauncher => self.dynamic_launcher,
+            PortalKind::Notification => self.notification,
+            PortalKind::OpenUri => self.open_uri,
+            PortalKind::FileChooser => self.file_chooser,
+            PortalKind::Camera => self.camera,
+            PortalKind::Location => self.location,
+            PortalKind::Settings => self.settings,
+            PortalKind::Background => self.background,
+        }
+    }
+
+    pub fn set(&mut self, kind: PortalKind, version: Option<u32>) {
+        let slot = match kind {
+            PortalKind::DynamicLauncher => &mut self.dynamic_launcher,
+            PortalKind::Notification => &mut self.notification,
+            PortalKind::OpenUri => &mut self.open_uri,
+            PortalKind::FileChooser => &mut self.file_chooser,
+            PortalKind::Camera => &mut self.camera,
+            PortalKind::Location => &mut self.location,
+            PortalKind::Settings => &mut self.settings,
+            PortalKind::Background => &mut self.background,
+        };
+        *slot = version;
+    }
+
+    pub fn has(&self, kind: PortalKind) -> bool {
+        self.version(kind).is_some()
+    }
+
+    pub fn missing(&self) -> Vec<PortalKind> {
+        PortalKind::ALL
+            .into_iter()
+            .filter(|&kind| !self.has(kind))
+            .collect()
+    }
+}
+
+/// Reads each interface's `version` property; portals that don't answer are reported as missing.
+pub(crate) async fn probe_capabilities() -> PortalCapabilities {
+    let mut capabilities = PortalCapabilities::default();
+    let Ok(connection) = Connection::session().await else {
+        debug!(target: "portal", "no session bus; all portals unavailable");
+        return capabilities;
+    };
+    for kind in PortalKind::ALL {
+        capabilities.set(kind, interface_version(&connection, kind).await);
+    }
+    debug!(target: "portal", ?capabilities, "probed portal capabilities");
+    capabilities
+}
+
+pub(crate) async fn interface_version(connection: &Connection, kind: PortalKind) -> Option<u32> {
+    let proxy = Proxy::new(
+        connection,
+        DESKTOP_DESTINATION,
+        DESKTOP_PATH,
+        kind.interface(),
+    )
+    .await
+    .ok()?;
+    proxy.get_property::<u32>("version").await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_missing_portals_in_order() {
+        let mut capabilities = PortalCapabilities::all(2);
+        assert!(capabilities.missing().is_empty());
+
+        capabilities.set(PortalKind::Camera, None);
+        capabilities.set(PortalKind::DynamicLauncher, None);
+        assert_eq!(
+            capabilities.missing(),
+            vec![PortalKind::DynamicLauncher, PortalKind::Camera]
+        );
+        assert_eq!(capabilities.version(PortalKind::Settings), Some(2));
+        assert!(!capabilities.has(PortalKind::Camera));
+    }
+}
@@ -0,0 +1,506 @@
+//! Typed builder and parser for freedesktop `.desktop` files.
+//!
+//! Values are escaped per the Desktop Entry Specification so user-provided names cannot break
+//! out of their key or inject extra groups, and `Exec` arguments are quoted per the spec's
+//! command-line rules.
+
+use anyhow::{bail, Context, Result};
+
+/// Field codes that are passed through `Exec` unquoted so the launcher can expand them.
+const FIELD_CODES: &[&str] = &["%f", "%F", "%u", "%U", "%i", "%c", "%k"];
+
+/// Characters that force an `Exec` argument to be quoted.
+const EXEC_RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')',
+    '`',
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesktopEntry {
+    pub name: String,
+    /// Command line as separate arguments; quoting is applied when rendering.
+    pub exec: Vec<String>,
+    pub icon: Option<String>,
+    pub comment: Option<String>,
+    pub startup_wm_class: Option<String>,
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+    pub mime_types: Vec<String>,
+    pub actions: Vec<DesktopAction>,
+}
+
+/// An additional application action, rendered as a `[Desktop Action <id>]` group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub exec: Vec<String>,
+    pub icon: Option<String>,
+}
+
+impl DesktopAction {
+    /// Creates an action; characters outside `[A-Za-z0-9-]` in `id` are replaced with `-`.
+    pub fn new<I, S>(id: &str, name: impl Into<String>, exec: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            id: sanitize_action_id(id),
+            name: name.into(),
+            exec: exec.into_iter().map(Into::into).collect(),
+            icon: None,
+        }
+    }
+
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+}
+
+impl DesktopEntry {
+    pub fn new<I, S>(name: impl Into<String>, exec: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            exec: exec.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    pub fn startup_wm_class(mut self, class: impl Into<String>) -> Self {
+        self.startup_wm_class = Some(class.into());
+        self
+    }
+
+    pub fn categories<I, S>(mut self, categories: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.categories = categories.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keywords = keywords.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn mime_types<I, S>(mut self, mime_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.mime_types = mime_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn action(mut self, action: DesktopAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Renders the entry; empty optional keys are omitted.
+    pub fn render(&self) -> String {
+        let mut out = String::from("[Desktop Entry]\n");
+        push_key(&mut out, "Name", &escape_string(&self.name));
+        push_key(&mut out, "Exec", &render_exec(&self.exec));
+        push_key(&mut out, "Type", "Application");
+        if let Some(icon) = &self.icon {
+            push_key(&mut out, "Icon", &escape_string(icon));
+        }
+        if !self.categories.is_empty() {
+            push_key(&mut out, "Categories", &render_list(&self.categories));
+        }
+        if let Some(comment) = &self.comment {
+            push_key(&mut out, "Comment", &escape_string(comment));
+        }
+        if !self.keywords.is_empty() {
+            push_key(&mut out, "Keywords", &render_list(&self.keywords));
+        }
+        if let Some(class) = &self.startup_wm_class {
+            push_key(&mut out, "StartupWMClass", &escape_string(class));
+        }
+        if !self.mime_types.is_empty() {
+            push_key(&mut out, "MimeType", &render_list(&self.mime_types));
+        }
+        if !self.actions.is_empty() {
+            let ids: Vec<String> = self.actions.iter().map(|a| a.id.clone()).collect();
+            push_key(&mut out, "Actions", &render_list(&ids));
+        }
+        for action in &self.actions {
+            out.push_str(&format!("\n[Desktop Action {}]\n", action.id));
+            push_key(&mut out, "Name", &escape_string(&action.name));
+            push_key(&mut out, "Exec", &render_exec(&action.exec));
+            if let Some(icon) = &action.icon {
+                push_key(&mut out, "Icon", &escape_string(icon));
+            }
+        }
+        out
+    }
+
+    /// Parses a desktop file back into an entry. Unknown and localized keys are ignored.
+    pub fn parse(input: &str) -> Result<Self> {
+        let groups = parse_groups(input)?;
+        let main = groups
+            .iter()
+            .find(|(name, _)| name == "Desktop Entry")
+            .map(|(_, keys)| keys)
+            .context("missing [Desktop Entry] group")?;
+
+        let mut entry = DesktopEntry {
+            name: unescape_string(lookup(main, "Name").context("missing Name key")?)?,
+            exec: parse_exec(lookup(main, "Exec").context("missing Exec key")?)?,
+            icon: lookup(main, "Icon").map(unescape_string).transpose()?,
+            comment: lookup(main, "Comment").map(unescape_string).transpose()?,
+            startup_wm_class: lookup(main, "StartupWMClass")
+                .map(unescape_string)
+                .transpose()?,
+            categories: lookup(main, "Categories")
+                .map(parse_list)
+                .transpose()?
+                .unwrap_or_default(),
+            keywords: lookup(main, "Keywords")
+                .map(parse_list)
+                .transpose()?
+                .unwrap_or_default(),
+            mime_types: lookup(main, "MimeType")
+                .map(parse_list)
+                .transpose()?
+                .unwrap_or_default(),
+            actions: Vec::new(),
+        };
+
+        let action_ids = lookup(main, "Actions")
+            .map(parse_list)
+            .transpose()?
+            .unwrap_or_default();
+        for id in action_ids {
+            let group_name = format!("Desktop Action {id}");
+            let keys = groups
+                .iter()
+                .find(|(name, _)| *name == group_name)
+                .map(|(_, keys)| keys)
+                .with_context(|| format!("missing [{group_name}] group"))?;
+            entry.actions.push(DesktopAction {
+                id,
+                name: unescape_string(lookup(keys, "Name").context("action missing Name")?)?,
+                exec: parse_exec(lookup(keys, "Exec").context("action missing Exec")?)?,
+                icon: lookup(keys, "Icon").map(unescape_string).transpose()?,
+            });
+        }
+
+        Ok(entry)
+    }
+}
+
+type Group = (String, Vec<(String, String)>);
+
+fn push_key(out: &mut String, key: &str, value: &str) {
+    out.push_str(key);
+    out.push('=');
+    out.push_str(value);
+    out.push('\n');
+}
+
+fn lookup<'a>(keys: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    keys.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn sanitize_action_id(id: &str) -> String {
+    let sanitized: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if sanitized.is_empty() {
+        "action".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// Escapes a `string`/`localestring` value. Leading spaces use `\s` so they survive trimming.
+pub fn escape_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut leading = true;
+    for c in value.chars() {
+        match c {
+            ' ' if leading => out.push_str("\\s"),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+        if c != ' ' {
+            leading = false;
+        }
+    }
+    out
+}
+
+pub fn unescape_string(value: &str) -> Result<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            // Only meaningful inside lists, where `parse_list` has already split on bare `;`.
+            Some(';') => out.push(';'),
+            Some(other) => bail!("invalid escape sequence \\{other}"),
+            None => bail!("dangling backslash at end of value"),
+        }
+    }
+    Ok(out)
+}
+
+fn render_list(items: &[String]) -> String {
+    let mut out = String::new();
+    for item in items {
+        out.push_str(&escape_string(item).replace(';', "\\;"));
+        out.push(';');
+    }
+    out
+}
+
+fn parse_list(value: &str) -> Result<Vec<String>> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            ';' => items.push(unescape_string(&std::mem::take(&mut current))?),
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        items.push(unescape_string(&current)?);
+    }
+    Ok(items)
+}
+
+fn quote_exec_arg(arg: &str) -> String {
+    if FIELD_CODES.contains(&arg) {
+        return arg.to_string();
+    }
+    let arg = arg.replace('%', "%%");
+    if !arg.is_empty() && !arg.contains(EXEC_RESERVED) {
+        return arg;
+    }
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn render_exec(args: &[String]) -> String {
+    let line = args
+        .iter()
+        .map(|arg| quote_exec_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    escape_string(&line)
+}
+
+fn parse_exec(value: &str) -> Result<Vec<String>> {
+    let line = unescape_string(value)?;
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_arg = true;
+            }
+            '\\' if in_quotes => match chars.next() {
+                Some(escaped) => current.push(escaped),
+                None => bail!("dangling backslash in Exec"),
+            },
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                current.push('%');
+                in_arg = true;
+            }
+            ' ' | '\t' if !in_quotes => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            _ => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_quotes {
+        bail!("unterminated quote in Exec");
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+fn parse_groups(input: &str) -> Result<Vec<Group>> {
+    let mut groups: Vec<Group> = Vec::new();
+    for (index, raw) in input.lines().enumerate() {
+        let line = raw.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .with_context(|| format!("line {}: malformed group header", index + 1))?;
+            groups.push((name.to_string(), Vec::new()));
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .with_context(|| format!("line {}: expected key=value", index + 1))?;
+        let Some((_, keys)) = groups.last_mut() else {
+            bail!("line {}: key outside of any group", index + 1);
+        };
+        keys.push((key.trim().to_string(), value.trim_start().to_string()));
+    }
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DesktopEntry {
+        DesktopEntry::new("Demo App", ["sitewrap", "--shell", "123"])
+            .icon("xyz.andriishafar.Sitewrap.webapp.123")
+            .categories(["Network", "WebBrowser"])
+    }
+
+    #[test]
+    fn renders_minimal_entry() {
+        let expected = "[Desktop Entry]\nName=Demo App\nExec=sitewrap --shell 123\nType=Application\nIcon=xyz.andriishafar.Sitewrap.webapp.123\nCategories=Network;WebBrowser;\n";
+        assert_eq!(sample().render(), expected);
+    }
+
+    #[test]
+    fn hostile_name_cannot_inject_keys_or_groups() {
+        let entry = DesktopEntry::new(
+            "Evil\nExec=rm -rf ~\n[Desktop Entry]\nName=x",
+            ["sitewrap", "--shell", "123"],
+        );
+        let rendered = entry.render();
+        assert_eq!(rendered.matches("\nExec=").count(), 1);
+        assert_eq!(rendered.lines().filter(|l| l.starts_with('[')).count(), 1);
+        assert_eq!(DesktopEntry::parse(&rendered).unwrap(), entry);
+    }
+
+    #[test]
+    fn roundtrips_all_keys() {
+        let entry = sample()
+            .comment("  Mail; calendar\\contacts\tand more")
+            .startup_wm_class("xyz.andriishafar.Sitewrap.webapp.123")
+            .keywords(["mail", "semi;colon", "back\\slash", "trailing\\"])
+            .mime_types(["x-scheme-handler/mailto"])
+            .action(DesktopAction::new(
+                "compose",
+                "Compose = new",
+                [
+                    "sitewrap",
+                    "--shell",
+                    "123",
+                    "--url",
+                    "https://mail.example/compose?to=a b&x=%20",
+                ],
+            ))
+            .action(
+                DesktopAction::new("inbox", "Open Inbox", ["sitewrap", "--shell", "123"])
+                    .icon("mail-inbox"),
+            );
+        let rendered = entry.render();
+        assert!(rendered.contains("Actions=compose;inbox;\n"));
+        assert!(rendered.contains("\n[Desktop Action compose]\n"));
+        assert_eq!(DesktopEntry::parse(&rendered).unwrap(), entry);
+    }
+
+    #[test]
+    fn exec_quoting_follows_spec() {
+        let entry = DesktopEntry::new(
+            "x",
+            [
+                "sitewrap",
+                "--url",
+                "https://a.example/?q=\"$HOME\"",
+                "%u",
+                "100%",
+            ],
+        );
+        let rendered = entry.render();
+        let exec_line = rendered.lines().find(|l| l.starts_with("Exec=")).unwrap();
+        assert_eq!(
+            exec_line,
+            r#"Exec=sitewrap --url "https://a.example/?q=\\"\\$HOME\\"" %u 100%%"#
+        );
+        assert_eq!(DesktopEntry::parse(&rendered).unwrap().exec, entry.exec);
+    }
+
+    #[test]
+    fn action_ids_are_sanitized() {
+        let action = DesktopAction::new("new window!", "New Window", ["sitewrap"]);
+        assert_eq!(action.id, "new-window-");
+    }
+
+    #[test]
+    fn parse_rejects_missing_keys() {
+        assert!(DesktopEntry::parse("[Desktop Entry]\nName=x\n").is_err());
+        assert!(DesktopEntry::parse("Name=x\n").is_err());
+    }
+}
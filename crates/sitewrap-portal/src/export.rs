@@ -0,0 +1,258 @@
+//! Manual launcher export for desktops without the DynamicLauncher portal.
+//!
+//! Files are laid out like `$XDG_DATA_HOME`, so exporting into `~/.local/share`
+//! installs the launcher directly; any other directory can be copied there later.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use ashpd::desktop::file_chooser::SelectedFiles;
+use tracing::{info, warn};
+
+use crate::{desktop_entry_from_descriptor, LauncherDescriptor, RUNTIME};
+
+/// Files written by [`export_launcher`].
+#[derive(Debug, Clone)]
+pub struct ExportedLauncher {
+    pub desktop_file: PathBuf,
+    pub icons: Vec<PathBuf>,
+}
+
+/// Writes `applications/<desktop_id>`, `icons/hicolor/<size>/apps/<icon_name>.png` and, for
+/// SVG icons, `icons/hicolor/scalable/apps/<icon_name>.svg` under `dir`.
+pub fn export_launcher(descriptor: &LauncherDescriptor, dir: &Path) -> Result<ExportedLauncher> {
+    info!(target: "portal", desktop_id = %descriptor.desktop_id, dir = %dir.display(), "export launcher manually");
+    let mut descriptor = descriptor.clone();
+    descriptor.exec = host_exec(
+        &descriptor.exec,
+        std::env::var("FLATPAK_ID").ok().as_deref(),
+    );
+
+    let mut icons = Vec::new();
+    for (size, source) in &descriptor.icon_set {
+        let target_dir = dir
+            .join("icons/hicolor")
+            .join(format!("{size}x{size}"))
+            .join("apps");
+        fs::create_dir_all(&target_dir)
+            .with_context(|| format!("create icon dir {target_dir:?}"))?;
+        let target = target_dir.join(format!("{}.png", descriptor.icon_name));
+        match fs::copy(source, &target) {
+            Ok(_) => icons.push(target),
+            Err(err) => {
+                warn!(target: "portal", path = %source.display(), error = %err, "skip icon during export")
+            }
+        }
+    }
+
+    if let Some(source) = &descriptor.scalable_icon {
+        let target_dir = dir.join("icons/hicolor/scalable/apps");
+        fs::create_dir_all(&target_dir)
+            .with_context(|| format!("create icon dir {target_dir:?}"))?;
+        let target = target_dir.join(format!("{}.svg", descriptor.icon_name));
+        match fs::copy(source, &target) {
+            Ok(_) => icons.push(target),
+            Err(err) => {
+                warn!(target: "portal", path = %source.display(), error = %err, "skip icon during export")
+            }
+        }
+    }
+
+    let applications = dir.join("applications");
+    fs::create_dir_all(&applications)
+        .with_context(|| format!("create applications dir {applications:?}"))?;
+    let desktop_file = applications.join(&descriptor.desktop_id);
+    fs::write(&desktop_file, desktop_entry_from_descriptor(&descriptor))
+        .with_context(|| format!("write desktop file {desktop_file:?}"))?;
+
+    Ok(ExportedLauncher {
+        desktop_file,
+        icons,
+    })
+}
+
+/// Deletes what [`export_launcher`] wrote for `icon_name` next to `desktop_file`: the
+/// desktop file and the icon in every size of the hicolor theme. Missing files are skipped.
+pub fn remove_exported_launcher(desktop_file: &Path, icon_name: &str) -> Result<()> {
+    remove_if_exists(desktop_file)?;
+    let Some(dir) = desktop_file.parent().and_then(Path::parent) else {
+        return Ok(());
+    };
+    let hicolor = dir.join("icons/hicolor");
+    let Ok(sizes) = fs::read_dir(&hicolor) else {
+        return Ok(());
+    };
+    for size in sizes.flatten() {
+        let apps = size.path().join("apps");
+        for ext in ["png", "svg"] {
+            remove_if_exists(&apps.join(format!("{icon_name}.{ext}")))?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("remove {path:?}")),
+    }
+}
+
+/// Rewrites the command so the host can start it; inside Flatpak the binary is only
+/// reachable through `flatpak run`. The DynamicLauncher portal does this for us.
+fn host_exec(exec: &[String], flatpak_id: Option<&str>) -> Vec<String> {
+    let Some(app_id) = flatpak_id else {
+        return exec.to_vec();
+    };
+    let Some((command, args)) = exec.split_first() else {
+        return exec.to_vec();
+    };
+    let mut wrapped = vec![
+        "flatpak".to_string(),
+        "run".to_string(),
+        format!("--command={command}"),
+        app_id.to_string(),
+    ];
+    wrapped.extend(args.iter().cloned());
+    wrapped
+}
+
+/// Asks for an export directory through the FileChooser portal.
+/// Returns `Ok(None)` when the user cancels.
+pub fn pick_export_directory(title: &str) -> Result<Option<PathBuf>> {
+    RUNTIME.block_on(pick_export_directory_async(title))
+}
+
+pub(crate) async fn pick_export_directory_async(title: &str) -> Result<Option<PathBuf>> {
+    info!(target: "portal", "pick export directory via FileChooser portal");
+    let response = SelectedFiles::open_file()
+        .title(title)
+        .accept_label("Export")
+        .modal(true)
+        .directory(true)
+        .send()
+        .await
+        .context("open FileChooser portal")?
+        .response();
+    let response = match response {
+        Ok(response) => response,
+        Err(ashpd::Error::Response(ashpd::desktop::ResponseError::Cancelled)) => return Ok(None),
+        Err(err) => return Err(err).context("read FileChooser response"),
+    };
+    Ok(response
+        .uris()
+        .first()
+        .and_then(|uri| uri.to_file_path().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_exec_for_flatpak_hosts() {
+        let exec = vec![
+            "sitewrap".to_string(),
+            "--shell".to_string(),
+            "123".to_string(),
+        ];
+        assert_eq!(host_exec(&exec, None), exec);
+        assert_eq!(
+            host_exec(&exec, Some("xyz.andriishafar.Sitewrap")),
+            vec![
+                "flatpak",
+                "run",
+                "--command=sitewrap",
+                "xyz.andriishafar.Sitewrap",
+                "--shell",
+                "123"
+            ]
+        );
+    }
+
+    #[test]
+    fn export_writes_desktop_file_and_icons() {
+        let dir = std::env::temp_dir().join(format!(
+            "sitewrap-export-{}-{}",
+            std::process::id(),
+            time::OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let icon = dir.join("source-48x48.png");
+        fs::write(&icon, b"png").unwrap();
+        let scalable = dir.join("source.svg");
+        fs::write(&scalable, b"<svg/>").unwrap();
+
+        let descriptor = LauncherDescriptor {
+            desktop_id: "xyz.andriishafar.Sitewrap.webapp.123.desktop".into(),
+            name: "Demo".into(),
+            exec: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+            icon_name: "xyz.andriishafar.Sitewrap.webapp.123".into(),
+            icon_set: vec![(48, icon), (128, dir.join("missing.png"))],
+            scalable_icon: Some(scalable),
+            ..Default::default()
+        };
+        let exported = export_launcher(&descriptor, &dir.join("share")).unwrap();
+
+        assert_eq!(
+            exported.desktop_file,
+            dir.join("share/applications/xyz.andriishafar.Sitewrap.webapp.123.desktop")
+        );
+        let entry = fs::read_to_string(&exported.desktop_file).unwrap();
+        assert!(entry.contains("Name=Demo\n"));
+        assert!(entry.contains("Icon=xyz.andriishafar.Sitewrap.webapp.123\n"));
+        assert_eq!(
+            exported.icons,
+            vec![
+                dir.join("share/icons/hicolor/48x48/apps/xyz.andriishafar.Sitewrap.webapp.123.png"),
+                dir.join(
+                    "share/icons/hicolor/scalable/apps/xyz.andriishafar.Sitewrap.webapp.123.svg"
+                ),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn remove_deletes_exported_desktop_file_and_icons() {
+        let dir = std::env::temp_dir().join(format!(
+            "sitewrap-unexport-{}-{}",
+            std::process::id(),
+            time::OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let icon = dir.join("source-48x48.png");
+        fs::write(&icon, b"png").unwrap();
+        let scalable = dir.join("source.svg");
+        fs::write(&scalable, b"<svg/>").unwrap();
+        let share = dir.join("share");
+        let other_icon = share.join("icons/hicolor/48x48/apps/other.png");
+        fs::create_dir_all(other_icon.parent().unwrap()).unwrap();
+        fs::write(&other_icon, b"png").unwrap();
+
+        let descriptor = LauncherDescriptor {
+            desktop_id: "xyz.andriishafar.Sitewrap.webapp.123.desktop".into(),
+            name: "Demo".into(),
+            exec: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+            icon_name: "xyz.andriishafar.Sitewrap.webapp.123".into(),
+            icon_set: vec![(48, icon)],
+            scalable_icon: Some(scalable),
+            ..Default::default()
+        };
+        let exported = export_launcher(&descriptor, &share).unwrap();
+        remove_exported_launcher(&exported.desktop_file, &descriptor.icon_name).unwrap();
+
+        assert!(!exported.desktop_file.exists());
+        for icon in &exported.icons {
+            assert!(!icon.exists(), "{icon:?}");
+        }
+        assert!(other_icon.exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
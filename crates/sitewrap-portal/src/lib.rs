@@ -1,28 +1,55 @@
 use std::{fs, path::PathBuf};
 
 use anyhow::{Context, Result};
-use ashpd::desktop::file_chooser::SelectedFiles;
+use ashpd::desktop::file_chooser::{FileFilter, SelectedFiles};
 use ashpd::desktop::Icon;
-use ashpd::desktop::{dynamic_launcher, notification, open_uri};
+use ashpd::desktop::{background, dynamic_launcher, notification, open_uri};
 use ashpd::url::Url;
 use once_cell::sync::Lazy;
 use thiserror::Error;
 use tokio::runtime::Runtime;
 use tracing::{info, warn};
 
+use crate::capabilities::{interface_version, probe_capabilities};
+
+pub mod appearance;
+pub mod capabilities;
+pub mod desktop_entry;
+pub mod export;
+pub mod mock;
+pub mod portals;
+
+pub use appearance::{appearance, AccentColor, Appearance, AppearanceWatch, ColorScheme};
+pub use capabilities::{PortalCapabilities, PortalKind};
+pub use desktop_entry::{DesktopAction, DesktopEntry};
+pub use export::{
+    export_launcher, pick_export_directory, remove_exported_launcher, ExportedLauncher,
+};
+pub use portals::{DesktopPortals, PortalFuture, Portals};
+
 static RUNTIME: Lazy<Runtime> = Lazy::new(|| Runtime::new().expect("tokio runtime"));
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct LauncherDescriptor {
     /// Desktop file id used by the portal (e.g., xyz.andriishafar.Sitewrap.webapp.<uuid>.desktop)
     pub desktop_id: String,
     pub name: String,
-    pub exec: String,
+    /// Command line as separate arguments (e.g., `["sitewrap", "--shell", "<uuid>"]`).
+    pub exec: Vec<String>,
     pub icon_name: String,
     pub icon_file: Option<PathBuf>,
+    /// SVG version of the icon; preferred over `icon_file` and the sized PNGs when present.
+    pub scalable_icon: Option<PathBuf>,
+    /// Icon files by pixel size, copied into the hicolor theme on manual export.
+    pub icon_set: Vec<(u32, PathBuf)>,
+    pub comment: Option<String>,
+    pub keywords: Vec<String>,
+    pub startup_wm_class: Option<String>,
+    pub mime_types: Vec<String>,
+    pub actions: Vec<DesktopAction>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NotificationRequest {
     pub app_id: String,
     pub title: String,
@@ -38,6 +65,32 @@ pub struct SaveFileRequest {
     pub content: Vec<u8>,
 }
 
+/// Asks for one existing file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickFileRequest {
+    pub title: String,
+    pub accept_label: String,
+    /// `(label, mime types)` choices offered in the dialog; the first is preselected.
+    pub filters: Vec<(String, Vec<String>)>,
+}
+
+/// Asks to keep running without windows and, optionally, to be started at login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackgroundRequest {
+    /// Shown to the user if the desktop asks for confirmation.
+    pub reason: String,
+    pub autostart: bool,
+    /// Command the autostart entry runs; the portal wraps it for Flatpak.
+    pub commandline: Vec<String>,
+}
+
+/// What the Background portal granted; both are `false` when the user declined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackgroundGrant {
+    pub run_in_background: bool,
+    pub autostart: bool,
+}
+
 #[derive(Debug, Error)]
 pub enum PortalError {
     #[error("required portal backend unavailable")]
@@ -51,19 +104,94 @@ fn icon_from_path(path: &PathBuf) -> Result<Icon> {
     Ok(Icon::Bytes(bytes))
 }
 
-fn desktop_entry_from_descriptor(descriptor: &LauncherDescriptor) -> String {
-    format!(
-        "[Desktop Entry]\nName={}\nExec={}\nType=Application\nIcon={}\nCategories=Network;WebBrowser;\n",
-        descriptor.name, descriptor.exec, descriptor.icon_name
-    )
+pub(crate) fn desktop_entry_from_descriptor(descriptor: &LauncherDescriptor) -> String {
+    let mut entry = DesktopEntry::new(descriptor.name.as_str(), descriptor.exec.iter().cloned())
+        .icon(descriptor.icon_name.as_str())
+        .categories(["Network", "WebBrowser"])
+        .keywords(descriptor.keywords.iter().cloned())
+        .mime_types(descriptor.mime_types.iter().cloned());
+    if let Some(comment) = &descriptor.comment {
+        entry = entry.comment(comment.as_str());
+    }
+    if let Some(class) = &descriptor.startup_wm_class {
+        entry = entry.startup_wm_class(class.as_str());
+    }
+    for action in &descriptor.actions {
+        entry = entry.action(action.clone());
+    }
+    entry.render()
 }
 
+// Blocking entry points for worker threads and the CLI. GTK code should go through
+// [`Portals`] so dialogs and D-Bus round-trips don't stall the main loop.
+
 pub fn install_launcher(descriptor: &LauncherDescriptor) -> Result<()> {
+    RUNTIME.block_on(install_launcher_async(descriptor))
+}
+
+pub fn update_launcher(descriptor: &LauncherDescriptor) -> Result<()> {
+    install_launcher(descriptor)
+}
+
+pub fn remove_launcher(desktop_id: &str) -> Result<()> {
+    RUNTIME.block_on(remove_launcher_async(desktop_id))
+}
+
+pub fn send_notification(request: &NotificationRequest) -> Result<()> {
+    RUNTIME.block_on(send_notification_async(request))
+}
+
+pub fn open_uri(uri: &str) -> Result<()> {
+    RUNTIME.block_on(open_uri_async(uri))
+}
+
+pub fn save_file(request: &SaveFileRequest) -> Result<()> {
+    RUNTIME.block_on(save_file_async(request))
+}
+
+pub fn pick_file(request: &PickFileRequest) -> Result<Option<PathBuf>> {
+    RUNTIME.block_on(pick_file_async(request))
+}
+
+pub fn request_background(request: &BackgroundRequest) -> Result<BackgroundGrant> {
+    RUNTIME.block_on(request_background_async(request))
+}
+
+/// Availability and interface version of every portal Sitewrap knows about.
+pub fn capabilities() -> PortalCapabilities {
+    RUNTIME.block_on(probe_capabilities())
+}
+
+/// Whether DynamicLauncher, Notification and OpenURI are all reachable.
+pub fn is_supported() -> bool {
+    RUNTIME.block_on(portals_available())
+}
+
+pub fn is_dynamic_launcher_supported() -> bool {
+    RUNTIME.block_on(portal_available(PortalKind::DynamicLauncher))
+}
+
+pub fn is_open_uri_supported() -> bool {
+    RUNTIME.block_on(portal_available(PortalKind::OpenUri))
+}
+
+pub fn is_file_chooser_supported() -> bool {
+    RUNTIME.block_on(portal_available(PortalKind::FileChooser))
+}
+
+pub fn warn_if_stubbed() {
+    if !is_supported() {
+        warn!(target: "portal", "xdg-desktop-portal not available; host integration is disabled");
+    }
+}
+
+async fn install_launcher_async(descriptor: &LauncherDescriptor) -> Result<()> {
     info!(target: "portal", desktop_id = %descriptor.desktop_id, "install launcher via DynamicLauncher portal");
     let icon = descriptor
-        .icon_file
-        .as_ref()
-        .and_then(|path| match icon_from_path(path) {
+        .scalable_icon
+        .iter()
+        .chain(&descriptor.icon_file)
+        .find_map(|path| match icon_from_path(path) {
             Ok(icon) => Some(icon),
             Err(err) => {
                 warn!(
@@ -79,174 +207,169 @@ pub fn install_launcher(descriptor: &LauncherDescriptor) -> Result<()> {
 
     let desktop_entry = desktop_entry_from_descriptor(descriptor);
 
-    RUNTIME.block_on(async {
-        let proxy = dynamic_launcher::DynamicLauncherProxy::new()
-            .await
-            .context("connect DynamicLauncher portal")?;
-        let options = dynamic_launcher::PrepareInstallOptions::default()
-            .launcher_type(dynamic_launcher::LauncherType::WebApplication);
-        let response = proxy
-            .prepare_install(None, &descriptor.name, icon, options)
-            .await
-            .context("prepare DynamicLauncher install")?
-            .response()
-            .context("read DynamicLauncher prepare response")?;
-        let token = response.token();
-        proxy
-            .install(token, &descriptor.desktop_id, &desktop_entry)
-            .await
-            .context("install desktop entry via DynamicLauncher portal")?;
-        Ok::<_, anyhow::Error>(())
-    })
-}
-
-pub fn update_launcher(descriptor: &LauncherDescriptor) -> Result<()> {
-    install_launcher(descriptor)
+    let proxy = dynamic_launcher::DynamicLauncherProxy::new()
+        .await
+        .context("connect DynamicLauncher portal")?;
+    let options = dynamic_launcher::PrepareInstallOptions::default()
+        .launcher_type(dynamic_launcher::LauncherType::WebApplication);
+    let response = proxy
+        .prepare_install(None, &descriptor.name, icon, options)
+        .await
+        .context("prepare DynamicLauncher install")?
+        .response()
+        .context("read DynamicLauncher prepare response")?;
+    let token = response.token();
+    proxy
+        .install(token, &descriptor.desktop_id, &desktop_entry)
+        .await
+        .context("install desktop entry via DynamicLauncher portal")?;
+    Ok(())
 }
 
-pub fn remove_launcher(desktop_id: &str) -> Result<()> {
+async fn remove_launcher_async(desktop_id: &str) -> Result<()> {
     info!(target: "portal", desktop_id, "remove launcher via DynamicLauncher portal");
-    RUNTIME.block_on(async {
-        let proxy = dynamic_launcher::DynamicLauncherProxy::new()
-            .await
-            .context("connect DynamicLauncher portal")?;
-        proxy
-            .uninstall(desktop_id)
-            .await
-            .context("uninstall desktop entry via DynamicLauncher portal")?;
-        Ok::<_, anyhow::Error>(())
-    })
+    let proxy = dynamic_launcher::DynamicLauncherProxy::new()
+        .await
+        .context("connect DynamicLauncher portal")?;
+    proxy
+        .uninstall(desktop_id)
+        .await
+        .context("uninstall desktop entry via DynamicLauncher portal")?;
+    Ok(())
 }
 
-pub fn send_notification(request: &NotificationRequest) -> Result<()> {
+async fn send_notification_async(request: &NotificationRequest) -> Result<()> {
     info!(target: "portal", app_id = %request.app_id, title = %request.title, "send notification via portal");
-    RUNTIME.block_on(async {
-        let proxy = notification::NotificationProxy::new()
-            .await
-            .context("connect Notification portal")?;
-        let note = notification::Notification::new(&request.title).body(request.body.as_str());
-        let note = if let Some(icon) = &request.icon {
-            note.icon(Icon::with_names([icon.as_str()]))
-        } else {
-            note
-        };
-        proxy
-            .add_notification(&request.app_id, note)
-            .await
-            .context("send notification via portal")?;
-        Ok::<_, anyhow::Error>(())
-    })
+    let proxy = notification::NotificationProxy::new()
+        .await
+        .context("connect Notification portal")?;
+    let note = notification::Notification::new(&request.title).body(request.body.as_str());
+    let note = if let Some(icon) = &request.icon {
+        note.icon(Icon::with_names([icon.as_str()]))
+    } else {
+        note
+    };
+    proxy
+        .add_notification(&request.app_id, note)
+        .await
+        .context("send notification via portal")?;
+    Ok(())
 }
 
-pub fn open_uri(uri: &str) -> Result<()> {
+async fn open_uri_async(uri: &str) -> Result<()> {
     info!(target: "portal", uri, "open uri via portal");
-    RUNTIME.block_on(async {
-        let uri = Url::parse(uri).context("parse URI")?;
-        open_uri::OpenFileRequest::default()
-            .send_uri(&uri)
-            .await
-            .context("send OpenURI request")?
-            .response()
-            .context("read OpenURI response")?;
-        Ok::<_, anyhow::Error>(())
-    })
+    let uri = Url::parse(uri).context("parse URI")?;
+    open_uri::OpenFileRequest::default()
+        .send_uri(&uri)
+        .await
+        .context("send OpenURI request")?
+        .response()
+        .context("read OpenURI response")?;
+    Ok(())
 }
 
-pub fn save_file(request: &SaveFileRequest) -> Result<()> {
+async fn save_file_async(request: &SaveFileRequest) -> Result<()> {
     info!(target: "portal", file = %request.suggested_name, "save file via FileChooser portal");
-    RUNTIME.block_on(async {
-        let mut save_request = SelectedFiles::save_file()
-            .title(request.title.as_str())
-            .accept_label("Save")
-            .modal(true)
-            .current_name(request.suggested_name.as_str());
-
-        if let Some(dir) = &request.default_directory {
-            save_request = save_request.current_folder(dir).context("set current folder")?;
-        }
-
-        let response = save_request
-            .send()
-            .await
-            .context("open SaveFile portal")?
-            .response()
-            .context("read SaveFile response")?;
-
-        let uris = response.uris();
-        let Some(uri) = uris.first() else {
-            return Ok(()); // cancelled
-        };
-
-        if let Ok(path) = uri.to_file_path() {
-            std::fs::write(&path, &request.content)
-                .with_context(|| format!("write selected file {:?}", path))?;
-        } else {
-            warn!(target: "portal", uri = %uri, "SaveFile returned non-file URI");
-        }
-        Ok::<_, anyhow::Error>(())
-    })
-}
+    let mut save_request = SelectedFiles::save_file()
+        .title(request.title.as_str())
+        .accept_label("Save")
+        .modal(true)
+        .current_name(request.suggested_name.as_str());
+
+    if let Some(dir) = &request.default_directory {
+        save_request = save_request
+            .current_folder(dir)
+            .context("set current folder")?;
+    }
 
-async fn file_chooser_portal_available() -> bool {
-    let Ok(connection) = ashpd::zbus::Connection::session().await else {
-        return false;
+    let response = save_request
+        .send()
+        .await
+        .context("open SaveFile portal")?
+        .response();
+    let response = match response {
+        Ok(response) => response,
+        Err(ashpd::Error::Response(ashpd::desktop::ResponseError::Cancelled)) => return Ok(()),
+        Err(err) => return Err(err).context("read SaveFile response"),
     };
 
-    let proxy = match ashpd::zbus::Proxy::new(
-        &connection,
-        "org.freedesktop.portal.Desktop",
-        "/org/freedesktop/portal/desktop",
-        "org.freedesktop.portal.FileChooser",
-    )
-    .await
-    {
-        Ok(proxy) => proxy,
-        Err(_) => return false,
+    let uris = response.uris();
+    let Some(uri) = uris.first() else {
+        return Ok(());
     };
 
-    proxy.get_property::<u32>("version").await.is_ok()
+    if let Ok(path) = uri.to_file_path() {
+        std::fs::write(&path, &request.content)
+            .with_context(|| format!("write selected file {:?}", path))?;
+    } else {
+        warn!(target: "portal", uri = %uri, "SaveFile returned non-file URI");
+    }
+    Ok(())
 }
 
-async fn open_uri_portal_available() -> bool {
-    let Ok(connection) = ashpd::zbus::Connection::session().await else {
-        return false;
-    };
-
-    let proxy = match ashpd::zbus::Proxy::new(
-        &connection,
-        "org.freedesktop.portal.Desktop",
-        "/org/freedesktop/portal/desktop",
-        "org.freedesktop.portal.OpenURI",
-    )
-    .await
-    {
-        Ok(proxy) => proxy,
-        Err(_) => return false,
+async fn pick_file_async(request: &PickFileRequest) -> Result<Option<PathBuf>> {
+    info!(target: "portal", title = %request.title, "pick file via FileChooser portal");
+    let mut open_request = SelectedFiles::open_file()
+        .title(request.title.as_str())
+        .accept_label(request.accept_label.as_str())
+        .modal(true)
+        .multiple(false);
+    for (label, mime_types) in &request.filters {
+        let filter = mime_types
+            .iter()
+            .fold(FileFilter::new(label), |filter, mime| filter.mimetype(mime));
+        open_request = open_request.filter(filter);
+    }
+    let response = open_request
+        .send()
+        .await
+        .context("open FileChooser portal")?
+        .response();
+    let response = match response {
+        Ok(response) => response,
+        Err(ashpd::Error::Response(ashpd::desktop::ResponseError::Cancelled)) => return Ok(None),
+        Err(err) => return Err(err).context("read FileChooser response"),
     };
-
-    proxy.get_property::<u32>("version").await.is_ok()
-}
-
-pub fn is_supported() -> bool {
-    RUNTIME.block_on(async {
-        dynamic_launcher::DynamicLauncherProxy::new().await.is_ok()
-            && notification::NotificationProxy::new().await.is_ok()
-            && open_uri_portal_available().await
-    })
+    Ok(response
+        .uris()
+        .first()
+        .and_then(|uri| uri.to_file_path().ok()))
 }
 
-pub fn is_open_uri_supported() -> bool {
-    RUNTIME.block_on(open_uri_portal_available())
+async fn request_background_async(request: &BackgroundRequest) -> Result<BackgroundGrant> {
+    info!(target: "portal", autostart = request.autostart, commandline = ?request.commandline, "request background via portal");
+    let response = background::Background::request()
+        .reason(request.reason.as_str())
+        .auto_start(request.autostart)
+        .command::<Vec<String>, String>(request.commandline.clone())
+        .dbus_activatable(false)
+        .send()
+        .await
+        .context("send Background request")?
+        .response();
+    match response {
+        Ok(response) => Ok(BackgroundGrant {
+            run_in_background: response.run_in_background(),
+            autostart: response.auto_start(),
+        }),
+        Err(ashpd::Error::Response(ashpd::desktop::ResponseError::Cancelled)) => {
+            Ok(BackgroundGrant::default())
+        }
+        Err(err) => Err(err).context("read Background response"),
+    }
 }
 
-pub fn is_file_chooser_supported() -> bool {
-    RUNTIME.block_on(file_chooser_portal_available())
+async fn portals_available() -> bool {
+    portal_available(PortalKind::DynamicLauncher).await
+        && portal_available(PortalKind::Notification).await
+        && portal_available(PortalKind::OpenUri).await
 }
 
-pub fn warn_if_stubbed() {
-    if !is_supported() {
-        warn!(target: "portal", "xdg-desktop-portal not available; host integration is disabled");
-    }
+async fn portal_available(kind: PortalKind) -> bool {
+    let Ok(connection) = ashpd::zbus::Connection::session().await else {
+        return false;
+    };
+    interface_version(&connection, kind).await.is_some()
 }
 
 #[cfg(test)]
@@ -258,13 +381,35 @@ mod tests {
         let descriptor = LauncherDescriptor {
             desktop_id: "xyz.andriishafar.Sitewrap.webapp.123.desktop".into(),
             name: "Demo App".into(),
-            exec: "sitewrap --shell 123".into(),
+            exec: vec!["sitewrap".into(), "--shell".into(), "123".into()],
             icon_name: "xyz.andriishafar.Sitewrap.webapp.123".into(),
             icon_file: None,
+            ..Default::default()
         };
 
         let entry = desktop_entry_from_descriptor(&descriptor);
         let expected = "[Desktop Entry]\nName=Demo App\nExec=sitewrap --shell 123\nType=Application\nIcon=xyz.andriishafar.Sitewrap.webapp.123\nCategories=Network;WebBrowser;\n";
         assert_eq!(entry, expected);
     }
+
+    #[test]
+    fn desktop_entry_escapes_descriptor_fields() {
+        let descriptor = LauncherDescriptor {
+            desktop_id: "xyz.andriishafar.Sitewrap.webapp.123.desktop".into(),
+            name: "Mail\nExec=evil".into(),
+            exec: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+            icon_name: "xyz.andriishafar.Sitewrap.webapp.123".into(),
+            comment: Some("Web app for https://mail.example".into()),
+            keywords: vec!["mail.example".into()],
+            ..Default::default()
+        };
+
+        let rendered = desktop_entry_from_descriptor(&descriptor);
+        let parsed = DesktopEntry::parse(&rendered).unwrap();
+        assert_eq!(parsed.name, descriptor.name);
+        assert_eq!(parsed.exec, descriptor.exec);
+        assert_eq!(parsed.comment, descriptor.comment);
+        assert_eq!(parsed.keywords, descriptor.keywords);
+        assert_eq!(rendered.matches("\nExec=").count(), 1);
+    }
 }
@@ -0,0 +1,263 @@
+//! In-memory [`Portals`] implementation for unit tests.
+
+use std::{
+    cell::{Cell, RefCell},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Result};
+use tokio::sync::mpsc;
+
+use crate::{
+    desktop_entry_from_descriptor,
+    portals::{PortalFuture, Portals},
+    Appearance, AppearanceWatch, BackgroundGrant, BackgroundRequest, LauncherDescriptor,
+    NotificationRequest, PickFileRequest, PortalCapabilities, SaveFileRequest,
+};
+
+/// A request received by [`MockPortals`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortalCall {
+    InstallLauncher {
+        desktop_id: String,
+        desktop_entry: String,
+    },
+    RemoveLauncher(String),
+    SendNotification(NotificationRequest),
+    OpenUri(String),
+    SaveFile {
+        suggested_name: String,
+        content: Vec<u8>,
+    },
+    PickDirectory(String),
+    PickFile(PickFileRequest),
+    RequestBackground(BackgroundRequest),
+}
+
+/// Records every call and answers immediately. All portals are available (at version 1)
+/// and every request succeeds unless configured otherwise.
+#[derive(Debug)]
+pub struct MockPortals {
+    capabilities: Cell<PortalCapabilities>,
+    failure: RefCell<Option<String>>,
+    directory: RefCell<Option<PathBuf>>,
+    file: RefCell<Option<PathBuf>>,
+    calls: RefCell<Vec<PortalCall>>,
+    appearance: Cell<Appearance>,
+    appearance_watchers: RefCell<Vec<mpsc::UnboundedSender<Appearance>>>,
+}
+
+impl Default for MockPortals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockPortals {
+    pub fn new() -> Self {
+        Self {
+            capabilities: Cell::new(PortalCapabilities::all(1)),
+            failure: RefCell::new(None),
+            directory: RefCell::new(None),
+            file: RefCell::new(None),
+            calls: RefCell::new(Vec::new()),
+            appearance: Cell::new(Appearance::default()),
+            appearance_watchers: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Reports every portal as available or every portal as missing.
+    pub fn set_available(&self, available: bool) {
+        self.set_capabilities(if available {
+            PortalCapabilities::all(1)
+        } else {
+            PortalCapabilities::default()
+        });
+    }
+
+    pub fn set_capabilities(&self, capabilities: PortalCapabilities) {
+        self.capabilities.set(capabilities);
+    }
+
+    /// Makes every subsequent request fail with `message` (still recorded).
+    pub fn fail_with(&self, message: impl Into<String>) {
+        self.failure.replace(Some(message.into()));
+    }
+
+    /// Directory returned by [`Portals::pick_directory`]; `None` acts like a cancelled dialog.
+    pub fn set_directory(&self, directory: Option<PathBuf>) {
+        self.directory.replace(directory);
+    }
+
+    /// File returned by [`Portals::pick_file`]; `None` acts like a cancelled dialog.
+    pub fn set_file(&self, file: Option<PathBuf>) {
+        self.file.replace(file);
+    }
+
+    /// Changes the system appearance and notifies open watches.
+    pub fn set_appearance(&self, appearance: Appearance) {
+        self.appearance.set(appearance);
+        self.appearance_watchers
+            .borrow_mut()
+            .retain(|sender| sender.send(appearance).is_ok());
+    }
+
+    pub fn calls(&self) -> Vec<PortalCall> {
+        self.calls.borrow().clone()
+    }
+
+    fn record(&self, call: PortalCall) -> Result<()> {
+        self.calls.borrow_mut().push(call);
+        match self.failure.borrow().as_ref() {
+            Some(message) => Err(anyhow!("{message}")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn ready<T: 'static>(value: T) -> PortalFuture<'static, T> {
+    Box::pin(async move { value })
+}
+
+impl Portals for MockPortals {
+    fn capabilities(&self) -> PortalFuture<'_, PortalCapabilities> {
+        ready(self.capabilities.get())
+    }
+
+    fn install_launcher(&self, descriptor: &LauncherDescriptor) -> PortalFuture<'_, Result<()>> {
+        ready(self.record(PortalCall::InstallLauncher {
+            desktop_id: descriptor.desktop_id.clone(),
+            desktop_entry: desktop_entry_from_descriptor(descriptor),
+        }))
+    }
+
+    fn remove_launcher(&self, desktop_id: &str) -> PortalFuture<'_, Result<()>> {
+        ready(self.record(PortalCall::RemoveLauncher(desktop_id.to_string())))
+    }
+
+    fn send_notification(&self, request: &NotificationRequest) -> PortalFuture<'_, Result<()>> {
+        ready(self.record(PortalCall::SendNotification(request.clone())))
+    }
+
+    fn open_uri(&self, uri: &str) -> PortalFuture<'_, Result<()>> {
+        ready(self.record(PortalCall::OpenUri(uri.to_string())))
+    }
+
+    fn save_file(&self, request: &SaveFileRequest) -> PortalFuture<'_, Result<()>> {
+        ready(self.record(PortalCall::SaveFile {
+            suggested_name: request.suggested_name.clone(),
+            content: request.content.clone(),
+        }))
+    }
+
+    fn pick_directory(&self, title: &str) -> PortalFuture<'_, Result<Option<PathBuf>>> {
+        let result = self
+            .record(PortalCall::PickDirectory(title.to_string()))
+            .map(|()| self.directory.borrow().clone());
+        ready(result)
+    }
+
+    fn pick_file(&self, request: &PickFileRequest) -> PortalFuture<'_, Result<Option<PathBuf>>> {
+        let result = self
+            .record(PortalCall::PickFile(request.clone()))
+            .map(|()| self.file.borrow().clone());
+        ready(result)
+    }
+
+    fn watch_appearance(&self) -> AppearanceWatch {
+        let (sender, watch) = AppearanceWatch::channel();
+        if sender.send(self.appearance.get()).is_ok() {
+            self.appearance_watchers.borrow_mut().push(sender);
+        }
+        watch
+    }
+
+    /// Grants exactly what was asked for.
+    fn request_background(
+        &self,
+        request: &BackgroundRequest,
+    ) -> PortalFuture<'_, Result<BackgroundGrant>> {
+        let result = self
+            .record(PortalCall::RequestBackground(request.clone()))
+            .map(|()| BackgroundGrant {
+                run_in_background: true,
+                autostart: request.autostart,
+            });
+        ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorScheme, PortalKind};
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn records_calls_and_configured_failures() {
+        let portals = MockPortals::new();
+        block_on(async {
+            assert!(portals.capabilities().await.missing().is_empty());
+            portals.open_uri("https://example.com").await.unwrap();
+            assert_eq!(portals.pick_directory("Export").await.unwrap(), None);
+
+            portals.set_available(false);
+            portals.fail_with("no portal");
+            assert!(!portals.capabilities().await.has(PortalKind::FileChooser));
+            assert!(portals.remove_launcher("demo.desktop").await.is_err());
+        });
+
+        assert_eq!(
+            portals.calls(),
+            vec![
+                PortalCall::OpenUri("https://example.com".into()),
+                PortalCall::PickDirectory("Export".into()),
+                PortalCall::RemoveLauncher("demo.desktop".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn appearance_watch_sees_current_then_changes() {
+        let portals = MockPortals::new();
+        let dark = Appearance {
+            color_scheme: ColorScheme::PreferDark,
+            accent_color: None,
+        };
+        let mut watch = portals.watch_appearance();
+        portals.set_appearance(dark);
+        block_on(async {
+            assert_eq!(watch.next().await, Some(Appearance::default()));
+            assert_eq!(watch.next().await, Some(dark));
+        });
+    }
+
+    #[test]
+    fn install_records_rendered_desktop_entry() {
+        let portals = MockPortals::new();
+        let descriptor = LauncherDescriptor {
+            desktop_id: "demo.desktop".into(),
+            name: "Demo".into(),
+            exec: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+            icon_name: "demo".into(),
+            ..Default::default()
+        };
+        block_on(portals.install_launcher(&descriptor)).unwrap();
+        let calls = portals.calls();
+        let [PortalCall::InstallLauncher {
+            desktop_id,
+            desktop_entry,
+        }] = calls.as_slice()
+        else {
+            panic!("expected one install call");
+        };
+        assert_eq!(desktop_id, "demo.desktop");
+        assert!(desktop_entry.contains("Exec=sitewrap --shell 123\n"));
+    }
+}
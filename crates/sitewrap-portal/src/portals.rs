@@ -0,0 +1,115 @@
+//! Non-blocking portal access for GTK code.
+//!
+//! [`DesktopPortals`] runs each request on the crate's tokio runtime and hands back a
+//! future that only waits for the result, so it can be awaited from
+//! `glib::MainContext::spawn_local` without blocking the main loop.
+
+use std::{future::Future, path::PathBuf, pin::Pin};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    appearance::watch_appearance, capabilities::probe_capabilities,
+    export::pick_export_directory_async, install_launcher_async, open_uri_async, pick_file_async,
+    remove_launcher_async, request_background_async, save_file_async, send_notification_async,
+    AppearanceWatch, BackgroundGrant, BackgroundRequest, LauncherDescriptor, NotificationRequest,
+    PickFileRequest, PortalCapabilities, SaveFileRequest, RUNTIME,
+};
+
+/// Boxed future returned by [`Portals`]; not `Send`, so implementations may use `Rc` state.
+pub type PortalFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Desktop integration used by the app, implemented by [`DesktopPortals`] and, in tests,
+/// by [`MockPortals`](crate::mock::MockPortals).
+pub trait Portals {
+    /// Which portals are reachable; callers enable features one portal at a time.
+    fn capabilities(&self) -> PortalFuture<'_, PortalCapabilities>;
+    fn install_launcher(&self, descriptor: &LauncherDescriptor) -> PortalFuture<'_, Result<()>>;
+    fn remove_launcher(&self, desktop_id: &str) -> PortalFuture<'_, Result<()>>;
+    fn send_notification(&self, request: &NotificationRequest) -> PortalFuture<'_, Result<()>>;
+    fn open_uri(&self, uri: &str) -> PortalFuture<'_, Result<()>>;
+    /// Resolves once the user picked a file (or cancelled) and the content was written.
+    fn save_file(&self, request: &SaveFileRequest) -> PortalFuture<'_, Result<()>>;
+    /// Asks for a directory; `Ok(None)` when the user cancels.
+    fn pick_directory(&self, title: &str) -> PortalFuture<'_, Result<Option<PathBuf>>>;
+    /// Asks for an existing file; `Ok(None)` when the user cancels.
+    fn pick_file(&self, request: &PickFileRequest) -> PortalFuture<'_, Result<Option<PathBuf>>>;
+    fn request_background(
+        &self,
+        request: &BackgroundRequest,
+    ) -> PortalFuture<'_, Result<BackgroundGrant>>;
+    /// Follows the system color scheme and accent; see [`AppearanceWatch`].
+    fn watch_appearance(&self) -> AppearanceWatch;
+}
+
+/// The real xdg-desktop-portal backend.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DesktopPortals;
+
+/// Spawns `task` on the portal runtime and returns a future that waits for it from any executor.
+fn on_runtime<T, F>(task: F) -> PortalFuture<'static, Result<T>>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T>> + Send + 'static,
+{
+    let handle = RUNTIME.spawn(task);
+    Box::pin(async move {
+        handle
+            .await
+            .map_err(|err| anyhow!("portal task failed: {err}"))?
+    })
+}
+
+impl Portals for DesktopPortals {
+    fn capabilities(&self) -> PortalFuture<'_, PortalCapabilities> {
+        let handle = RUNTIME.spawn(probe_capabilities());
+        Box::pin(async move { handle.await.unwrap_or_default() })
+    }
+
+    fn install_launcher(&self, descriptor: &LauncherDescriptor) -> PortalFuture<'_, Result<()>> {
+        let descriptor = descriptor.clone();
+        on_runtime(async move { install_launcher_async(&descriptor).await })
+    }
+
+    fn remove_launcher(&self, desktop_id: &str) -> PortalFuture<'_, Result<()>> {
+        let desktop_id = desktop_id.to_string();
+        on_runtime(async move { remove_launcher_async(&desktop_id).await })
+    }
+
+    fn send_notification(&self, request: &NotificationRequest) -> PortalFuture<'_, Result<()>> {
+        let request = request.clone();
+        on_runtime(async move { send_notification_async(&request).await })
+    }
+
+    fn open_uri(&self, uri: &str) -> PortalFuture<'_, Result<()>> {
+        let uri = uri.to_string();
+        on_runtime(async move { open_uri_async(&uri).await })
+    }
+
+    fn save_file(&self, request: &SaveFileRequest) -> PortalFuture<'_, Result<()>> {
+        let request = request.clone();
+        on_runtime(async move { save_file_async(&request).await })
+    }
+
+    fn pick_directory(&self, title: &str) -> PortalFuture<'_, Result<Option<PathBuf>>> {
+        let title = title.to_string();
+        on_runtime(async move { pick_export_directory_async(&title).await })
+    }
+
+    fn pick_file(&self, request: &PickFileRequest) -> PortalFuture<'_, Result<Option<PathBuf>>> {
+        let request = request.clone();
+        on_runtime(async move { pick_file_async(&request).await })
+    }
+
+    fn request_background(
+        &self,
+        request: &BackgroundRequest,
+    ) -> PortalFuture<'_, Result<BackgroundGrant>> {
+        let request = request.clone();
+        on_runtime(async move { request_background_async(&request).await })
+    }
+
+    fn watch_appearance(&self) -> AppearanceWatch {
+        watch_appearance()
+    }
+}
@@ -0,0 +1,534 @@
+//! A private D-Bus session bus with an in-process fake `org.freedesktop.portal.Desktop`.
+//!
+//! The bus is started once per test binary and `DBUS_SESSION_BUS_ADDRESS` is pointed at it
+//! before ashpd opens its (process-wide, cached) session connection. The fake implements
+//! just enough of DynamicLauncher, Notification, OpenURI, FileChooser, Background and
+//! Settings to answer the
+//! requests `sitewrap-portal` makes, and records every call.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    process::{ChildStdin, Command, Stdio},
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+};
+
+use tokio::runtime::Runtime;
+use zbus::{
+    fdo,
+    message::Header,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+    Connection,
+};
+
+const DESKTOP_PATH: &str = "/org/freedesktop/portal/desktop";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+
+const BUS_CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:dir=DIR</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#;
+
+/// A portal method call as seen by the fake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortalCall {
+    PrepareInstall {
+        name: String,
+        /// Icon bytes as sent; empty when the launcher has no icon.
+        icon: Vec<u8>,
+        token: String,
+    },
+    Install {
+        token: String,
+        desktop_file_id: String,
+        desktop_entry: String,
+    },
+    Uninstall {
+        desktop_file_id: String,
+    },
+    AddNotification {
+        id: String,
+        title: Option<String>,
+        body: Option<String>,
+        has_icon: bool,
+    },
+    OpenUri {
+        uri: String,
+    },
+    OpenFile {
+        title: String,
+        directory: bool,
+    },
+    SaveFile {
+        title: String,
+        current_name: Option<String>,
+    },
+    RequestBackground {
+        reason: Option<String>,
+        autostart: bool,
+        commandline: Vec<String>,
+    },
+}
+
+#[derive(Default)]
+struct State {
+    calls: Vec<PortalCall>,
+    issued_tokens: u32,
+    /// Answer for FileChooser dialogs; `None` responds as if the user cancelled.
+    chosen_uri: Option<String>,
+    /// `org.freedesktop.appearance` `color-scheme`: 0 no preference, 1 dark, 2 light.
+    color_scheme: u32,
+}
+
+type Shared = Arc<Mutex<State>>;
+
+fn lock(state: &Shared) -> MutexGuard<'_, State> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub struct FakePortal {
+    state: Shared,
+    /// Serializes tests: they share one bus and one recorder.
+    test_lock: Mutex<()>,
+    runtime: Runtime,
+    connection: Connection,
+    /// Closing this pipe (on process exit) makes the wrapper shell stop the bus daemon.
+    _daemon_stdin: ChildStdin,
+}
+
+/// A test's exclusive handle on the fake portal; recorded calls start out empty.
+pub struct Session<'a> {
+    portal: &'a FakePortal,
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl Session<'_> {
+    pub fn calls(&self) -> Vec<PortalCall> {
+        lock(&self.portal.state).calls.clone()
+    }
+
+    /// What the next FileChooser dialog "picks"; `None` cancels it.
+    pub fn choose(&self, uri: Option<String>) {
+        lock(&self.portal.state).chosen_uri = uri;
+    }
+
+    /// Changes the desktop color scheme and emits `SettingChanged`, like a theme switch.
+    /// Safe to call from inside another runtime: the signal goes out from its own thread.
+    pub fn set_color_scheme(&self, color_scheme: u32) {
+        lock(&self.portal.state).color_scheme = color_scheme;
+        let portal = self.portal;
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    portal.runtime.block_on(portal.connection.emit_signal(
+                        None::<&str>,
+                        DESKTOP_PATH,
+                        "org.freedesktop.portal.Settings",
+                        "SettingChanged",
+                        &(
+                            APPEARANCE_NAMESPACE,
+                            "color-scheme",
+                            Value::from(color_scheme),
+                        ),
+                    ))
+                })
+                .join()
+                .expect("signal thread")
+                .expect("emit SettingChanged");
+        });
+    }
+}
+
+/// Starts the bus and fake portal on first use. Returns `None` (and the caller should
+/// skip) when `dbus-daemon` is not installed.
+pub fn session() -> Option<Session<'static>> {
+    static PORTAL: OnceLock<Option<FakePortal>> = OnceLock::new();
+    let portal = PORTAL
+        .get_or_init(|| match FakePortal::start() {
+            Ok(portal) => Some(portal),
+            Err(err) => {
+                eprintln!("skipping portal harness tests: {err:#}");
+                None
+            }
+        })
+        .as_ref()?;
+    let guard = portal
+        .test_lock
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    {
+        let mut state = lock(&portal.state);
+        state.calls.clear();
+        state.chosen_uri = None;
+        state.color_scheme = 0;
+    }
+    Some(Session {
+        portal,
+        _guard: guard,
+    })
+}
+
+impl FakePortal {
+    fn start() -> anyhow::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("sitewrap-fake-bus-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let config = dir.join("session.conf");
+        std::fs::write(
+            &config,
+            BUS_CONFIG.replace("DIR", &dir.display().to_string()),
+        )?;
+
+        let mut daemon = Command::new("sh")
+            .arg("-c")
+            .arg(r#"dbus-daemon --config-file="$1" --nofork --print-address=1 & read _; kill $!"#)
+            .arg("sh")
+            .arg(&config)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().expect("piped stdout")).read_line(&mut address)?;
+        let address = address.trim().to_string();
+        anyhow::ensure!(!address.is_empty(), "dbus-daemon did not start");
+        let daemon_stdin = daemon.stdin.take().expect("piped stdin");
+
+        // Must happen before ashpd's first connection, which it caches for the process.
+        std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+
+        let state = Shared::default();
+        let runtime = Runtime::new()?;
+        let connection = runtime.block_on(async {
+            zbus::connection::Builder::address(address.as_str())?
+                .name("org.freedesktop.portal.Desktop")?
+                .serve_at(DESKTOP_PATH, DynamicLauncher(state.clone()))?
+                .serve_at(DESKTOP_PATH, Notification(state.clone()))?
+                .serve_at(DESKTOP_PATH, OpenUri(state.clone()))?
+                .serve_at(DESKTOP_PATH, FileChooser(state.clone()))?
+                .serve_at(DESKTOP_PATH, Background(state.clone()))?
+                .serve_at(DESKTOP_PATH, Settings(state.clone()))?
+                .build()
+                .await
+        })?;
+
+        Ok(Self {
+            state,
+            test_lock: Mutex::new(()),
+            runtime,
+            connection,
+            _daemon_stdin: daemon_stdin,
+        })
+    }
+}
+
+fn string_option(options: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    options
+        .get(key)
+        .and_then(|value| value.downcast_ref::<&str>().ok())
+        .map(str::to_string)
+}
+
+/// Answers a portal request the way xdg-desktop-portal does: by emitting
+/// `Request.Response` on the handle derived from the caller and its `handle_token`.
+async fn respond(
+    connection: &Connection,
+    header: &Header<'_>,
+    options: &HashMap<String, OwnedValue>,
+    response: u32,
+    results: HashMap<&str, Value<'_>>,
+) -> fdo::Result<OwnedObjectPath> {
+    let sender = header
+        .sender()
+        .ok_or_else(|| fdo::Error::Failed("request without sender".into()))?;
+    let token = string_option(options, "handle_token")
+        .ok_or_else(|| fdo::Error::InvalidArgs("missing handle_token".into()))?;
+    let path = format!(
+        "{DESKTOP_PATH}/request/{}/{token}",
+        sender.trim_start_matches(':').replace('.', "_")
+    );
+    let path =
+        OwnedObjectPath::try_from(path).map_err(|err| fdo::Error::Failed(err.to_string()))?;
+    connection
+        .emit_signal(
+            Some(sender.as_str()),
+            &path,
+            REQUEST_INTERFACE,
+            "Response",
+            &(response, results),
+        )
+        .await?;
+    Ok(path)
+}
+
+struct DynamicLauncher(Shared);
+
+/// Unpacks a serialized `GIcon` of the `("bytes", <ay>)` form.
+fn icon_bytes(icon: OwnedValue) -> Vec<u8> {
+    let Value::Structure(icon) = &*icon else {
+        return Vec::new();
+    };
+    match icon.fields() {
+        [_, Value::Value(data)] => match &**data {
+            Value::Array(bytes) => bytes
+                .iter()
+                .filter_map(|byte| u8::try_from(byte).ok())
+                .collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+#[zbus::interface(name = "org.freedesktop.portal.DynamicLauncher")]
+impl DynamicLauncher {
+    async fn prepare_install(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        _parent_window: &str,
+        name: &str,
+        icon: OwnedValue,
+        options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let token = {
+            let mut state = lock(&self.0);
+            state.issued_tokens += 1;
+            let token = format!("install-token-{}", state.issued_tokens);
+            state.calls.push(PortalCall::PrepareInstall {
+                name: name.to_string(),
+                icon: icon_bytes(icon),
+                token: token.clone(),
+            });
+            token
+        };
+        let results = HashMap::from([
+            ("name", Value::from(name)),
+            ("icon", Value::from("")),
+            ("token", Value::from(token.as_str())),
+        ]);
+        respond(connection, &header, &options, 0, results).await
+    }
+
+    async fn install(
+        &self,
+        token: &str,
+        desktop_file_id: &str,
+        desktop_entry: &str,
+        _options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<()> {
+        let mut state = lock(&self.0);
+        let issued = (1..=state.issued_tokens).any(|n| token == format!("install-token-{n}"));
+        state.calls.push(PortalCall::Install {
+            token: token.to_string(),
+            desktop_file_id: desktop_file_id.to_string(),
+            desktop_entry: desktop_entry.to_string(),
+        });
+        if issued {
+            Ok(())
+        } else {
+            Err(fdo::Error::InvalidArgs(format!("unknown token {token}")))
+        }
+    }
+
+    async fn uninstall(
+        &self,
+        desktop_file_id: &str,
+        _options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<()> {
+        lock(&self.0).calls.push(PortalCall::Uninstall {
+            desktop_file_id: desktop_file_id.to_string(),
+        });
+        Ok(())
+    }
+
+    #[zbus(property, name = "version")]
+    fn version(&self) -> u32 {
+        1
+    }
+}
+
+struct Notification(Shared);
+
+#[zbus::interface(name = "org.freedesktop.portal.Notification")]
+impl Notification {
+    async fn add_notification(
+        &self,
+        id: &str,
+        notification: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<()> {
+        lock(&self.0).calls.push(PortalCall::AddNotification {
+            id: id.to_string(),
+            title: string_option(&notification, "title"),
+            body: string_option(&notification, "body"),
+            has_icon: notification.contains_key("icon"),
+        });
+        Ok(())
+    }
+
+    async fn remove_notification(&self, _id: &str) -> fdo::Result<()> {
+        Ok(())
+    }
+
+    #[zbus(property, name = "version")]
+    fn version(&self) -> u32 {
+        2
+    }
+}
+
+struct OpenUri(Shared);
+
+#[zbus::interface(name = "org.freedesktop.portal.OpenURI")]
+impl OpenUri {
+    #[zbus(name = "OpenURI")]
+    async fn open_uri(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        _parent_window: &str,
+        uri: &str,
+        options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        lock(&self.0).calls.push(PortalCall::OpenUri {
+            uri: uri.to_string(),
+        });
+        respond(connection, &header, &options, 0, HashMap::new()).await
+    }
+
+    #[zbus(property, name = "version")]
+    fn version(&self) -> u32 {
+        4
+    }
+}
+
+struct FileChooser(Shared);
+
+impl FileChooser {
+    async fn answer(
+        &self,
+        connection: &Connection,
+        header: &Header<'_>,
+        options: &HashMap<String, OwnedValue>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let chosen = lock(&self.0).chosen_uri.clone();
+        match chosen {
+            Some(uri) => {
+                let results = HashMap::from([("uris", Value::from(vec![uri]))]);
+                respond(connection, header, options, 0, results).await
+            }
+            None => respond(connection, header, options, 1, HashMap::new()).await,
+        }
+    }
+}
+
+#[zbus::interface(name = "org.freedesktop.portal.FileChooser")]
+impl FileChooser {
+    async fn open_file(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        _parent_window: &str,
+        title: &str,
+        options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let directory = options
+            .get("directory")
+            .and_then(|value| value.downcast_ref::<bool>().ok())
+            .unwrap_or(false);
+        lock(&self.0).calls.push(PortalCall::OpenFile {
+            title: title.to_string(),
+            directory,
+        });
+        self.answer(connection, &header, &options).await
+    }
+
+    async fn save_file(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        _parent_window: &str,
+        title: &str,
+        options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        lock(&self.0).calls.push(PortalCall::SaveFile {
+            title: title.to_string(),
+            current_name: string_option(&options, "current_name"),
+        });
+        self.answer(connection, &header, &options).await
+    }
+
+    #[zbus(property, name = "version")]
+    fn version(&self) -> u32 {
+        4
+    }
+}
+
+/// Grants whatever is requested, like a desktop that doesn't prompt.
+struct Background(Shared);
+
+#[zbus::interface(name = "org.freedesktop.portal.Background")]
+impl Background {
+    async fn request_background(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        _parent_window: &str,
+        options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let autostart = options
+            .get("autostart")
+            .and_then(|value| value.downcast_ref::<bool>().ok())
+            .unwrap_or(false);
+        let commandline = options
+            .get("commandline")
+            .and_then(|value| Vec::<String>::try_from(value.try_clone().ok()?).ok())
+            .unwrap_or_default();
+        lock(&self.0).calls.push(PortalCall::RequestBackground {
+            reason: string_option(&options, "reason"),
+            autostart,
+            commandline,
+        });
+        let results = HashMap::from([
+            ("background", Value::from(true)),
+            ("autostart", Value::from(autostart)),
+        ]);
+        respond(connection, &header, &options, 0, results).await
+    }
+
+    #[zbus(property, name = "version")]
+    fn version(&self) -> u32 {
+        1
+    }
+}
+
+/// Only `org.freedesktop.appearance` `color-scheme` is set; other keys are unknown,
+/// like on a desktop without an accent color.
+struct Settings(Shared);
+
+#[zbus::interface(name = "org.freedesktop.portal.Settings")]
+impl Settings {
+    async fn read(&self, namespace: &str, key: &str) -> fdo::Result<OwnedValue> {
+        if namespace == APPEARANCE_NAMESPACE && key == "color-scheme" {
+            Ok(OwnedValue::from(lock(&self.0).color_scheme))
+        } else {
+            Err(fdo::Error::Failed(format!(
+                "Requested setting {namespace}.{key} not found"
+            )))
+        }
+    }
+
+    #[zbus(property, name = "version")]
+    fn version(&self) -> u32 {
+        1
+    }
+}
@@ -0,0 +1,319 @@
+//! End-to-end checks of the portal calls against a fake xdg-desktop-portal on a private bus.
+
+mod fake_portal;
+
+use fake_portal::PortalCall;
+use sitewrap_portal::{
+    mock::{self, MockPortals},
+    Appearance, BackgroundGrant, BackgroundRequest, ColorScheme, DesktopPortals,
+    LauncherDescriptor, NotificationRequest, PickFileRequest, PortalCapabilities, Portals,
+    SaveFileRequest,
+};
+
+/// Acquires the fake portal or skips the test when no `dbus-daemon` is installed.
+macro_rules! session {
+    () => {
+        match fake_portal::session() {
+            Some(session) => session,
+            None => return,
+        }
+    };
+}
+
+fn descriptor() -> LauncherDescriptor {
+    LauncherDescriptor {
+        desktop_id: "xyz.andriishafar.Sitewrap.webapp.123.desktop".into(),
+        name: "Demo".into(),
+        exec: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+        icon_name: "xyz.andriishafar.Sitewrap.webapp.123".into(),
+        ..Default::default()
+    }
+}
+
+/// The entry `install_launcher` should send, rendered by the same code path via the mock.
+fn rendered_entry(descriptor: &LauncherDescriptor) -> String {
+    let portals = MockPortals::new();
+    block_on(portals.install_launcher(descriptor)).unwrap();
+    match portals.calls().as_slice() {
+        [mock::PortalCall::InstallLauncher { desktop_entry, .. }] => desktop_entry.clone(),
+        calls => panic!("unexpected mock calls {calls:?}"),
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("sitewrap-harness-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn install_uses_prepared_token_and_rendered_entry() {
+    let session = session!();
+    let descriptor = descriptor();
+    sitewrap_portal::install_launcher(&descriptor).unwrap();
+
+    let calls = session.calls();
+    let [PortalCall::PrepareInstall { name, token, .. }, PortalCall::Install {
+        token: install_token,
+        desktop_file_id,
+        desktop_entry,
+    }] = calls.as_slice()
+    else {
+        panic!("unexpected calls {calls:?}");
+    };
+    assert_eq!(name, "Demo");
+    assert_eq!(install_token, token);
+    assert_eq!(desktop_file_id, &descriptor.desktop_id);
+    assert_eq!(desktop_entry, &rendered_entry(&descriptor));
+}
+
+#[test]
+fn install_prefers_the_scalable_icon() {
+    let session = session!();
+    let dir = temp_dir("scalable-icon");
+    let png = dir.join("icon-128x128.png");
+    let svg = dir.join("icon.svg");
+    std::fs::write(&png, b"png bytes").unwrap();
+    std::fs::write(&svg, b"<svg/>").unwrap();
+
+    let mut descriptor = descriptor();
+    descriptor.icon_file = Some(png);
+    sitewrap_portal::install_launcher(&descriptor).unwrap();
+    descriptor.scalable_icon = Some(svg);
+    sitewrap_portal::install_launcher(&descriptor).unwrap();
+
+    let icons: Vec<_> = session
+        .calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            PortalCall::PrepareInstall { icon, .. } => Some(icon),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(icons, [b"png bytes".to_vec(), b"<svg/>".to_vec()]);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn remove_uninstalls_desktop_id() {
+    let session = session!();
+    sitewrap_portal::remove_launcher("xyz.andriishafar.Sitewrap.webapp.123.desktop").unwrap();
+    assert_eq!(
+        session.calls(),
+        vec![PortalCall::Uninstall {
+            desktop_file_id: "xyz.andriishafar.Sitewrap.webapp.123.desktop".into()
+        }]
+    );
+}
+
+#[test]
+fn notification_carries_title_body_and_icon() {
+    let session = session!();
+    sitewrap_portal::send_notification(&NotificationRequest {
+        app_id: "demo-notification".into(),
+        title: "Hello".into(),
+        body: "From the harness".into(),
+        icon: Some("xyz.andriishafar.Sitewrap".into()),
+    })
+    .unwrap();
+    assert_eq!(
+        session.calls(),
+        vec![PortalCall::AddNotification {
+            id: "demo-notification".into(),
+            title: Some("Hello".into()),
+            body: Some("From the harness".into()),
+            has_icon: true,
+        }]
+    );
+}
+
+#[test]
+fn open_uri_forwards_uri() {
+    let session = session!();
+    sitewrap_portal::open_uri("https://example.com/path?q=1").unwrap();
+    assert_eq!(
+        session.calls(),
+        vec![PortalCall::OpenUri {
+            uri: "https://example.com/path?q=1".into()
+        }]
+    );
+}
+
+#[test]
+fn save_file_writes_selected_file() {
+    let session = session!();
+    let target = temp_dir("save").join("notes.txt");
+    let _ = std::fs::remove_file(&target);
+    let request = SaveFileRequest {
+        title: "Save notes".into(),
+        suggested_name: "notes.txt".into(),
+        default_directory: None,
+        content: b"hello".to_vec(),
+    };
+
+    session.choose(None);
+    sitewrap_portal::save_file(&request).unwrap();
+    assert!(!target.exists(), "cancelled dialog must not write");
+
+    session.choose(Some(format!("file://{}", target.display())));
+    sitewrap_portal::save_file(&request).unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), b"hello");
+
+    let save_call = PortalCall::SaveFile {
+        title: "Save notes".into(),
+        current_name: Some("notes.txt".into()),
+    };
+    assert_eq!(session.calls(), vec![save_call.clone(), save_call]);
+}
+
+#[test]
+fn pick_export_directory_returns_choice() {
+    let session = session!();
+    let dir = temp_dir("pick");
+
+    assert_eq!(
+        sitewrap_portal::pick_export_directory("Export").unwrap(),
+        None
+    );
+    session.choose(Some(format!("file://{}", dir.display())));
+    assert_eq!(
+        sitewrap_portal::pick_export_directory("Export").unwrap(),
+        Some(dir)
+    );
+
+    let pick_call = PortalCall::OpenFile {
+        title: "Export".into(),
+        directory: true,
+    };
+    assert_eq!(session.calls(), vec![pick_call.clone(), pick_call]);
+}
+
+#[test]
+fn pick_file_returns_choice() {
+    let session = session!();
+    let file = temp_dir("pick-file").join("icon.svg");
+    let request = PickFileRequest {
+        title: "Choose Icon".into(),
+        accept_label: "Use Icon".into(),
+        filters: vec![(
+            "Images".into(),
+            vec!["image/png".into(), "image/svg+xml".into()],
+        )],
+    };
+
+    assert_eq!(sitewrap_portal::pick_file(&request).unwrap(), None);
+    session.choose(Some(format!("file://{}", file.display())));
+    assert_eq!(sitewrap_portal::pick_file(&request).unwrap(), Some(file));
+
+    let pick_call = PortalCall::OpenFile {
+        title: "Choose Icon".into(),
+        directory: false,
+    };
+    assert_eq!(session.calls(), vec![pick_call.clone(), pick_call]);
+}
+
+#[test]
+fn background_request_carries_autostart_commandline() {
+    let session = session!();
+    let grant = sitewrap_portal::request_background(&BackgroundRequest {
+        reason: "Deliver notifications".into(),
+        autostart: true,
+        commandline: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+    })
+    .unwrap();
+    assert_eq!(
+        grant,
+        BackgroundGrant {
+            run_in_background: true,
+            autostart: true,
+        }
+    );
+    assert_eq!(
+        session.calls(),
+        vec![PortalCall::RequestBackground {
+            reason: Some("Deliver notifications".into()),
+            autostart: true,
+            commandline: vec!["sitewrap".into(), "--shell".into(), "123".into()],
+        }]
+    );
+}
+
+#[test]
+fn appearance_watch_follows_setting_changes() {
+    let session = session!();
+    let dark = Appearance {
+        color_scheme: ColorScheme::PreferDark,
+        accent_color: None,
+    };
+    session.set_color_scheme(1);
+    assert_eq!(sitewrap_portal::appearance().unwrap(), dark);
+
+    let mut watch = DesktopPortals.watch_appearance();
+    block_on(async {
+        assert_eq!(watch.next().await, Some(dark));
+        session.set_color_scheme(2);
+        assert_eq!(
+            watch.next().await.map(|appearance| appearance.color_scheme),
+            Some(ColorScheme::PreferLight)
+        );
+    });
+}
+
+#[test]
+fn probes_detect_fake_portals() {
+    let _session = session!();
+    assert!(sitewrap_portal::is_supported());
+    assert!(sitewrap_portal::is_dynamic_launcher_supported());
+    assert!(sitewrap_portal::is_open_uri_supported());
+    assert!(sitewrap_portal::is_file_chooser_supported());
+
+    // Versions come from the fake's `version` properties; unserved interfaces are missing.
+    assert_eq!(
+        sitewrap_portal::capabilities(),
+        PortalCapabilities {
+            dynamic_launcher: Some(1),
+            notification: Some(2),
+            open_uri: Some(4),
+            file_chooser: Some(4),
+            background: Some(1),
+            settings: Some(1),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn desktop_portals_trait_reaches_fake() {
+    let session = session!();
+    let portals = DesktopPortals;
+    block_on(async {
+        assert!(portals.capabilities().await.open_uri.is_some());
+        portals.install_launcher(&descriptor()).await.unwrap();
+        portals
+            .remove_launcher("xyz.andriishafar.Sitewrap.webapp.123.desktop")
+            .await
+            .unwrap();
+        portals.open_uri("https://example.com").await.unwrap();
+    });
+
+    let calls = session.calls();
+    assert!(
+        matches!(
+            calls.as_slice(),
+            [
+                PortalCall::PrepareInstall { .. },
+                PortalCall::Install { .. },
+                PortalCall::Uninstall { .. },
+                PortalCall::OpenUri { .. },
+            ]
+        ),
+        "unexpected calls {calls:?}"
+    );
+}